@@ -1,7 +1,7 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use preference_splitting::graph::dijkstra::{find_path, Dijkstra};
 use preference_splitting::graphml::read_graphml;
-use preference_splitting::EDGE_COST_DIMENSION;
+use preference_splitting::helpers::equal_weights;
 
 pub fn dijkstra_benchmark(c: &mut Criterion) {
     let graph_data = read_graphml("resources/dijkstra_bench.graphml")
@@ -11,10 +11,16 @@ pub fn dijkstra_benchmark(c: &mut Criterion) {
 
     let source = 3;
     let target = 4501;
-    let pref = [1.0 / EDGE_COST_DIMENSION as f64; EDGE_COST_DIMENSION];
+    let pref = equal_weights(graph_data.graph.dim());
 
     c.bench_function("dijkstra", |b| {
-        b.iter(|| find_path(&mut dijkstra, &[black_box(source), black_box(target)], pref))
+        b.iter(|| {
+            find_path(
+                &mut dijkstra,
+                &[black_box(source), black_box(target)],
+                &pref,
+            )
+        })
     });
 }
 
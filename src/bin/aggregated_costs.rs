@@ -4,7 +4,6 @@ use preference_splitting::graph::{
 };
 use preference_splitting::{
     helpers::costs_by_alpha,
-    EDGE_COST_DIMENSION
 };
 use rand::thread_rng;
 
@@ -59,15 +58,16 @@ fn main() -> MyResult<()> {
     println!("reading trajectories {}", results.trajectory_file);
     let mut trajectories = read_trajectories(&results.trajectory_file)?;
 
-    trajectories.iter_mut().for_each(|t| {
-        t.filter_out_self_loops(&graph, &edge_lookup);
-    });
+    for t in trajectories.iter_mut() {
+        t.filter_out_self_loops(&graph, &edge_lookup)?;
+    }
 
     println!("checking trajectory consistency");
-    if trajectories
+    let valid = trajectories
         .par_iter()
-        .all(|t| check_trajectory(&t, &graph, &edge_lookup))
-    {
+        .map(|t| check_trajectory(&t, &graph, &edge_lookup))
+        .collect::<Result<Vec<_>, _>>()?;
+    if valid.into_iter().all(|v| v) {
         println!("all {} trajectories seem valid :-)", trajectories.len());
     } else {
         println!("There are invalid trajectories :-(");
@@ -89,6 +89,8 @@ fn main() -> MyResult<()> {
     let mut paths = trajectories
         .into_iter()
         .map(|t| t.to_path(&graph, &edge_lookup))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
         .zip(results.results.iter_mut())
         .collect::<Vec<_>>();
 
@@ -105,10 +107,10 @@ fn main() -> MyResult<()> {
                 for (p, s) in chunk {
                     let ids = [*p.nodes.first().unwrap(), *p.nodes.last().unwrap()];
                     //travel time only
-                    let mut tt_preference = [0.0; EDGE_COST_DIMENSION];
+                    let mut tt_preference = vec![0.0; graph.dim()];
                     tt_preference[0] = 1.0;
                     let tt_path = graph
-                            .find_shortest_path(&mut d, 0, &ids, tt_preference)
+                            .find_shortest_path(&mut d, 0, &ids, &tt_preference)
                             .expect("there must be a path");
                     let aggregated_tt_costs = costs_by_alpha(&tt_path.total_dimension_costs, &tt_preference);
                     let aggregated_real_costs = costs_by_alpha(&s.trajectory_cost, &tt_preference);
@@ -121,9 +123,9 @@ fn main() -> MyResult<()> {
                     let mut random_preferences = Vec::new();
                     let mut random_costs = Vec::new();
                     for _ in 0..nr_of_random_preferences {
-                        let rand_pref = randomized_preference(&mut rng);
+                        let rand_pref = randomized_preference(&mut rng, graph.dim());
                         let alpha_path = graph
-                            .find_shortest_path(&mut d, 0, &ids, rand_pref)
+                            .find_shortest_path(&mut d, 0, &ids, &rand_pref)
                             .expect("there must be a path");
                         let aggregated_random_costs = costs_by_alpha(&alpha_path.total_dimension_costs, &rand_pref);
                         let aggregated_costs = costs_by_alpha(&s.trajectory_cost, &rand_pref);
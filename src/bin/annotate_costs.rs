@@ -0,0 +1,91 @@
+use preference_splitting::costs::{annotate_costs, missing_costs, read_cost_file};
+use preference_splitting::export::{write_graphml, write_minimal_graph_file};
+use preference_splitting::graph::parse_minimal_graph_file;
+use preference_splitting::graphml::read_graphml;
+use preference_splitting::MyResult;
+
+use std::collections::HashMap;
+
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// Base graph file to annotate
+    graph_file: String,
+    /// File to write the cost annotated graph to
+    out_file: String,
+    /// Cost files (*.costs.json) with per-edge costs keyed by edge name
+    #[structopt(required = true)]
+    cost_files: Vec<String>,
+    /// Base graph should be read as graphml
+    #[structopt(long = "graphml")]
+    graphml_format: bool,
+    /// Write the annotated graph in minimal fmi format instead of graphml
+    #[structopt(long = "fmi")]
+    fmi_output: bool,
+    /// Normalize each metric to a mean of 1
+    #[structopt(short, long)]
+    normalize: bool,
+    /// Json file to write the names of edges missing from each cost file to
+    #[structopt(long)]
+    missing_report: Option<String>,
+    /// Costs for edges missing from a cost file. Without it, missing edges
+    /// are an error
+    #[structopt(long)]
+    fill: Option<f64>,
+    /// Drop all shortcuts of graphs that were not contracted customizably,
+    /// instead of failing. The annotated graph has to be contracted again
+    #[structopt(long)]
+    drop_shortcuts: bool,
+}
+
+fn main() -> MyResult<()> {
+    let opts = Opts::from_args();
+
+    let mut graph_data = if opts.graphml_format {
+        read_graphml(&opts.graph_file)?
+    } else {
+        parse_minimal_graph_file(&opts.graph_file)?
+    };
+
+    let mut cost_files = Vec::new();
+    for file in &opts.cost_files {
+        println!("reading cost file {}", file);
+        cost_files.push(read_cost_file(file)?);
+    }
+
+    let missing = missing_costs(&graph_data, &cost_files);
+    for (file, m) in opts.cost_files.iter().zip(&missing) {
+        if !m.edges.is_empty() {
+            println!("{} edges are missing from {}", m.edges.len(), file);
+        }
+    }
+
+    if let Some(report_file) = opts.missing_report {
+        let report: HashMap<_, _> = opts
+            .cost_files
+            .iter()
+            .zip(&missing)
+            .map(|(file, m)| (file, &m.edges))
+            .collect();
+        let report_file = std::fs::File::create(report_file)?;
+        serde_json::to_writer_pretty(std::io::BufWriter::new(report_file), &report)?;
+    }
+
+    annotate_costs(
+        &mut graph_data,
+        &cost_files,
+        opts.normalize,
+        opts.fill,
+        opts.drop_shortcuts,
+    )?;
+
+    println!("writing cost annotated graph to {}", opts.out_file);
+    if opts.fmi_output {
+        write_minimal_graph_file(&opts.out_file, &graph_data)?;
+    } else {
+        write_graphml(&opts.out_file, &graph_data)?;
+    }
+
+    Ok(())
+}
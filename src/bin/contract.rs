@@ -0,0 +1,59 @@
+use preference_splitting::export::write_minimal_graph_file;
+use preference_splitting::graph::{
+    ch::{contract, contract_customizable, name_shortcuts},
+    parse_minimal_graph_file,
+};
+use preference_splitting::graphml::{read_graphml, GraphData};
+use preference_splitting::lp::LpProcess;
+use preference_splitting::MyResult;
+
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// Uncontracted graph file to use
+    graph_file: String,
+    /// File to write the contracted graph to (minimal fmi format)
+    out_file: String,
+    /// File should be read as graphml
+    #[structopt(long = "graphml")]
+    graphml_format: bool,
+    /// Add shortcuts without witness searches, so the costs can be replaced
+    /// later
+    #[structopt(long = "customizable")]
+    customizable: bool,
+}
+
+fn main() -> MyResult<()> {
+    let opts = Opts::from_args();
+
+    let GraphData {
+        graph,
+        mut edge_lookup,
+        keys,
+    } = if opts.graphml_format {
+        read_graphml(&opts.graph_file)?
+    } else {
+        parse_minimal_graph_file(&opts.graph_file)?
+    };
+
+    let contracted = if opts.customizable {
+        contract_customizable(&graph)?
+    } else {
+        let mut lp = LpProcess::new(graph.dim())?;
+        contract(&graph, &mut lp)?
+    };
+    name_shortcuts(&mut edge_lookup, &contracted);
+
+    println!("writing contracted graph to {}", opts.out_file);
+    write_minimal_graph_file(
+        &opts.out_file,
+        &GraphData {
+            graph: contracted,
+            edge_lookup,
+            keys,
+        },
+    )?;
+
+    Ok(())
+}
@@ -2,6 +2,7 @@ use geojson::Value;
 use osmpbfreader::{OsmObj, OsmPbfReader};
 
 use preference_splitting::geojson::read_geojson_map;
+use preference_splitting::helpers::haversine;
 use preference_splitting::{MyError, MyResult};
 
 use std::collections::HashMap;
@@ -172,18 +173,6 @@ impl CrowdednessGrid {
     }
 }
 
-fn haversine(from_lat: f64, from_lng: f64, to_lat: f64, to_lng: f64) -> f64 {
-    const EARTH_RADIUS: f64 = 6_371_007.2; // in m
-    let theta1 = from_lat.to_radians();
-    let theta2 = to_lat.to_radians();
-    let delta_theta = (to_lat - from_lat).to_radians();
-    let delta_lambda = (to_lng - from_lng).to_radians();
-    let a = (delta_theta / 2.0).sin().powi(2)
-        + theta1.cos() * theta2.cos() * (delta_lambda / 2.0).sin().powi(2);
-    let c = 2.0 * a.sqrt().asin();
-
-    EARTH_RADIUS * c
-}
 #[test]
 fn test_bbox_init() {
     let mut grid = CrowdednessGrid::new(10);
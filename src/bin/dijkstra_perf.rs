@@ -46,10 +46,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         let source_id = node_dstribution.sample(&mut rng);
         let dest_id = node_dstribution.sample(&mut rng);
 
-        let alpha = randomized_preference(&mut rng);
+        let alpha = randomized_preference(&mut rng, graph.dim());
 
         let now = Instant::now();
-        let path = find_path(&mut d, &[source_id, dest_id], alpha);
+        let path = find_path(&mut d, &[source_id, dest_id], &alpha);
         let elapsed = now.elapsed();
 
         match path {
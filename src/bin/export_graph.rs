@@ -0,0 +1,55 @@
+use preference_splitting::export::{
+    write_dimacs, write_graph_file, write_graphml, write_minimal_graph_file,
+};
+use preference_splitting::graph::parse_minimal_graph_file;
+use preference_splitting::graphml::read_graphml;
+use preference_splitting::MyResult;
+
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// Graph file to export
+    graph_file: String,
+    /// File to write the graph to. For DIMACS one file per metric is
+    /// written, named `<out_file>.<metric>.gr`
+    out_file: String,
+    /// File should be read as graphml
+    #[structopt(long = "graphml")]
+    graphml_format: bool,
+    /// Output format: fmi, minimal, graphml or dimacs
+    #[structopt(long = "format", default_value = "minimal")]
+    format: String,
+    /// Factor DIMACS costs are multiplied with before rounding them
+    #[structopt(long = "scale", default_value = "1")]
+    scale: f64,
+}
+
+fn main() -> MyResult<()> {
+    let opts = Opts::from_args();
+
+    let graph_data = if opts.graphml_format {
+        read_graphml(&opts.graph_file)?
+    } else {
+        parse_minimal_graph_file(&opts.graph_file)?
+    };
+
+    match opts.format.as_str() {
+        "fmi" => write_graph_file(&opts.out_file, &graph_data.graph)?,
+        "minimal" => write_minimal_graph_file(&opts.out_file, &graph_data)?,
+        "graphml" => write_graphml(&opts.out_file, &graph_data)?,
+        "dimacs" => {
+            for (metric, name) in graph_data.metric_names().iter().enumerate() {
+                let file = format!("{}.{}.gr", opts.out_file, name);
+                println!("writing metric {} to {}", name, file);
+                write_dimacs(&file, &graph_data, metric, opts.scale)?;
+            }
+        }
+        format => {
+            eprintln!("unknown format {}", format);
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use preference_splitting::export::{write_graphml, write_minimal_graph_file};
+use preference_splitting::geojson::{attach_geometries, read_geojson_map};
+use preference_splitting::graph::subgraph::{
+    induced_subgraph, nodes_in_bounding_box, nodes_within_hops, trajectory_corridor, Shortcuts,
+};
+use preference_splitting::graph::{
+    ch::{contract, name_shortcuts},
+    parse_minimal_graph_file, Coordinates,
+};
+use preference_splitting::graphml::{read_graphml, GraphData};
+use preference_splitting::lp::LpProcess;
+use preference_splitting::trajectories::read_trajectories;
+use preference_splitting::{MyError, MyResult};
+
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// Graph file to cut the subgraph from
+    graph_file: String,
+    /// File to write the subgraph to
+    out_file: String,
+    /// File should be read as graphml
+    #[structopt(long = "graphml")]
+    graphml_format: bool,
+    /// Geojson file with edge geometries (as written by osm_import)
+    #[structopt(long)]
+    geojson: Option<String>,
+    /// Write the graph in minimal fmi format instead of graphml
+    #[structopt(long = "fmi")]
+    fmi_output: bool,
+    /// Keep nodes within min_lat,min_lon,max_lat,max_lon
+    #[structopt(long, use_delimiter = true)]
+    bbox: Vec<f64>,
+    /// Keep nodes around these (internal) node ids
+    #[structopt(long, use_delimiter = true)]
+    nodes: Vec<u32>,
+    /// Keep nodes around the trajectory with this trip id
+    #[structopt(long)]
+    trip_id: Option<u32>,
+    /// Trajectory file to find the trip in
+    #[structopt(long)]
+    trajectories: Option<String>,
+    /// Number of hops around nodes or trajectories to keep
+    #[structopt(long, default_value = "0")]
+    hops: usize,
+    /// What to do with shortcuts: rebuild (contract again), drop or keep.
+    /// Keeping them only works if no shortest path leaves the subgraph
+    #[structopt(long, default_value = "rebuild")]
+    shortcuts: String,
+}
+
+fn main() -> MyResult<()> {
+    let opts = Opts::from_args();
+
+    let mut graph_data = if opts.graphml_format {
+        read_graphml(&opts.graph_file)?
+    } else {
+        parse_minimal_graph_file(&opts.graph_file)?
+    };
+    if let Some(geojson_file) = &opts.geojson {
+        println!("reading edge geometries from {}", geojson_file);
+        attach_geometries(&mut graph_data, &read_geojson_map(geojson_file)?);
+    }
+    let graph = &graph_data.graph;
+
+    if !opts.bbox.is_empty() && opts.bbox.len() != 4 {
+        eprintln!(
+            "--bbox needs 4 values (min_lat,min_lon,max_lat,max_lon), got {}",
+            opts.bbox.len()
+        );
+        std::process::exit(1);
+    }
+    if let Some(node) = opts
+        .nodes
+        .iter()
+        .find(|&&n| n as usize >= graph.nodes.len())
+    {
+        eprintln!(
+            "node {} is not in the graph, which has {} nodes",
+            node,
+            graph.nodes.len()
+        );
+        std::process::exit(1);
+    }
+
+    let keep = if !opts.bbox.is_empty() {
+        let min = Coordinates::new(opts.bbox[0], opts.bbox[1]);
+        let max = Coordinates::new(opts.bbox[2], opts.bbox[3]);
+        nodes_in_bounding_box(graph, min, max)
+    } else if !opts.nodes.is_empty() {
+        nodes_within_hops(graph, &opts.nodes, opts.hops)
+    } else if let (Some(trip_id), Some(trajectory_file)) = (opts.trip_id, &opts.trajectories) {
+        let trajectories = read_trajectories(trajectory_file)?;
+        let trajectory = trajectories
+            .iter()
+            .find(|t| t.trip_id[0].0 == Some(trip_id))
+            .ok_or(MyError::InvalidTrajectories)?;
+        trajectory_corridor(&graph_data, trajectory, opts.hops)?
+    } else {
+        eprintln!("one of --bbox, --nodes or --trip-id with --trajectories has to be given");
+        std::process::exit(1);
+    };
+
+    let shortcuts = match opts.shortcuts.as_str() {
+        "keep" => Shortcuts::Keep,
+        "drop" | "rebuild" => Shortcuts::Drop,
+        other => {
+            eprintln!("unknown shortcut handling {}", other);
+            std::process::exit(1);
+        }
+    };
+    let mut subgraph = induced_subgraph(&graph_data, &keep, shortcuts)?;
+
+    if opts.shortcuts == "rebuild" {
+        let GraphData {
+            graph,
+            mut edge_lookup,
+            keys,
+        } = subgraph;
+        let mut lp = LpProcess::new(graph.dim())?;
+        let contracted = contract(&graph, &mut lp)?;
+        name_shortcuts(&mut edge_lookup, &contracted);
+        subgraph = GraphData {
+            graph: contracted,
+            edge_lookup,
+            keys,
+        };
+    }
+
+    println!(
+        "subgraph has {} nodes and {} edges",
+        subgraph.graph.nodes.len(),
+        subgraph.graph.edges.len()
+    );
+    println!("writing subgraph to {}", opts.out_file);
+    if opts.fmi_output {
+        write_minimal_graph_file(&opts.out_file, &subgraph)?;
+    } else {
+        write_graphml(&opts.out_file, &subgraph)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use preference_splitting::graph::parse_minimal_graph_file_unchecked;
+use preference_splitting::graphml::read_graphml_unchecked;
+use preference_splitting::{MyError, MyResult};
+
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// Graph file to check
+    graph_file: String,
+    /// File should be read as graphml
+    #[structopt(long = "graphml")]
+    graphml_format: bool,
+    /// Also fail if unpacking an edge goes through more nested shortcuts
+    #[structopt(long = "max-depth")]
+    max_depth: Option<usize>,
+}
+
+fn main() -> MyResult<()> {
+    let opts = Opts::from_args();
+
+    // Inconsistent edges are kept, so the report can list them
+    let graph_data = if opts.graphml_format {
+        read_graphml_unchecked(&opts.graph_file)?
+    } else {
+        parse_minimal_graph_file_unchecked(&opts.graph_file)?
+    };
+
+    println!("validating graph");
+    let report = graph_data.graph.validate();
+    println!("{}", report);
+
+    let too_deep = opts.max_depth.map_or(false, |max| report.max_depth > max);
+    if !report.is_valid() || too_deep {
+        return Err(Box::new(MyError::InvalidGraph));
+    }
+    println!("graph is valid :-)");
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use preference_splitting::cache::write_graph_cache;
+use preference_splitting::geojson::{attach_geometries, read_geojson_map};
+use preference_splitting::graph::parse_minimal_graph_file;
+use preference_splitting::graphml::read_graphml;
+use preference_splitting::MyResult;
+
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// Graph file to convert
+    graph_file: String,
+    /// File to write the binary graph cache to
+    cache_file: String,
+    /// File should be read as graphml
+    #[structopt(long = "graphml")]
+    graphml_format: bool,
+    /// Geojson file with edge geometries (as written by osm_import)
+    #[structopt(long)]
+    geojson: Option<String>,
+}
+
+fn main() -> MyResult<()> {
+    let opts = Opts::from_args();
+
+    let mut graph_data = if opts.graphml_format {
+        read_graphml(&opts.graph_file)?
+    } else {
+        parse_minimal_graph_file(&opts.graph_file)?
+    };
+    if let Some(geojson_file) = &opts.geojson {
+        println!("reading edge geometries from {}", geojson_file);
+        attach_geometries(&mut graph_data, &read_geojson_map(geojson_file)?);
+    }
+
+    println!("writing graph cache to {}", opts.cache_file);
+    write_graph_cache(&opts.cache_file, &graph_data)?;
+
+    Ok(())
+}
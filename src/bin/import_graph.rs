@@ -0,0 +1,78 @@
+use preference_splitting::export::{write_graphml, write_minimal_graph_file};
+use preference_splitting::import::{
+    read_csv_edges, read_dimacs, read_dimacs_coordinates, CsvColumns,
+};
+use preference_splitting::MyResult;
+
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// File to write the graph to
+    graph_file: String,
+    /// DIMACS .gr files to import, one per metric
+    #[structopt(long, use_delimiter = true)]
+    dimacs: Vec<String>,
+    /// DIMACS .co file with node coordinates
+    #[structopt(long)]
+    coordinates: Option<String>,
+    /// CSV edge list to import
+    #[structopt(long)]
+    csv: Option<String>,
+    /// CSV column with the source node
+    #[structopt(long, default_value = "source")]
+    source_column: String,
+    /// CSV column with the target node
+    #[structopt(long, default_value = "target")]
+    target_column: String,
+    /// CSV column with the edge names
+    #[structopt(long)]
+    name_column: Option<String>,
+    /// CSV cost columns (comma separated), optionally renamed as column=metric
+    #[structopt(long, use_delimiter = true)]
+    metrics: Vec<String>,
+    /// CSV field delimiter
+    #[structopt(long, default_value = ",")]
+    delimiter: char,
+    /// Write the graph in minimal fmi format instead of graphml
+    #[structopt(long = "fmi")]
+    fmi_output: bool,
+}
+
+fn main() -> MyResult<()> {
+    let opts = Opts::from_args();
+
+    let mut graph_data = match opts.csv {
+        Some(csv) => {
+            let mut columns = CsvColumns::new(&opts.source_column, &opts.target_column, &[]);
+            columns.name = opts.name_column;
+            columns.delimiter = opts.delimiter as u8;
+            columns.metrics = opts
+                .metrics
+                .iter()
+                .map(|m| match m.find('=') {
+                    Some(i) => (m[..i].to_owned(), m[i + 1..].to_owned()),
+                    None => (m.clone(), m.clone()),
+                })
+                .collect();
+            read_csv_edges(csv, &columns)?
+        }
+        None if !opts.dimacs.is_empty() => read_dimacs(&opts.dimacs)?,
+        None => {
+            eprintln!("either --dimacs or --csv has to be given");
+            std::process::exit(1);
+        }
+    };
+    if let Some(coordinates) = opts.coordinates {
+        read_dimacs_coordinates(&mut graph_data, coordinates)?;
+    }
+
+    println!("writing graph to {}", opts.graph_file);
+    if opts.fmi_output {
+        write_minimal_graph_file(&opts.graph_file, &graph_data)?;
+    } else {
+        write_graphml(&opts.graph_file, &graph_data)?;
+    }
+
+    Ok(())
+}
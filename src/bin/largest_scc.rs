@@ -0,0 +1,83 @@
+use preference_splitting::export::{write_graphml, write_minimal_graph_file};
+use preference_splitting::graph::{
+    parse_minimal_graph_file,
+    scc::largest_scc,
+    subgraph::{induced_subgraph, Shortcuts},
+};
+use preference_splitting::graphml::read_graphml;
+use preference_splitting::trajectories::read_trajectories;
+use preference_splitting::MyResult;
+
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// Graph file to use
+    graph_file: String,
+    /// File to write the largest strongly connected component to
+    out_file: String,
+    /// File should be read as graphml
+    #[structopt(long = "graphml")]
+    graphml_format: bool,
+    /// Write the graph in minimal fmi format instead of graphml
+    #[structopt(long = "fmi")]
+    fmi_output: bool,
+    /// Trajectories to check against the extracted component
+    #[structopt(long)]
+    trajectories: Option<String>,
+}
+
+fn main() -> MyResult<()> {
+    let opts = Opts::from_args();
+
+    let graph_data = if opts.graphml_format {
+        read_graphml(&opts.graph_file)?
+    } else {
+        parse_minimal_graph_file(&opts.graph_file)?
+    };
+
+    println!("computing strongly connected components");
+    let keep = largest_scc(&graph_data.graph);
+    let component = induced_subgraph(&graph_data, &keep, Shortcuts::Keep)?;
+    println!(
+        "largest component has {} of {} nodes and {} of {} edges",
+        component.graph.nodes.len(),
+        graph_data.graph.nodes.len(),
+        component.graph.edges.len(),
+        graph_data.graph.edges.len()
+    );
+
+    if let Some(trajectory_file) = opts.trajectories {
+        let trajectories = read_trajectories(&trajectory_file)?;
+        let mut outside_edges = 0;
+        let mut affected_trajectories = 0;
+        for t in trajectories.iter() {
+            let outside = t
+                .path
+                .iter()
+                .filter(|&&e| component.edge_lookup.get_number(e).is_none())
+                .count();
+            outside_edges += outside;
+            if outside > 0 {
+                affected_trajectories += 1;
+            }
+        }
+        let total_edges: usize = trajectories.iter().map(|t| t.path.len()).sum();
+        println!(
+            "{} of {} trajectory edges lie outside the component, affecting {} of {} trajectories",
+            outside_edges,
+            total_edges,
+            affected_trajectories,
+            trajectories.len()
+        );
+    }
+
+    println!("writing component to {}", opts.out_file);
+    if opts.fmi_output {
+        write_minimal_graph_file(&opts.out_file, &component)?;
+    } else {
+        write_graphml(&opts.out_file, &component)?;
+    }
+
+    Ok(())
+}
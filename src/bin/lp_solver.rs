@@ -1,5 +1,5 @@
-use preference_splitting::lp::{BUFFER_SIZE, F64_SIZE, OUTPUT_BUFFER_SIZE};
-use preference_splitting::EDGE_COST_DIMENSION;
+use preference_splitting::lp::{buffer_size, output_buffer_size, F64_SIZE};
+use preference_splitting::MyError;
 
 use glpk_sys::*;
 
@@ -8,7 +8,6 @@ use std::ffi::CString;
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::os::raw::c_int;
 
-const DIM: c_int = EDGE_COST_DIMENSION as c_int;
 const GLP_MAX: c_int = 2; // maximisation
 const GLP_LO: c_int = 2; // variable with lower bound
 const GLP_DB: c_int = 4; // double-bounded variable
@@ -25,21 +24,24 @@ const GLP_FEAS: c_int = 2; // solution is feasible
 
 struct Lp {
     lp: *mut glp_prob,
+    dim: c_int,
     delta_col: c_int,
     counter: usize,
 }
 
 impl Lp {
-    fn new(counter: usize) -> Lp {
+    fn new(dim: usize, counter: usize) -> Lp {
+        let dim = dim as c_int;
         let (lp, delta_col) = unsafe {
             let lp = glp_create_prob();
             glp_set_obj_dir(lp, GLP_MAX);
-            let delta_col = Self::init_variables(lp);
+            let delta_col = Self::init_variables(lp, dim);
 
             (lp, delta_col)
         };
         let mut lp = Self {
             lp,
+            dim,
             delta_col,
             counter,
         };
@@ -49,9 +51,9 @@ impl Lp {
         lp
     }
 
-    unsafe fn init_variables(lp: *mut glp_prob) -> c_int {
-        glp_add_cols(lp, DIM);
-        for i in 0..DIM {
+    unsafe fn init_variables(lp: *mut glp_prob, dim: c_int) -> c_int {
+        glp_add_cols(lp, dim);
+        for i in 0..dim {
             let name =
                 CString::new(format!("alpha_{}", i)).expect("Column name could not be created");
             glp_set_col_bnds(lp, i + 1, GLP_DB, 0.0, 1.0);
@@ -73,25 +75,27 @@ impl Lp {
 
     unsafe fn add_sum_of_alpha_eq_one(&mut self) {
         let row = glp_add_rows(self.lp, 1);
-        let indices: Vec<_> = (0..=DIM).collect();
-        let values = vec![1.0; EDGE_COST_DIMENSION as usize + 1];
+        let indices: Vec<_> = (0..=self.dim).collect();
+        let values = vec![1.0; self.dim as usize + 1];
 
         glp_set_row_bnds(self.lp, row, GLP_FX, 1.0, 1.0);
-        glp_set_mat_row(self.lp, row, DIM, indices.as_ptr(), values.as_ptr());
+        glp_set_mat_row(self.lp, row, self.dim, indices.as_ptr(), values.as_ptr());
     }
 
     fn add_constraint(&mut self, coeff: &[f64]) {
-        if coeff.len() != EDGE_COST_DIMENSION as usize {
+        if coeff.len() != self.dim as usize {
             panic!(format!(
                 "got wrong number of coefficients ({} instead of {})",
                 coeff.len(),
-                EDGE_COST_DIMENSION
+                self.dim
             ));
         }
         unsafe {
             let row = glp_add_rows(self.lp, 1);
             // leading 0 + indices for alpha cols + index of delta col
-            let indices: Vec<_> = (0..=DIM).chain(std::iter::once(self.delta_col)).collect();
+            let indices: Vec<_> = (0..=self.dim)
+                .chain(std::iter::once(self.delta_col))
+                .collect();
 
             // leading 0 + values for alpha cols + value of delta col
             let values: Vec<_> = std::iter::once(0.0)
@@ -102,11 +106,17 @@ impl Lp {
             // 0 <= cost(alpha, p_alpha) - cost(alpha, p_trajectory) - delta
 
             glp_set_row_bnds(self.lp, row, GLP_LO, 0.0, 0.0);
-            glp_set_mat_row(self.lp, row, DIM + 1, indices.as_ptr(), values.as_ptr());
+            glp_set_mat_row(
+                self.lp,
+                row,
+                self.dim + 1,
+                indices.as_ptr(),
+                values.as_ptr(),
+            );
         }
     }
 
-    fn solve(&mut self) -> Result<[f64; EDGE_COST_DIMENSION + 1], LpError> {
+    fn solve(&mut self) -> Result<Vec<f64>, LpError> {
         unsafe {
             let mut params = glp_smcp::default();
             glp_init_smcp(&mut params);
@@ -136,8 +146,8 @@ impl Lp {
             } else {
                 return Err(LpError::Infeasible);
             }
-            let mut result = [0.0; EDGE_COST_DIMENSION + 1];
-            for i in 0..DIM {
+            let mut result = vec![0.0; self.dim as usize + 1];
+            for i in 0..self.dim {
                 result[i as usize] = glp_get_col_prim(self.lp, i + 1);
             }
 
@@ -160,7 +170,15 @@ fn main() -> Result<(), Box<dyn Error>> {
         glp_term_out(GLP_OFF);
     }
 
-    let mut buffer = [0u8; BUFFER_SIZE];
+    let dim: usize = match std::env::args().nth(1) {
+        Some(dim) => dim.parse()?,
+        None => {
+            eprintln!("usage: lp_solver <metric-count>");
+            return Err(Box::new(MyError::WrongArgumentNumber));
+        }
+    };
+
+    let mut buffer = vec![0u8; buffer_size(dim)];
     let stdin = std::io::stdin();
     let stdin = stdin.lock();
     let mut reader = BufReader::new(stdin);
@@ -170,14 +188,14 @@ fn main() -> Result<(), Box<dyn Error>> {
     let mut writer = BufWriter::new(stdout);
 
     let mut control_byte = [0u8; 1];
-    let mut lp = Lp::new(0);
+    let mut lp = Lp::new(dim, 0);
     loop {
         if reader.read_exact(&mut control_byte).is_err() {
             return Ok(());
         }
 
         match control_byte[0] {
-            0 => lp = Lp::new(lp.counter),
+            0 => lp = Lp::new(dim, lp.counter),
             1 => {
                 reader.read_exact(&mut buffer)?;
 
@@ -195,7 +213,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             2 => {
                 match lp.solve() {
                     Ok(results) => {
-                        let mut output = [0u8; OUTPUT_BUFFER_SIZE];
+                        let mut output = vec![0u8; output_buffer_size(dim)];
 
                         results
                             .iter()
@@ -0,0 +1,49 @@
+use preference_splitting::export::{write_graphml, write_minimal_graph_file};
+use preference_splitting::geojson::write_geojson_map;
+use preference_splitting::osm::{import_pbf, OsmImport, DEFAULT_HIGHWAYS};
+use preference_splitting::MyResult;
+
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+struct Opts {
+    /// OSM PBF file to import
+    pbf_file: String,
+    /// File to write the graph to
+    graph_file: String,
+    /// File to write the geojson edge geometries to
+    geojson_file: String,
+    /// Highway types to import (comma separated), defaults to all roads for cars
+    #[structopt(long, use_delimiter = true)]
+    highways: Vec<String>,
+    /// Write the graph in minimal fmi format instead of graphml
+    #[structopt(long = "fmi")]
+    fmi_output: bool,
+}
+
+fn main() -> MyResult<()> {
+    let opts = Opts::from_args();
+
+    let highways: Vec<_> = if opts.highways.is_empty() {
+        DEFAULT_HIGHWAYS.to_vec()
+    } else {
+        opts.highways.iter().map(String::as_str).collect()
+    };
+
+    let OsmImport {
+        graph_data,
+        geometries,
+    } = import_pbf(&opts.pbf_file, &highways)?;
+
+    println!("writing graph to {}", opts.graph_file);
+    if opts.fmi_output {
+        write_minimal_graph_file(&opts.graph_file, &graph_data)?;
+    } else {
+        write_graphml(&opts.graph_file, &graph_data)?;
+    }
+
+    println!("writing edge geometries to {}", opts.geojson_file);
+    write_geojson_map(&opts.geojson_file, &geometries)?;
+
+    Ok(())
+}
@@ -8,7 +8,7 @@ use preference_splitting::graphml::GraphData;
 
 use preference_splitting::trajectories::{check_trajectory, read_trajectories};
 use preference_splitting::{
-    helpers::randomized_preference, statistics::read_representative_results, MyError, MyResult, EDGE_COST_DIMENSION
+    helpers::randomized_preference, statistics::read_representative_results, MyError, MyResult,
 };
 
 use std::convert::TryInto;
@@ -55,15 +55,16 @@ fn main() -> MyResult<()> {
     println!("reading trajectories {}", results.trajectory_file);
     let mut trajectories = read_trajectories(&results.trajectory_file)?;
 
-    trajectories.iter_mut().for_each(|t| {
-        t.filter_out_self_loops(&graph, &edge_lookup);
-    });
+    for t in trajectories.iter_mut() {
+        t.filter_out_self_loops(&graph, &edge_lookup)?;
+    }
 
     println!("checking trajectory consistency");
-    if trajectories
+    let valid = trajectories
         .par_iter()
-        .all(|t| check_trajectory(&t, &graph, &edge_lookup))
-    {
+        .map(|t| check_trajectory(&t, &graph, &edge_lookup))
+        .collect::<Result<Vec<_>, _>>()?;
+    if valid.into_iter().all(|v| v) {
         println!("all {} trajectories seem valid :-)", trajectories.len());
     } else {
         println!("There are invalid trajectories :-(");
@@ -85,6 +86,8 @@ fn main() -> MyResult<()> {
     let mut paths = trajectories
         .into_iter()
         .map(|t| t.to_path(&graph, &edge_lookup))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
         .zip(results.results.iter_mut())
         .collect::<Vec<_>>();
 
@@ -101,21 +104,21 @@ fn main() -> MyResult<()> {
                 for (p, s) in chunk {
                     let ids = [*p.nodes.first().unwrap(), *p.nodes.last().unwrap()];
                     //travel time only
-                    let mut tt_preference = [0.0; EDGE_COST_DIMENSION];
+                    let mut tt_preference = vec![0.0; graph.dim()];
                     tt_preference[0] = 1.0;
                     let tt_path = graph
-                            .find_shortest_path(&mut d, 0, &ids, tt_preference)
+                            .find_shortest_path(&mut d, 0, &ids, &tt_preference)
                             .expect("there must be a path");
-                    s.tt_costs = Some(tt_path.total_dimension_costs);
                     s.overlap_by_tt = Some(overlap(p, &tt_path));
+                    s.tt_costs = Some(tt_path.total_dimension_costs);
                     //random preferences
                     let mut better = 0;
                     let mut overlaps_by_rng = Vec::new();
                     for _ in 0..nr_of_random_preferences {
-                        let rand_pref = randomized_preference(&mut rng);
+                        let rand_pref = randomized_preference(&mut rng, graph.dim());
 
                         let alpha_path = graph
-                            .find_shortest_path(&mut d, 0, &ids, rand_pref)
+                            .find_shortest_path(&mut d, 0, &ids, &rand_pref)
                             .expect("there must be a path");
                         let overlap = overlap(p, &alpha_path);
                         if  overlap > s.overlap + accuracy {
@@ -9,7 +9,7 @@ use preference_splitting::{
     helpers::{costs_by_alpha},
     lp::{LpProcess, PreferenceEstimator},
     statistics::{ExperimentResults, RepresentativeAlphaResult},
-    MyResult, EDGE_COST_DIMENSION,
+    MyResult,
 };
 
 use std::convert::TryInto;
@@ -63,7 +63,7 @@ fn main() -> MyResult<()> {
     for t in &trajectories{
         if t.vehicle_id == vehicle_id {
             trajectories_of_vehicle.push(t);
-            paths_of_vehicle.push(t.to_path(&graph, &edge_lookup));
+            paths_of_vehicle.push(t.to_path(&graph, &edge_lookup)?);
         }
     }
     let mut statistics : Vec<RepresentativeAlphaResult> = Vec::new();
@@ -111,7 +111,7 @@ fn main() -> MyResult<()> {
         for chunk in paths_with_statistics.chunks_mut(items_per_thread) {
             (scope.spawn(|_| {
                 let mut d = Dijkstra::new(&graph);
-                let mut lp = LpProcess::new().unwrap();
+                let mut lp = LpProcess::new(graph.dim()).unwrap();
                 let mut estimator = PreferenceEstimator::new(&graph, &mut lp);
                 let mut counter = 0;
                 for (paths, s) in chunk {
@@ -134,15 +134,15 @@ fn main() -> MyResult<()> {
                                &mut d,
                                0,
                                &[*p.nodes.first().unwrap(), *p.nodes.last().unwrap()],
-                               s.preference,
+                               &s.preference,
                             )
                             .expect("there must be a path");
                         
-                        alpha_costs.push(alpha_path.total_dimension_costs);
                         aggregated_cost_diffs.push(costs_by_alpha(&p.total_dimension_costs, &s.preference)
                             - costs_by_alpha(&alpha_path.total_dimension_costs, &s.preference));
                         overlaps.push(overlap(p, &alpha_path));
-                        trajectory_costs.push(p.total_dimension_costs);
+                        alpha_costs.push(alpha_path.total_dimension_costs);
+                        trajectory_costs.push(p.total_dimension_costs.clone());
                     }
                     s.alpha_costs = Some(alpha_costs);
                     s.aggregated_cost_diffs = Some(aggregated_cost_diffs);
@@ -164,7 +164,7 @@ fn main() -> MyResult<()> {
 
     println!("writing results to \"{}\"", outfile_name);
 
-    let mut metrics = vec!["".to_owned(); EDGE_COST_DIMENSION];
+    let mut metrics = vec!["".to_owned(); graph.dim()];
 
     for key in keys.values() {
         if let AttributeType::Double(idx) = key.attribute_type {
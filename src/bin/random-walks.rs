@@ -1,13 +1,15 @@
 use preference_splitting::{
-    graph::parse_minimal_graph_file, graphml::read_graphml,
-    trajectories::create_randomwalk_trajectory, MyResult,
+    graph::parse_minimal_graph_file,
+    graphml::{read_graphml, EdgeName},
+    trajectories::create_randomwalk_trajectory,
+    MyResult,
 };
 
 use rand::{
     distributions::{Distribution, Uniform},
     thread_rng,
 };
-use std::{collections::HashMap, fs::File, io::BufWriter, path::PathBuf};
+use std::{fs::File, io::BufWriter, path::PathBuf};
 use structopt::StructOpt;
 
 #[derive(StructOpt)]
@@ -66,14 +68,13 @@ fn main() -> MyResult<()> {
     println!("created {} walks", walks.len());
     println!("Mapping internal to external edge ids");
 
-    let reverse_lookup: HashMap<_, _> =
-        graph_data.edge_lookup.iter().map(|(k, v)| (v, k)).collect();
-
     for w in &mut walks {
         w.path.iter_mut().for_each(|internal| {
-            *internal = reverse_lookup[&(*internal as u32)]
-                .parse()
-                .unwrap_or_else(|_| panic!("could not map {} back to exteranl id", internal))
+            *internal = graph_data
+                .edge_lookup
+                .name(*internal as u32)
+                .and_then(EdgeName::as_number)
+                .unwrap_or_else(|| panic!("could not map {} back to exteranl id", internal))
         })
     }
 
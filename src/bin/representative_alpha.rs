@@ -9,7 +9,7 @@ use preference_splitting::{
     helpers::costs_by_alpha,
     lp::{LpProcess, PreferenceEstimator},
     statistics::{ExperimentResults, RepresentativeAlphaResult},
-    MyError, MyResult, EDGE_COST_DIMENSION,
+    MyError, MyResult,
 };
 
 use std::convert::TryInto;
@@ -56,18 +56,16 @@ fn main() -> MyResult<()> {
         .map(RepresentativeAlphaResult::new)
         .collect();
 
-    trajectories
-        .iter_mut()
-        .zip(statistics.iter_mut())
-        .for_each(|(t, s)| {
-            s.removed_self_loop_indices = t.filter_out_self_loops(&graph, &edge_lookup);
-        });
+    for (t, s) in trajectories.iter_mut().zip(statistics.iter_mut()) {
+        s.removed_self_loop_indices = t.filter_out_self_loops(&graph, &edge_lookup)?;
+    }
 
     println!("checking trajectory consistency");
-    if trajectories
+    let valid = trajectories
         .par_iter()
-        .all(|t| check_trajectory(&t, &graph, &edge_lookup))
-    {
+        .map(|t| check_trajectory(&t, &graph, &edge_lookup))
+        .collect::<Result<Vec<_>, _>>()?;
+    if valid.into_iter().all(|v| v) {
         println!("all {} trajectories seem valid :-)", trajectories.len());
     } else {
         println!("There are invalid trajectories :-(");
@@ -89,6 +87,8 @@ fn main() -> MyResult<()> {
     let mut paths = trajectories
         .into_iter()
         .map(|t| t.to_path(&graph, &edge_lookup))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
         .zip(statistics.iter_mut())
         .collect::<Vec<_>>();
 
@@ -99,7 +99,7 @@ fn main() -> MyResult<()> {
         for chunk in paths.chunks_mut(items_per_thread) {
             (scope.spawn(|_| {
                 let mut d = Dijkstra::new(&graph);
-                let mut lp = LpProcess::new().unwrap();
+                let mut lp = LpProcess::new(graph.dim()).unwrap();
                 let mut estimator = PreferenceEstimator::new(&graph, &mut lp);
                 let mut counter = 0;
                 for (p, s) in chunk {
@@ -115,20 +115,21 @@ fn main() -> MyResult<()> {
                         .try_into()
                         .expect("Couldn't convert run time into usize");
 
-                    s.trajectory_cost = p.total_dimension_costs;
+                    s.trajectory_cost = p.total_dimension_costs.clone();
 
                     let alpha_path = graph
                         .find_shortest_path(
                             &mut d,
                             0,
                             &[*p.nodes.first().unwrap(), *p.nodes.last().unwrap()],
-                            pref,
+                            &s.preference,
                         )
                         .expect("there must be a path");
-                    s.alpha_cost = alpha_path.total_dimension_costs;
-                    s.aggregated_cost_diff = costs_by_alpha(&p.total_dimension_costs, &pref)
-                        - costs_by_alpha(&alpha_path.total_dimension_costs, &pref);
+                    s.aggregated_cost_diff =
+                        costs_by_alpha(&p.total_dimension_costs, &s.preference)
+                            - costs_by_alpha(&alpha_path.total_dimension_costs, &s.preference);
                     s.overlap = overlap(p, &alpha_path);
+                    s.alpha_cost = alpha_path.total_dimension_costs;
 
                     if counter % 10 == 0 {
                         progress.inc(10);
@@ -146,7 +147,7 @@ fn main() -> MyResult<()> {
 
     println!("writing results to \"{}\"", outfile_name);
 
-    let mut metrics = vec!["".to_owned(); EDGE_COST_DIMENSION];
+    let mut metrics = vec!["".to_owned(); graph.dim()];
 
     for key in keys.values() {
         if let AttributeType::Double(idx) = key.attribute_type {
@@ -9,7 +9,7 @@ use preference_splitting::{
     helpers::{costs_by_alpha, Preference},
     lp::{LpProcess, PreferenceEstimator},
     statistics::{ExperimentResults, RepresentativeAlphaResult},
-    MyResult, EDGE_COST_DIMENSION,
+    MyResult,
 };
 
 use std::convert::TryInto;
@@ -71,11 +71,11 @@ fn main() -> MyResult<()> {
     let mut statistics : Vec<RepresentativeAlphaResult> = Vec::new();
     for _ in 0..num_vehicles {
         paths_per_vehicle_id.push(Vec::new());
-        preference_per_vehicle.push([0.0; EDGE_COST_DIMENSION]);
+        preference_per_vehicle.push(vec![0.0; graph.dim()]);
     }
     for t in &trajectories {
         let index = (t.vehicle_id - min_vehicle_id) as usize;
-        let path = t.to_path(&graph, &edge_lookup);
+        let path = t.to_path(&graph, &edge_lookup)?;
         paths_per_vehicle_id[index].push(path.clone());
         all_paths.push(path);
         statistics.push(RepresentativeAlphaResult::new(&t));
@@ -107,7 +107,7 @@ fn main() -> MyResult<()> {
         for chunk in paths_with_prefs.chunks_mut(items_per_thread) {
             (scope.spawn(|_| {
                 let mut d = Dijkstra::new(&graph);
-                let mut lp = LpProcess::new().unwrap();
+                let mut lp = LpProcess::new(graph.dim()).unwrap();
                 let mut estimator = PreferenceEstimator::new(&graph, &mut lp);
                 for (paths, pref) in chunk {
                     **pref = estimator
@@ -122,7 +122,7 @@ fn main() -> MyResult<()> {
     progress.finish();
 
     for i in 0..statistics.len() {
-        statistics[i].preference = preference_per_vehicle[(statistics[i].vehicle_id-min_vehicle_id) as usize];
+        statistics[i].preference = preference_per_vehicle[(statistics[i].vehicle_id-min_vehicle_id) as usize].clone();
     }
 
     let mut paths = all_paths
@@ -145,20 +145,20 @@ fn main() -> MyResult<()> {
                         .try_into()
                         .expect("Couldn't convert run time into usize");
 
-                    s.trajectory_cost = p.total_dimension_costs;
+                    s.trajectory_cost = p.total_dimension_costs.clone();
 
                     let alpha_path = graph
                         .find_shortest_path(
                             &mut d,
                             0,
                             &[*p.nodes.first().unwrap(), *p.nodes.last().unwrap()],
-                            s.preference,
+                            &s.preference,
                         )
                         .expect("there must be a path");
-                    s.alpha_cost = alpha_path.total_dimension_costs;
                     s.aggregated_cost_diff = costs_by_alpha(&p.total_dimension_costs, &s.preference)
                         - costs_by_alpha(&alpha_path.total_dimension_costs, &s.preference);
                     s.overlap = overlap(p, &alpha_path);
+                    s.alpha_cost = alpha_path.total_dimension_costs;
                 }
             }));
         }
@@ -171,7 +171,7 @@ fn main() -> MyResult<()> {
 
     println!("writing results to \"{}\"", outfile_name);
 
-    let mut metrics = vec!["".to_owned(); EDGE_COST_DIMENSION];
+    let mut metrics = vec!["".to_owned(); graph.dim()];
 
     for key in keys.values() {
         if let AttributeType::Double(idx) = key.attribute_type {
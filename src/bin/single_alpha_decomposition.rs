@@ -58,18 +58,16 @@ fn main() -> MyResult<()> {
         .map(RepresentativeAlphaResult::new)
         .collect();
 
-    trajectories
-        .iter_mut()
-        .zip(statistics.iter_mut())
-        .for_each(|(t, s)| {
-            s.removed_self_loop_indices = t.filter_out_self_loops(&graph, &edge_lookup);
-        });
+    for (t, s) in trajectories.iter_mut().zip(statistics.iter_mut()) {
+        s.removed_self_loop_indices = t.filter_out_self_loops(&graph, &edge_lookup)?;
+    }
 
     println!("checking trajectory consistency");
-    if trajectories
+    let valid = trajectories
         .par_iter()
-        .all(|t| check_trajectory(&t, &graph, &edge_lookup))
-    {
+        .map(|t| check_trajectory(&t, &graph, &edge_lookup))
+        .collect::<Result<Vec<_>, _>>()?;
+    if valid.into_iter().all(|v| v) {
         println!("all {} trajectories seem valid :-)", trajectories.len());
     } else {
         println!("There are invalid trajectories :-(");
@@ -91,6 +89,8 @@ fn main() -> MyResult<()> {
     let mut paths = trajectories
         .into_iter()
         .map(|t| t.to_path(&graph, &edge_lookup))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
         .zip(results.results.iter_mut())
         .collect::<Vec<_>>();
 
@@ -101,7 +101,7 @@ fn main() -> MyResult<()> {
         for chunk in paths.chunks_mut(items_per_thread) {
             (scope.spawn(|_| {
                 let mut d = Dijkstra::new(&graph);
-                let mut lp = LpProcess::new().unwrap();
+                let mut lp = LpProcess::new(graph.dim()).unwrap();
                 let mut analyzer = TrajectoryAnalysis::new(&graph, &mut d, &mut lp);
                 let mut counter = 0;
                 for (p, s) in chunk {
@@ -156,7 +156,7 @@ fn main() -> MyResult<()> {
                         }
                     }
                     if modus == 2 || modus == 3 {
-                        let mut lp2 = LpProcess::new().unwrap();
+                        let mut lp2 = LpProcess::new(graph.dim()).unwrap();
                         let mut estimator = PreferenceEstimator::new(&graph, &mut lp2);
                         let mut d2 = Dijkstra::new(&graph);
                         let representative_pref = estimator.calc_representative_preference(
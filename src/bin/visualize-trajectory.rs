@@ -6,7 +6,7 @@ use preference_splitting::statistics::{
 use preference_splitting::trajectories::{read_trajectories, Trajectory};
 use preference_splitting::{
     graph::{dijkstra::Dijkstra, parse_minimal_graph_file},
-    graphml::GraphData,
+    graphml::{EdgeName, GraphData},
     MyResult,
 };
 
@@ -187,17 +187,17 @@ fn representative_path(
     let graph_data = load_graph(results)?;
     let mut d = Dijkstra::new(&graph_data.graph);
 
-    let first_edge_id = graph_data.edge_lookup[&trajectory.path[0 as u32].to_string()];
-    let last_edge_id = graph_data.edge_lookup[&trajectory.path.last().unwrap().to_string()];
+    let first_edge_id = graph_data.edge_lookup[&EdgeName::Number(trajectory.path[0 as u32])];
+    let last_edge_id = graph_data.edge_lookup[&EdgeName::Number(*trajectory.path.last().unwrap())];
     let first_node = graph_data.graph.edges[first_edge_id].source_id;
     let last_node = graph_data.graph.edges[last_edge_id].target_id;
 
     let path = graph_data
         .graph
-        .find_shortest_path(&mut d, 0, &[first_node, last_node], repr.preference)
+        .find_shortest_path(&mut d, 0, &[first_node, last_node], &repr.preference)
         .expect("Could not find representative path");
 
-    Ok(Trajectory::from_path(&path, &graph_data.edge_lookup))
+    Ok(Trajectory::from_path(&path, &graph_data.edge_lookup)?)
 }
 
 fn load_results(style: &Style, path: PathBuf) -> Result<Results, Box<dyn std::error::Error>> {
@@ -1,10 +1,10 @@
 use preference_splitting::graph::dijkstra::Dijkstra;
+use preference_splitting::graph::turns::{read_turns, Turns};
 use preference_splitting::graph::{parse_minimal_graph_file, path::Path, Graph};
 
 use preference_splitting::graphml::GraphData;
-use preference_splitting::helpers::Preference;
 use preference_splitting::trajectories::{check_trajectory, read_trajectories};
-use preference_splitting::{statistics::read_representative_results, MyError, MyResult, helpers::randomized_preference,EDGE_COST_DIMENSION};
+use preference_splitting::{statistics::read_representative_results, MyError, MyResult, helpers::randomized_preference};
 
 use rand::thread_rng;
 
@@ -28,6 +28,12 @@ struct Opts {
     /// Number of threads to use
     #[structopt(short, long, default_value = "8")]
     threads: usize,
+    /// Turn restrictions and costs to respect in the searches
+    #[structopt(long)]
+    turns: Option<String>,
+    /// Forbid U-turns in the searches
+    #[structopt(long)]
+    no_u_turns: bool,
 }
 
 fn main() -> MyResult<()> {
@@ -36,27 +42,35 @@ fn main() -> MyResult<()> {
         out_file,
         compare_with_rng,
         threads,
+        turns,
+        no_u_turns,
     } = Opts::from_args();
 
     let mut results = read_representative_results(repr_results_file)?;
 
     println!("reading graph file: {}", results.graph_file);
+    let graph_data = parse_minimal_graph_file(&results.graph_file)?;
+    let mut turns = turns.map(|f| read_turns(f, &graph_data)).transpose()?;
+    if no_u_turns {
+        turns.get_or_insert_with(Turns::new).forbid_u_turns = true;
+    }
     let GraphData {
         graph, edge_lookup, ..
-    } = parse_minimal_graph_file(&results.graph_file)?;
+    } = graph_data;
 
     println!("reading trajectories {}", results.trajectory_file);
     let mut trajectories = read_trajectories(&results.trajectory_file)?;
 
-    trajectories.iter_mut().for_each(|t| {
-        t.filter_out_self_loops(&graph, &edge_lookup);
-    });
+    for t in trajectories.iter_mut() {
+        t.filter_out_self_loops(&graph, &edge_lookup)?;
+    }
 
     println!("checking trajectory consistency");
-    if trajectories
+    let valid = trajectories
         .par_iter()
-        .all(|t| check_trajectory(&t, &graph, &edge_lookup))
-    {
+        .map(|t| check_trajectory(&t, &graph, &edge_lookup))
+        .collect::<Result<Vec<_>, _>>()?;
+    if valid.into_iter().all(|v| v) {
         println!("all {} trajectories seem valid :-)", trajectories.len());
     } else {
         println!("There are invalid trajectories :-(");
@@ -78,6 +92,8 @@ fn main() -> MyResult<()> {
     let mut paths = trajectories
         .into_iter()
         .map(|t| t.to_path(&graph, &edge_lookup))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
         .zip(results.results.iter_mut())
         .collect::<Vec<_>>();
 
@@ -89,12 +105,15 @@ fn main() -> MyResult<()> {
     crossbeam::scope(|scope| {
         for chunk in paths.chunks_mut(items_per_thread) {
             (scope.spawn(|_| {
-                let mut d = Dijkstra::new(&graph);
+                let mut d = match &turns {
+                    Some(turns) => Dijkstra::with_turns(&graph, turns),
+                    None => Dijkstra::new(&graph),
+                };
                 let mut counter = 0;
                 let mut rng = thread_rng();
                 for (p, s) in chunk {
                     //travel time only
-                    let mut tt_preference = [0.0; EDGE_COST_DIMENSION];
+                    let mut tt_preference = vec![0.0; graph.dim()];
                     tt_preference[0] = 1.0;
                     s.nr_of_wrong_turns_by_tt = Some(run_experiment(&graph, &mut d, p, &tt_preference).len());
                     if compare_with_rng == 0{
@@ -104,7 +123,7 @@ fn main() -> MyResult<()> {
                         let mut wrong_turns_by_rng = Vec::new();
                         s.nr_of_wrong_turns = Some(run_experiment(&graph, &mut d, p, &s.preference).len());
                         for _ in 0..compare_with_rng {
-                            let rand_pref = randomized_preference(&mut rng);
+                            let rand_pref = randomized_preference(&mut rng, graph.dim());
                             wrong_turns_by_rng.push(run_experiment(&graph, &mut d, p, &rand_pref).len());
                         }
                         s.nr_of_wrong_turns_by_rng = Some(wrong_turns_by_rng);
@@ -134,7 +153,7 @@ fn main() -> MyResult<()> {
     Ok(())
 }
 
-fn run_experiment(g: &Graph, d: &mut Dijkstra, p: &Path, alpha: &Preference) -> Vec<usize> {
+fn run_experiment(g: &Graph, d: &mut Dijkstra, p: &Path, alpha: &[f64]) -> Vec<usize> {
     let mut wrong_turns = vec![];
 
     let mut cur_node = *p.nodes.first().unwrap();
@@ -143,7 +162,7 @@ fn run_experiment(g: &Graph, d: &mut Dijkstra, p: &Path, alpha: &Preference) ->
 
     while cur_node != last_node {
         let path = g
-            .find_shortest_path(d, 0, &[cur_node, last_node], *alpha)
+            .find_shortest_path(d, 0, &[cur_node, last_node], alpha)
             .expect("There must be a path");
 
         let identical_edges = p.edges.0[cur_index..]
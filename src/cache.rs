@@ -0,0 +1,366 @@
+//! Binary cache format for `GraphData`.
+//!
+//! The cache stores the graph as built by `Graph::new`, including the half
+//! edges and offsets of every node. Loading maps the file and copies these
+//! arrays into the `Graph` without sorting or building anything again.
+//!
+//! Layout (all numbers little endian):
+//!
+//! | field          | type                                                          |
+//! |----------------|---------------------------------------------------------------|
+//! | magic          | `b"PPTSGRPH"`                                                 |
+//! | version        | u32                                                           |
+//! | dim            | u32                                                           |
+//! | node count     | u32                                                           |
+//! | edge count     | u32                                                           |
+//! | checksum       | u32, CRC32 of everything after the header                     |
+//! | metric names   | `dim` strings                                                 |
+//! | nodes          | id u32, ch_level u32, coordinates                             |
+//! | edges          | source u32, target u32, edge_a u32, edge_b u32, `dim` f64,    |
+//! |                | u32 geometry length, then that many coordinates               |
+//! | half edges out | node count + 1 offsets u32, then (edge id u32, target u32)    |
+//! | half edges in  | node count + 1 offsets u32, then (edge id u32, source u32)    |
+//! | edge lookup    | u32 count, then (edge id u32, name string) pairs              |
+//!
+//! Strings are stored as u32 length followed by UTF-8 bytes. Coordinates are
+//! two f64 (lat, lon), missing node coordinates as NaN. Nodes are stored by
+//! index, edges ordered by id, missing replaced edges as `u32::MAX` and
+//! missing geometries with length 0. The number of half edges is the last
+//! offset; they get the costs of their edges.
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+use memmap::Mmap;
+
+use crate::graph::{Coordinates, Edge, Graph, HalfEdge, Node};
+use crate::graphml::{EdgeLookup, GraphData, GraphmlAttribute};
+use crate::{MyError, MyResult};
+
+const MAGIC: &[u8; 8] = b"PPTSGRPH";
+const VERSION: u32 = 3;
+const HEADER_SIZE: usize = MAGIC.len() + 5 * 4;
+const NO_EDGE: u32 = u32::MAX;
+
+/// Checks whether the file at `file_path` starts like a graph cache.
+pub fn is_graph_cache<P: AsRef<Path>>(file_path: P) -> MyResult<bool> {
+    let mut file = File::open(file_path)?;
+    let mut magic = [0u8; 8];
+    match file.read_exact(&mut magic) {
+        Ok(()) => Ok(&magic == MAGIC),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(Box::new(e)),
+    }
+}
+
+pub fn write_graph_cache<P: AsRef<Path>>(file_path: P, graph_data: &GraphData) -> MyResult<()> {
+    let GraphData {
+        graph, edge_lookup, ..
+    } = graph_data;
+
+    let mut payload = Vec::new();
+    for name in graph_data.metric_names() {
+        write_str(&mut payload, &name);
+    }
+    for n in graph.nodes.iter() {
+        write_u32(&mut payload, n.id);
+        write_u32(&mut payload, n.ch_level);
+        let c = n
+            .coordinates
+            .unwrap_or(Coordinates::new(f64::NAN, f64::NAN));
+        write_coordinates(&mut payload, c);
+    }
+    for e in graph.edges.iter() {
+        let (edge_a, edge_b) = e.replaced_edges.unwrap_or((NO_EDGE, NO_EDGE));
+        write_u32(&mut payload, e.source_id);
+        write_u32(&mut payload, e.target_id);
+        write_u32(&mut payload, edge_a);
+        write_u32(&mut payload, edge_b);
+        for c in e.edge_costs.iter() {
+            payload.extend_from_slice(&c.to_le_bytes());
+        }
+        let geometry = e.geometry.as_deref().unwrap_or(&[]);
+        write_u32(&mut payload, geometry.len() as u32);
+        for &c in geometry {
+            write_coordinates(&mut payload, c);
+        }
+    }
+    for (offsets, half_edges) in graph.half_edges().iter() {
+        for &offset in offsets.iter() {
+            write_u32(&mut payload, offset);
+        }
+        for half_edge in half_edges.iter() {
+            write_u32(&mut payload, half_edge.edge_id);
+            write_u32(&mut payload, half_edge.target_id);
+        }
+    }
+    write_u32(&mut payload, edge_lookup.len() as u32);
+    for (name, id) in edge_lookup.iter() {
+        write_u32(&mut payload, id);
+        write_str(&mut payload, &name.to_string());
+    }
+
+    let file = File::create(file_path)?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(MAGIC)?;
+    for value in &[
+        VERSION,
+        graph.dim() as u32,
+        graph.nodes.len() as u32,
+        graph.edges.len() as u32,
+        crc32fast::hash(&payload),
+    ] {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    writer.write_all(&payload)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+pub fn read_graph_cache<P: AsRef<Path>>(file_path: P) -> MyResult<GraphData> {
+    println!("Reading graph cache...");
+    let file = File::open(file_path)?;
+    // Safety: the cache is only read and must not be modified concurrently
+    let mmap = unsafe { Mmap::map(&file)? };
+
+    if mmap.len() < HEADER_SIZE || &mmap[..MAGIC.len()] != MAGIC {
+        return Err(Box::new(MyError::InvalidGraphCache("wrong magic bytes")));
+    }
+    let mut header = Cursor::new(&mmap[MAGIC.len()..HEADER_SIZE]);
+    if header.u32()? != VERSION {
+        return Err(Box::new(MyError::InvalidGraphCache("unsupported version")));
+    }
+    let dim = header.u32()? as usize;
+    let node_count = header.u32()?;
+    let edge_count = header.u32()?;
+    let checksum = header.u32()?;
+
+    let payload = &mmap[HEADER_SIZE..];
+    if crc32fast::hash(payload) != checksum {
+        return Err(Box::new(MyError::InvalidGraphCache("checksum mismatch")));
+    }
+    let mut cursor = Cursor::new(payload);
+
+    let mut metric_names = Vec::with_capacity(dim);
+    for _ in 0..dim {
+        metric_names.push(cursor.str()?);
+    }
+    let keys = metric_names
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.to_string(), GraphmlAttribute::metric(n, i)))
+        .collect();
+
+    let mut nodes = Vec::with_capacity(node_count as usize);
+    for index in 0..node_count {
+        let mut node = Node::new(cursor.u32()?, cursor.u32()?);
+        if node.id != index {
+            return Err(Box::new(MyError::InvalidGraphCache(
+                "node id differs from index",
+            )));
+        }
+        let c = cursor.coordinates()?;
+        if !c.lat.is_nan() && !c.lon.is_nan() {
+            node.coordinates = Some(c);
+        }
+        nodes.push(node);
+    }
+
+    let mut edges = Vec::with_capacity(edge_count as usize);
+    for id in 0..edge_count {
+        let source_id = cursor.u32()?;
+        let target_id = cursor.u32()?;
+        let replaced_edges = match (cursor.u32()?, cursor.u32()?) {
+            (NO_EDGE, NO_EDGE) => None,
+            edges => Some(edges),
+        };
+        let mut edge_costs = Vec::with_capacity(dim);
+        for _ in 0..dim {
+            edge_costs.push(cursor.f64()?);
+        }
+        let mut edge = Edge::new(id, source_id, target_id, edge_costs, replaced_edges);
+        let geometry_len = cursor.u32()?;
+        if geometry_len > 0 {
+            let mut geometry = Vec::with_capacity(geometry_len as usize);
+            for _ in 0..geometry_len {
+                geometry.push(cursor.coordinates()?);
+            }
+            edge.geometry = Some(geometry);
+        }
+        edges.push(edge);
+    }
+    let half_edges_out = read_half_edges(&mut cursor, node_count, &edges)?;
+    let half_edges_in = read_half_edges(&mut cursor, node_count, &edges)?;
+
+    let lookup_count = cursor.u32()?;
+    let mut edge_lookup = EdgeLookup::with_capacity(lookup_count as usize);
+    for _ in 0..lookup_count {
+        let id = cursor.u32()?;
+        edge_lookup.insert(cursor.str()?, id);
+    }
+
+    Ok(GraphData {
+        graph: Graph::from_parts(nodes, edges, half_edges_out, half_edges_in),
+        edge_lookup,
+        keys,
+    })
+}
+
+/// Reads the offsets and half edges of one direction. `Graph` indexes them
+/// without checks, so they have to fit the nodes and edges.
+fn read_half_edges(
+    cursor: &mut Cursor,
+    node_count: u32,
+    edges: &[Edge],
+) -> MyResult<(Vec<u32>, Vec<HalfEdge>)> {
+    let mut offsets = Vec::with_capacity(node_count as usize + 1);
+    for _ in 0..=node_count {
+        offsets.push(cursor.u32()?);
+    }
+    if offsets[0] != 0 || offsets.windows(2).any(|w| w[0] > w[1]) {
+        return Err(Box::new(MyError::InvalidGraphCache(
+            "half edge offsets are not sorted",
+        )));
+    }
+
+    let count = offsets[node_count as usize];
+    let mut half_edges = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let edge_id = cursor.u32()?;
+        let target_id = cursor.u32()?;
+        let edge = edges
+            .get(edge_id as usize)
+            .filter(|_| target_id < node_count)
+            .ok_or(MyError::InvalidGraphCache(
+                "half edge with unknown edge or node",
+            ))?;
+        half_edges.push(HalfEdge::new(edge_id, target_id, edge.edge_costs.clone()));
+    }
+    Ok((offsets, half_edges))
+}
+
+fn write_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_coordinates(buffer: &mut Vec<u8>, value: Coordinates) {
+    buffer.extend_from_slice(&value.lat.to_le_bytes());
+    buffer.extend_from_slice(&value.lon.to_le_bytes());
+}
+
+fn write_str(buffer: &mut Vec<u8>, value: &str) {
+    write_u32(buffer, value.len() as u32);
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Cursor { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> MyResult<&'a [u8]> {
+        if self.pos + len > self.data.len() {
+            return Err(Box::new(MyError::InvalidGraphCache(
+                "unexpected end of file",
+            )));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> MyResult<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn f64(&mut self) -> MyResult<f64> {
+        Ok(f64::from_le_bytes(self.bytes(8)?.try_into()?))
+    }
+
+    fn coordinates(&mut self) -> MyResult<Coordinates> {
+        Ok(Coordinates::new(self.f64()?, self.f64()?))
+    }
+
+    fn str(&mut self) -> MyResult<String> {
+        let len = self.u32()? as usize;
+        Ok(std::str::from_utf8(self.bytes(len)?)?.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::parse_minimal_graph_file;
+
+    #[test]
+    fn round_trip() {
+        let path = std::env::temp_dir().join("ppts_cache_round_trip.bin");
+        let mut original = parse_minimal_graph_file("./src/test_graphs/minimalTestGraph").unwrap();
+        original.graph.nodes[3_u32].coordinates = Some(Coordinates::new(48.7, 9.1));
+        original.graph.edges[5_u32].geometry = Some(vec![
+            Coordinates::new(48.7, 9.1),
+            Coordinates::new(48.8, 9.2),
+        ]);
+        write_graph_cache(&path, &original).unwrap();
+
+        assert!(is_graph_cache(&path).unwrap());
+        let cached = read_graph_cache(&path).unwrap();
+
+        assert_eq!(cached.graph.dim(), original.graph.dim());
+        assert_eq!(cached.metric_names(), original.metric_names());
+        assert_eq!(cached.edge_lookup, original.edge_lookup);
+        assert_eq!(cached.graph.nodes.len(), original.graph.nodes.len());
+        for (a, b) in cached.graph.edges.iter().zip(original.graph.edges.iter()) {
+            assert_eq!(a.source_id, b.source_id);
+            assert_eq!(a.target_id, b.target_id);
+            assert_eq!(a.edge_costs, b.edge_costs);
+            assert_eq!(a.replaced_edges, b.replaced_edges);
+            assert_eq!(a.geometry, b.geometry);
+        }
+        for (a, b) in cached.graph.nodes.iter().zip(original.graph.nodes.iter()) {
+            assert_eq!(a.coordinates, b.coordinates);
+        }
+        let half_edges = |graph: &Graph| {
+            graph
+                .half_edges()
+                .iter()
+                .map(|(offsets, half_edges)| {
+                    let half_edges: Vec<_> = half_edges
+                        .iter()
+                        .map(|h| (h.edge_id, h.target_id, h.edge_costs.clone()))
+                        .collect();
+                    (offsets.to_vec(), half_edges)
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(half_edges(&cached.graph), half_edges(&original.graph));
+
+        // loaders pick up the cache on their own
+        let detected = parse_minimal_graph_file(&path).unwrap();
+        assert_eq!(detected.edge_lookup, original.edge_lookup);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn corrupted_cache_is_rejected() {
+        let path = std::env::temp_dir().join("ppts_cache_corrupted.bin");
+        let original = parse_minimal_graph_file("./src/test_graphs/minimalTestGraph").unwrap();
+        write_graph_cache(&path, &original).unwrap();
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        std::fs::write(&path, bytes).unwrap();
+
+        assert!(read_graph_cache(&path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
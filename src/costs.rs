@@ -0,0 +1,290 @@
+use crate::graph::subgraph::{induced_subgraph, Shortcuts};
+use crate::graphml::{GraphData, GraphmlAttribute};
+use crate::helpers::{Costs, MyVec};
+use crate::{MyError, MyResult};
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// Value of an edge in a cost file. Files contain either a single cost per
+/// edge or a list of costs (like the output of the `crowdedness` binary).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CostValue {
+    Single(f64),
+    Multiple(Costs),
+}
+
+/// Per-edge costs keyed by edge name, as in the `*.costs.json` files.
+pub struct CostFile {
+    pub metric_names: Vec<String>,
+    pub costs: HashMap<String, Costs>,
+}
+
+/// Edges of the base graph without an entry in a cost file.
+pub struct MissingCosts {
+    pub metric_names: Vec<String>,
+    pub edges: Vec<String>,
+}
+
+/// Reads a cost file. Metrics are named after the file, e.g.
+/// `travel_time_denmark.costs.json` yields `travel_time_denmark`; files with
+/// several costs per edge get numbered metric names.
+pub fn read_cost_file<P: AsRef<Path>>(path: P) -> MyResult<CostFile> {
+    let file_name = path
+        .as_ref()
+        .file_name()
+        .map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+    let name = file_name
+        .trim_end_matches(".json")
+        .trim_end_matches(".costs")
+        .to_owned();
+
+    let file = std::fs::File::open(&path)?;
+    let file = std::io::BufReader::new(file);
+    let values: HashMap<String, CostValue> = serde_json::from_reader(file)?;
+
+    let costs: HashMap<_, _> = values
+        .into_iter()
+        .map(|(edge, value)| match value {
+            CostValue::Single(c) => (edge, vec![c]),
+            CostValue::Multiple(c) => (edge, c),
+        })
+        .collect();
+
+    let dim = costs.values().next().map_or(1, |c| c.len());
+    if costs.values().any(|c| c.len() != dim) {
+        return Err(Box::new(MyError::InvalidCostFile(file_name)));
+    }
+    let metric_names = if dim == 1 {
+        vec![name]
+    } else {
+        (0..dim).map(|i| format!("{}_{}", name, i)).collect()
+    };
+
+    Ok(CostFile {
+        metric_names,
+        costs,
+    })
+}
+
+/// Edges of `graph_data` without an entry in each of `cost_files`, by name
+/// or by id for unnamed edges. Shortcuts are not listed.
+pub fn missing_costs(graph_data: &GraphData, cost_files: &[CostFile]) -> Vec<MissingCosts> {
+    let GraphData {
+        graph, edge_lookup, ..
+    } = graph_data;
+
+    cost_files
+        .iter()
+        .map(|cost_file| MissingCosts {
+            metric_names: cost_file.metric_names.clone(),
+            edges: graph
+                .edges
+                .iter()
+                .filter(|e| e.replaced_edges.is_none())
+                .filter_map(|e| match edge_lookup.name(e.id) {
+                    Some(name) if cost_file.costs.contains_key(&name.to_string()) => None,
+                    Some(name) => Some(name.to_string()),
+                    None => Some(e.id.to_string()),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Replaces the edge costs of `graph_data` with the costs of `cost_files`,
+/// one or more metrics per file in the given order. Shortcut costs are
+/// recomputed from the new costs if the graph was contracted with
+/// `ch::contract_customizable`. Other hierarchies are an error, as their
+/// witnesses might not hold for the new costs, unless `drop_shortcuts` is
+/// set, which removes all shortcuts and levels.
+///
+/// Edges missing from a cost file get the costs `fill`. Without one, missing
+/// edges are an error (see `missing_costs` to find them). With `normalize`
+/// every metric is scaled to a mean of 1 over the original edges with costs
+/// in the file.
+pub fn annotate_costs(
+    graph_data: &mut GraphData,
+    cost_files: &[CostFile],
+    normalize: bool,
+    fill: Option<f64>,
+    drop_shortcuts: bool,
+) -> MyResult<Vec<MissingCosts>> {
+    let missing = missing_costs(graph_data, cost_files);
+    if fill.is_none() {
+        if let Some(m) = missing.iter().find(|m| !m.edges.is_empty()) {
+            return Err(Box::new(MyError::MissingCosts {
+                metrics: m.metric_names.join(", "),
+                edges: m.edges.len(),
+            }));
+        }
+    }
+
+    if !graph_data.graph.is_customizable() {
+        if !drop_shortcuts {
+            return Err(Box::new(MyError::NotCustomizable));
+        }
+        eprintln!("warning: dropping all shortcuts, the annotated graph is not contracted");
+        let keep = MyVec(vec![true; graph_data.graph.nodes.len()]);
+        *graph_data = induced_subgraph(graph_data, &keep, Shortcuts::Drop)?;
+    }
+    let GraphData {
+        graph,
+        edge_lookup,
+        keys,
+    } = graph_data;
+
+    let dim: usize = cost_files.iter().map(|f| f.metric_names.len()).sum();
+    let mut new_costs = vec![Vec::new(); graph.edges.len()];
+    // Sums and numbers of the costs given in the files, for normalizing
+    let mut sums = vec![0.0; dim];
+    let mut counts = vec![0u32; dim];
+    let mut offset = 0;
+    for cost_file in cost_files {
+        let file_dim = cost_file.metric_names.len();
+        for e in graph.edges.iter().filter(|e| e.replaced_edges.is_none()) {
+            let costs = edge_lookup
+                .name(e.id)
+                .and_then(|name| cost_file.costs.get(&name.to_string()));
+            match costs {
+                Some(costs) => {
+                    new_costs[e.id as usize].extend_from_slice(costs);
+                    for (metric, c) in costs.iter().enumerate() {
+                        sums[offset + metric] += c;
+                        counts[offset + metric] += 1;
+                    }
+                }
+                None => {
+                    new_costs[e.id as usize].extend(vec![fill.expect("checked above"); file_dim])
+                }
+            }
+        }
+        offset += file_dim;
+    }
+
+    if normalize {
+        let means: Vec<_> = sums
+            .into_iter()
+            .zip(counts)
+            .map(|(s, count)| s / f64::from(count.max(1)))
+            .collect();
+        for costs in new_costs.iter_mut() {
+            costs
+                .iter_mut()
+                .zip(&means)
+                .filter(|(_, &mean)| mean > 0.0)
+                .for_each(|(c, mean)| *c /= mean);
+        }
+    }
+
+    for (e, costs) in graph.edges.iter_mut().zip(new_costs) {
+        if e.replaced_edges.is_none() {
+            e.edge_costs = costs;
+        }
+    }
+    graph.customize()?;
+
+    *keys = cost_files
+        .iter()
+        .flat_map(|f| f.metric_names.iter())
+        .enumerate()
+        .map(|(i, n)| (n.to_string(), GraphmlAttribute::metric(n, i)))
+        .collect::<BTreeMap<_, _>>();
+
+    Ok(missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::parse_minimal_graph_file;
+
+    #[test]
+    fn annotate_and_normalize() {
+        let mut graph_data =
+            parse_minimal_graph_file("./src/test_graphs/minimalTestGraph").unwrap();
+        let costs = graph_data
+            .edge_lookup
+            .iter()
+            .filter(|&(_, id)| graph_data.graph.edges[id].replaced_edges.is_none())
+            .map(|(name, id)| (name.to_string(), id))
+            .filter(|(name, _)| name != "100")
+            .map(|(name, id)| (name, vec![f64::from(id + 1), 2.0]))
+            .collect();
+        let cost_file = CostFile {
+            metric_names: vec!["a".to_owned(), "b".to_owned()],
+            costs,
+        };
+
+        let cost_files = [cost_file];
+        let missing = missing_costs(&graph_data, &cost_files);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].edges, vec!["100".to_owned()]);
+        assert!(annotate_costs(&mut graph_data, &cost_files, true, None, true).is_err());
+        // minimalTestGraph was contracted with witness searches
+        assert!(annotate_costs(&mut graph_data, &cost_files, true, Some(5.0), false).is_err());
+
+        annotate_costs(&mut graph_data, &cost_files, true, Some(5.0), true).unwrap();
+        let graph = &graph_data.graph;
+        assert_eq!(graph.dim(), 2);
+        assert_eq!(graph_data.metric_names(), vec!["a", "b"]);
+        let filled = graph_data.edge_lookup.get("100").unwrap();
+        // The fill value does not count towards the mean
+        assert_eq!(graph.edges[filled].edge_costs[1], 2.5);
+        let base_edges: Vec<_> = graph
+            .edges
+            .iter()
+            .filter(|e| e.replaced_edges.is_none() && e.id != filled)
+            .collect();
+        for metric in 0..2 {
+            let mean = base_edges.iter().map(|e| e.edge_costs[metric]).sum::<f64>()
+                / base_edges.len() as f64;
+            assert!((mean - 1.0).abs() < 0.000001);
+        }
+
+        assert!(graph.edges.iter().all(|e| e.replaced_edges.is_none()));
+    }
+
+    #[test]
+    fn annotate_customizable_graph() {
+        use crate::graph::{ch::contract_customizable, parse_graph_file};
+
+        let graph = parse_graph_file("./src/test_graphs/testGraph").unwrap();
+        let graph = contract_customizable(&graph).unwrap();
+        let costs = graph
+            .edges
+            .iter()
+            .filter(|e| e.replaced_edges.is_none())
+            .map(|e| (e.id.to_string(), f64::from(e.id % 3 + 1)))
+            .map(|(name, c)| (name, vec![c]))
+            .collect();
+        let mut graph_data = GraphData {
+            edge_lookup: graph
+                .edges
+                .iter()
+                .map(|e| (e.id.to_string(), e.id))
+                .collect(),
+            graph,
+            keys: Default::default(),
+        };
+        let cost_file = CostFile {
+            metric_names: vec!["a".to_owned()],
+            costs,
+        };
+
+        annotate_costs(&mut graph_data, &[cost_file], false, None, false).unwrap();
+        let graph = &graph_data.graph;
+        assert!(graph.edges.iter().any(|e| e.replaced_edges.is_some()));
+        for e in graph.edges.iter() {
+            if let Some((a, b)) = e.replaced_edges {
+                for (i, c) in e.edge_costs.iter().enumerate() {
+                    let expected = graph.edges[a].edge_costs[i] + graph.edges[b].edge_costs[i];
+                    assert!((c - expected).abs() < 0.000001);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//! Writers for the graph formats read by `graph::parse_graph_file`,
+//! `graph::parse_minimal_graph_file` and `graphml::read_graphml`, plus
+//! DIMACS shortest path graphs for other tools.
+//!
+//! Edges are always written ordered by id, so replaced edges of shortcuts
+//! refer to the right lines and reading a written graph yields the same
+//! edge ids again.
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::graph::Graph;
+use crate::graphml::{EdgeName, GraphData};
+use crate::MyResult;
+
+/// Writes `graph` in the full fmi format read by `parse_graph_file`.
+///
+/// The format has no place for edge and metric names. Nodes without
+/// coordinates are written at 0/0, OSM ids and elevations as 0.
+pub fn write_graph_file<P: AsRef<Path>>(file_path: P, graph: &Graph) -> MyResult<()> {
+    let file = File::create(file_path)?;
+    let mut writer = BufWriter::new(file);
+
+    // parse_graph_file skips exactly four lines
+    writeln!(
+        writer,
+        "# Node Attributes: ID OSM-ID lat lon elevation CH-Level"
+    )?;
+    writeln!(
+        writer,
+        "# Edge Attributes: source-id target-id {}-metrics replaced-edge1 replaced-edge2",
+        graph.dim()
+    )?;
+    writeln!(writer, "#")?;
+    writeln!(writer)?;
+    writeln!(writer, "{}", graph.dim())?;
+    writeln!(writer, "{}", graph.nodes.len())?;
+    writeln!(writer, "{}", graph.edges.len())?;
+
+    for n in graph.nodes.iter() {
+        let (lat, lon) = n.coordinates.map_or((0.0, 0.0), |c| (c.lat, c.lon));
+        writeln!(writer, "{} 0 {} {} 0 {}", n.id, lat, lon, n.ch_level)?;
+    }
+
+    for e in graph.edges.iter() {
+        write!(writer, "{} {}", e.source_id, e.target_id)?;
+        write_costs_and_replaced_edges(&mut writer, graph, e.id)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes `graph_data` in the minimal fmi format read by
+/// `parse_minimal_graph_file`. Edges without a name are written under their
+/// id.
+pub fn write_minimal_graph_file<P: AsRef<Path>>(
+    file_path: P,
+    graph_data: &GraphData,
+) -> MyResult<()> {
+    let graph = &graph_data.graph;
+    let edge_names = edge_names(graph_data);
+
+    let file = File::create(file_path)?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(writer, "# Node Attributes: ID CH-Level [lat lon]")?;
+    writeln!(
+        writer,
+        "# Edge Attributes: ID source-id target-id {}-metrics replaced-edge1 replaced-edge2",
+        graph.dim()
+    )?;
+    writeln!(writer)?;
+    writeln!(writer, "{}", graph.dim())?;
+    writeln!(writer, "{}", graph_data.metric_names().join(" "))?;
+    writeln!(writer, "{}", graph.nodes.len())?;
+    writeln!(writer, "{}", graph.edges.len())?;
+
+    for n in graph.nodes.iter() {
+        match n.coordinates {
+            Some(c) => writeln!(writer, "{} {} {} {}", n.id, n.ch_level, c.lat, c.lon)?,
+            None => writeln!(writer, "{} {}", n.id, n.ch_level)?,
+        }
+    }
+
+    for e in graph.edges.iter() {
+        write!(
+            writer,
+            "{} {} {}",
+            edge_names[e.id as usize], e.source_id, e.target_id
+        )?;
+        write_costs_and_replaced_edges(&mut writer, graph, e.id)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes `graph_data` as GraphML that `read_graphml` reads back. Replaced
+/// edges of shortcuts are stored by name in `edgeA` and `edgeB`.
+pub fn write_graphml<P: AsRef<Path>>(file_path: P, graph_data: &GraphData) -> MyResult<()> {
+    let graph = &graph_data.graph;
+    let edge_names = edge_names(graph_data);
+
+    let file = File::create(file_path)?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )?;
+    writeln!(
+        writer,
+        r#"  <key id="id" for="node" attr.name="id" attr.type="string"/>"#
+    )?;
+    writeln!(
+        writer,
+        r#"  <key id="level" for="node" attr.name="level" attr.type="long"/>"#
+    )?;
+    let with_coordinates = graph.nodes.iter().any(|n| n.coordinates.is_some());
+    if with_coordinates {
+        for name in &["lat", "lon"] {
+            writeln!(
+                writer,
+                r#"  <key id="{0}" for="node" attr.name="{0}" attr.type="double"/>"#,
+                name
+            )?;
+        }
+    }
+    for (i, name) in graph_data.metric_names().iter().enumerate() {
+        writeln!(
+            writer,
+            r#"  <key id="m{}" for="edge" attr.name="{}" attr.type="double"/>"#,
+            i,
+            escape(name)
+        )?;
+    }
+    for name in &["name", "edgeA", "edgeB"] {
+        writeln!(
+            writer,
+            r#"  <key id="{0}" for="edge" attr.name="{0}" attr.type="string"/>"#,
+            name
+        )?;
+    }
+    writeln!(writer, r#"  <graph id="G" edgedefault="directed">"#)?;
+
+    for n in graph.nodes.iter() {
+        write!(
+            writer,
+            r#"    <node id="n{0}"><data key="id">n{0}</data><data key="level">{1}</data>"#,
+            n.id, n.ch_level
+        )?;
+        if let Some(c) = n.coordinates {
+            write!(
+                writer,
+                r#"<data key="lat">{}</data><data key="lon">{}</data>"#,
+                c.lat, c.lon
+            )?;
+        }
+        writeln!(writer, "</node>")?;
+    }
+
+    for e in graph.edges.iter() {
+        write!(
+            writer,
+            r#"    <edge source="n{}" target="n{}"><data key="name">{}</data>"#,
+            e.source_id,
+            e.target_id,
+            escape(&edge_names[e.id as usize])
+        )?;
+        for (i, c) in e.edge_costs.iter().enumerate() {
+            write!(writer, r#"<data key="m{}">{}</data>"#, i, c)?;
+        }
+        let (edge_a, edge_b) = match e.replaced_edges {
+            Some((edge_a, edge_b)) => (
+                escape(&edge_names[edge_a as usize]),
+                escape(&edge_names[edge_b as usize]),
+            ),
+            None => ("-1".into(), "-1".into()),
+        };
+        writeln!(
+            writer,
+            r#"<data key="edgeA">{}</data><data key="edgeB">{}</data></edge>"#,
+            edge_a, edge_b
+        )?;
+    }
+
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Writes the original edges of `graph_data` as a DIMACS shortest path
+/// graph (`.gr`) with the costs of `metric` as arc weights.
+///
+/// DIMACS weights are integers, so costs are multiplied by `scale` and
+/// rounded. Node ids are shifted by one, as DIMACS counts from 1.
+pub fn write_dimacs<P: AsRef<Path>>(
+    file_path: P,
+    graph_data: &GraphData,
+    metric: usize,
+    scale: f64,
+) -> MyResult<()> {
+    let graph = &graph_data.graph;
+    let metric_name = graph_data.metric_names().swap_remove(metric);
+    let edges: Vec<_> = graph
+        .edges
+        .iter()
+        .filter(|e| e.replaced_edges.is_none())
+        .collect();
+
+    let file = File::create(file_path)?;
+    let mut writer = BufWriter::new(file);
+
+    writeln!(
+        writer,
+        "c metric {}, costs scaled by {}",
+        metric_name, scale
+    )?;
+    writeln!(writer, "p sp {} {}", graph.nodes.len(), edges.len())?;
+    for e in edges {
+        writeln!(
+            writer,
+            "a {} {} {}",
+            e.source_id + 1,
+            e.target_id + 1,
+            (e.edge_costs[metric] * scale).round() as u64
+        )?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn write_costs_and_replaced_edges<W: Write>(
+    writer: &mut W,
+    graph: &Graph,
+    edge: u32,
+) -> MyResult<()> {
+    let e = &graph.edges[edge];
+    for c in e.edge_costs.iter() {
+        write!(writer, " {}", c)?;
+    }
+    match e.replaced_edges {
+        Some((edge_a, edge_b)) => writeln!(writer, " {} {}", edge_a, edge_b)?,
+        None => writeln!(writer, " -1 -1")?,
+    }
+    Ok(())
+}
+
+/// Names of all edges by id. Edges missing from the lookup are named by
+/// their id.
+fn edge_names(graph_data: &GraphData) -> Vec<Cow<'_, str>> {
+    let mut edge_names: Vec<Cow<str>> = (0..graph_data.graph.edges.len())
+        .map(|id| Cow::Owned(id.to_string()))
+        .collect();
+    for (name, id) in graph_data.edge_lookup.iter() {
+        edge_names[id as usize] = match name {
+            EdgeName::Number(number) => Cow::Owned(number.to_string()),
+            EdgeName::Text(text) => Cow::Borrowed(text),
+        };
+    }
+    edge_names
+}
+
+fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(&['&', '<', '>', '"'][..]) {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{parse_graph_file, parse_minimal_graph_file, Coordinates};
+    use crate::graphml::read_graphml;
+
+    fn assert_same_edges(written: &Graph, original: &Graph) {
+        assert_eq!(written.dim(), original.dim());
+        assert_eq!(written.nodes.len(), original.nodes.len());
+        assert_eq!(written.edges.len(), original.edges.len());
+        for (e, other) in written.edges.iter().zip(original.edges.iter()) {
+            assert_eq!(e.source_id, other.source_id);
+            assert_eq!(e.target_id, other.target_id);
+            assert_eq!(e.edge_costs, other.edge_costs);
+            assert_eq!(e.replaced_edges, other.replaced_edges);
+        }
+    }
+
+    #[test]
+    fn written_graph_file_reads_back() {
+        let path = std::env::temp_dir().join("ppts_written_graph");
+        let original = parse_graph_file("./src/test_graphs/concTestGraph").unwrap();
+        write_graph_file(&path, &original).unwrap();
+        let written = parse_graph_file(path.to_str().unwrap()).unwrap();
+
+        assert_same_edges(&written, &original);
+        for (n, other) in written.nodes.iter().zip(original.nodes.iter()) {
+            assert_eq!(n.ch_level, other.ch_level);
+            assert_eq!(n.coordinates, other.coordinates);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn written_minimal_graph_file_reads_back() {
+        let path = std::env::temp_dir().join("ppts_written_minimal_graph");
+        let mut original = parse_minimal_graph_file("./src/test_graphs/minimalTestGraph").unwrap();
+        original.graph.nodes[0_u32].coordinates = Some(Coordinates::new(48.7, 9.1));
+        write_minimal_graph_file(&path, &original).unwrap();
+        let written = parse_minimal_graph_file(&path).unwrap();
+
+        assert_same_edges(&written.graph, &original.graph);
+        assert_eq!(written.metric_names(), original.metric_names());
+        assert_eq!(written.edge_lookup, original.edge_lookup);
+        for (n, other) in written.graph.nodes.iter().zip(original.graph.nodes.iter()) {
+            assert_eq!(n.coordinates, other.coordinates);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn written_graphml_reads_back() {
+        let path = std::env::temp_dir().join("ppts_written.graphml");
+        let mut original = read_graphml("./src/test_graphs/testGraph.graphml").unwrap();
+        for n in original.graph.nodes.iter_mut() {
+            n.coordinates = Some(Coordinates::new(48.0 + f64::from(n.id) / 10.0, 9.5));
+        }
+        write_graphml(&path, &original).unwrap();
+        let written = read_graphml(&path).unwrap();
+
+        assert_same_edges(&written.graph, &original.graph);
+        assert_eq!(written.metric_names(), original.metric_names());
+        assert_eq!(written.edge_lookup, original.edge_lookup);
+        for (n, other) in written.graph.nodes.iter().zip(original.graph.nodes.iter()) {
+            assert_eq!(n.coordinates, other.coordinates);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn dimacs_contains_original_edges() {
+        let path = std::env::temp_dir().join("ppts_written.gr");
+        let original = parse_minimal_graph_file("./src/test_graphs/minimalTestGraph").unwrap();
+        write_dimacs(&path, &original, 1, 10.0).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+
+        let base_edges: Vec<_> = original
+            .graph
+            .edges
+            .iter()
+            .filter(|e| e.replaced_edges.is_none())
+            .collect();
+        let mut lines = written.lines().skip(1);
+        assert_eq!(
+            lines.next(),
+            Some(format!("p sp {} {}", original.graph.nodes.len(), base_edges.len()).as_str())
+        );
+        for (line, e) in lines.zip(base_edges) {
+            let expected = format!(
+                "a {} {} {}",
+                e.source_id + 1,
+                e.target_id + 1,
+                (e.edge_costs[1] * 10.0).round()
+            );
+            assert_eq!(line, expected);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
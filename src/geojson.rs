@@ -1,6 +1,8 @@
+use crate::graph::Coordinates;
+use crate::graphml::GraphData;
 use crate::MyResult;
 
-use geojson::Geometry;
+use geojson::{Geometry, Value};
 use std::collections::HashMap;
 
 pub fn read_geojson_map<P: AsRef<std::path::Path>>(path: P) -> MyResult<HashMap<i64, Geometry>> {
@@ -8,8 +10,120 @@ pub fn read_geojson_map<P: AsRef<std::path::Path>>(path: P) -> MyResult<HashMap<
     let file = std::io::BufReader::new(file);
     let map: HashMap<i64, String> = serde_json::from_reader(file)?;
 
-    Ok(map
+    map.iter()
+        .map(|(&i, s)| Ok((i, serde_json::from_str(s)?)))
+        .collect()
+}
+
+/// Writes geometries in the format read by `read_geojson_map`.
+pub fn write_geojson_map<P: AsRef<std::path::Path>>(
+    path: P,
+    map: &HashMap<i64, Geometry>,
+) -> MyResult<()> {
+    let map = map
         .iter()
-        .map(|(&i, s)| (i, serde_json::from_str(s).expect("could not parse geojson")))
-        .collect())
+        .map(|(&i, g)| Ok((i, serde_json::to_string(g)?)))
+        .collect::<MyResult<HashMap<_, _>>>()?;
+
+    let file = std::fs::File::create(path)?;
+    let file = std::io::BufWriter::new(file);
+    serde_json::to_writer(file, &map)?;
+    Ok(())
+}
+
+/// Sets the geometry of all edges whose name has a line string in `map`.
+/// Nodes without coordinates get the matching end point of the line.
+pub fn attach_geometries(graph_data: &mut GraphData, map: &HashMap<i64, Geometry>) {
+    let GraphData {
+        graph, edge_lookup, ..
+    } = graph_data;
+
+    for (name, id) in edge_lookup.iter() {
+        let line = match name.as_number().and_then(|key| map.get(&key)) {
+            Some(Geometry {
+                value: Value::LineString(line),
+                ..
+            }) => line,
+            _ => continue,
+        };
+        let geometry: Vec<_> = line.iter().map(|p| Coordinates::new(p[1], p[0])).collect();
+        if geometry.is_empty() {
+            continue;
+        }
+
+        let edge = &mut graph.edges[id];
+        let (source, target) = (edge.source_id, edge.target_id);
+        let (first, last) = (geometry[0], geometry[geometry.len() - 1]);
+        edge.geometry = Some(geometry);
+        for &(node, c) in &[(source, first), (target, last)] {
+            let node = &mut graph.nodes[node];
+            if node.coordinates.is_none() {
+                node.coordinates = Some(c);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::parse_minimal_graph_file;
+
+    #[test]
+    fn shortcuts_get_attached_geometries() {
+        let mut graph_data =
+            parse_minimal_graph_file("./src/test_graphs/minimalTestGraph").unwrap();
+        let graph = &graph_data.graph;
+        let shortcut = graph
+            .edges
+            .iter()
+            .find(|e| e.replaced_edges.is_some())
+            .unwrap();
+        let shortcut_id = shortcut.id;
+        let unpacked = graph.unpack_edge(shortcut_id);
+        let (first, second) = (unpacked[0], &unpacked[1..]);
+        let name = |id: u32| {
+            graph_data
+                .edge_lookup
+                .name(id)
+                .and_then(|n| n.as_number())
+                .unwrap()
+        };
+
+        // A bend on the first edge, all other edges get straight lines
+        let mut map = HashMap::new();
+        map.insert(
+            name(first),
+            Geometry::new(Value::LineString(vec![
+                vec![9.0, 48.0],
+                vec![9.5, 48.2],
+                vec![10.0, 48.0],
+            ])),
+        );
+        for (i, &e) in second.iter().enumerate() {
+            let lon = 10.0 + i as f64;
+            map.insert(
+                name(e),
+                Geometry::new(Value::LineString(vec![
+                    vec![lon, 48.0],
+                    vec![lon + 1.0, 48.0],
+                ])),
+            );
+        }
+        attach_geometries(&mut graph_data, &map);
+
+        let graph = &graph_data.graph;
+        let source = graph.edges[shortcut_id].source_id;
+        assert_eq!(
+            graph.nodes[source].coordinates,
+            Some(Coordinates::new(48.0, 9.0))
+        );
+        let mut expected = vec![
+            Coordinates::new(48.0, 9.0),
+            Coordinates::new(48.2, 9.5),
+            Coordinates::new(48.0, 10.0),
+        ];
+        expected.extend((1..=second.len()).map(|i| Coordinates::new(48.0, 10.0 + i as f64)));
+        assert_eq!(graph.edge_geometry(shortcut_id), Some(expected));
+    }
 }
@@ -0,0 +1,476 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use ordered_float::OrderedFloat;
+
+use crate::graph::{Edge, Graph, Node};
+use crate::graphml::EdgeLookup;
+use crate::helpers::{add_edge_costs, costs_by_alpha, equal_weights, Costs, MyVec};
+use crate::lp::LpProcess;
+use crate::{MyError, MyResult};
+
+/// Maximum number of nodes a single witness search settles before it gives
+/// up and the shortcut is inserted anyway.
+const WITNESS_SETTLE_LIMIT: usize = 1000;
+
+/// Contracts an uncontracted `graph` (all `ch_level`s 0, no shortcuts).
+///
+/// A shortcut is only left out if for every preference of the linear
+/// model a witness path exists that is at least as cheap, so the resulting
+/// hierarchy answers queries for all preferences correctly. The returned
+/// graph keeps the ids of all original edges; shortcuts are appended after
+/// them.
+pub fn contract(graph: &Graph, lp: &mut LpProcess) -> MyResult<Graph> {
+    contract_with(graph, Some(lp))
+}
+
+/// Contracts `graph` without witness searches: every pair of neighbours of
+/// a contracted node gets a shortcut. The hierarchy does not depend on the
+/// metrics, so it stays exact when the costs are replaced and
+/// `Graph::customize` is called, at the price of more shortcuts.
+pub fn contract_customizable(graph: &Graph) -> MyResult<Graph> {
+    contract_with(graph, None)
+}
+
+fn contract_with(graph: &Graph, lp: Option<&mut LpProcess>) -> MyResult<Graph> {
+    if graph.nodes.iter().any(|n| n.ch_level != 0)
+        || graph.edges.iter().any(|e| e.replaced_edges.is_some())
+    {
+        return Err(Box::new(MyError::AlreadyContracted));
+    }
+
+    println!("Contracting graph...");
+    let mut contractor = Contractor::new(graph, lp);
+
+    let mut queue: BinaryHeap<_> = (0..graph.nodes.len() as u32)
+        .map(|node| Reverse((contractor.priority(node), node)))
+        .collect();
+    let mut levels = MyVec(vec![0; graph.nodes.len()]);
+    let mut level = 0;
+
+    while let Some(Reverse((priority, node))) = queue.pop() {
+        // lazy update: neighbours of contracted nodes might have become
+        // more expensive in the meantime
+        let current = contractor.priority(node);
+        if current > priority {
+            queue.push(Reverse((current, node)));
+            continue;
+        }
+        contractor.contract_node(node)?;
+        levels[node] = level;
+        level += 1;
+    }
+
+    println!(
+        "Inserted {} shortcuts",
+        contractor.edges.len() - graph.edges.len()
+    );
+
+    let nodes = graph
+        .nodes
+        .iter()
+        .map(|n| {
+            let mut node = Node::new(n.id, levels[n.id]);
+            node.coordinates = n.coordinates;
+            node
+        })
+        .collect();
+    Ok(Graph::new(nodes, contractor.edges.0))
+}
+
+/// Adds the shortcuts of `contracted` to `edge_lookup`, which names the
+/// edges of the graph it was contracted from. Shortcuts get numeric names
+/// continuing after the largest numeric name.
+pub fn name_shortcuts(edge_lookup: &mut EdgeLookup, contracted: &Graph) {
+    let first_name = edge_lookup
+        .iter()
+        .filter_map(|(name, _)| name.as_number())
+        .max()
+        .map_or(0, |max| max + 1);
+    let shortcuts = contracted
+        .edges
+        .iter()
+        .filter(|e| e.replaced_edges.is_some());
+    for (name, e) in (first_name..).zip(shortcuts) {
+        edge_lookup.insert(name, e.id);
+    }
+}
+
+struct Contractor<'a> {
+    // Without an LP, no witness searches are done
+    lp: Option<&'a mut LpProcess>,
+    dim: usize,
+    edges: MyVec<Edge>,
+    edges_out: MyVec<Vec<u32>>,
+    edges_in: MyVec<Vec<u32>>,
+    contracted: MyVec<bool>,
+    contracted_neighbours: MyVec<u32>,
+
+    // witness search state
+    dist: MyVec<f64>,
+    prev: MyVec<Option<u32>>,
+    touched: Vec<u32>,
+    heap: BinaryHeap<Reverse<(OrderedFloat<f64>, u32)>>,
+}
+
+impl<'a> Contractor<'a> {
+    fn new(graph: &Graph, lp: Option<&'a mut LpProcess>) -> Self {
+        let node_count = graph.nodes.len();
+        let mut edges_out = MyVec(vec![Vec::new(); node_count]);
+        let mut edges_in = MyVec(vec![Vec::new(); node_count]);
+
+        let edges: Vec<_> = graph
+            .edges
+            .iter()
+            .map(|e| {
+                let mut edge =
+                    Edge::new(e.id, e.source_id, e.target_id, e.edge_costs.clone(), None);
+                edge.geometry = e.geometry.clone();
+                edge
+            })
+            .collect();
+        for e in &edges {
+            edges_out[e.source_id].push(e.id);
+            edges_in[e.target_id].push(e.id);
+        }
+
+        Contractor {
+            lp,
+            dim: graph.dim(),
+            edges: MyVec(edges),
+            edges_out,
+            edges_in,
+            contracted: MyVec(vec![false; node_count]),
+            contracted_neighbours: MyVec(vec![0; node_count]),
+            dist: MyVec(vec![f64::MAX; node_count]),
+            prev: MyVec(vec![None; node_count]),
+            touched: Vec::new(),
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    /// Edges into `node` from uncontracted nodes, without self loops.
+    fn remaining_in(&self, node: u32) -> Vec<u32> {
+        self.edges_in[node]
+            .iter()
+            .copied()
+            .filter(|&e| {
+                let source = self.edges[e].source_id;
+                source != node && !self.contracted[source]
+            })
+            .collect()
+    }
+
+    /// Edges out of `node` to uncontracted nodes, without self loops.
+    fn remaining_out(&self, node: u32) -> Vec<u32> {
+        self.edges_out[node]
+            .iter()
+            .copied()
+            .filter(|&e| {
+                let target = self.edges[e].target_id;
+                target != node && !self.contracted[target]
+            })
+            .collect()
+    }
+
+    /// Estimated edge difference of contracting `node`. Uses the worst case
+    /// of one shortcut per pair of neighbours, as simulating the contraction
+    /// would need the LP for every candidate.
+    fn priority(&self, node: u32) -> i64 {
+        let ins = self.remaining_in(node).len() as i64;
+        let outs = self.remaining_out(node).len() as i64;
+        ins * outs - ins - outs + i64::from(self.contracted_neighbours[node])
+    }
+
+    fn contract_node(&mut self, node: u32) -> MyResult<()> {
+        let ins = self.remaining_in(node);
+        let outs = self.remaining_out(node);
+
+        let mut shortcuts = Vec::new();
+        for &edge_in in &ins {
+            for &edge_out in &outs {
+                let source = self.edges[edge_in].source_id;
+                let target = self.edges[edge_out].target_id;
+                if source == target {
+                    continue;
+                }
+                let costs = add_edge_costs(
+                    &self.edges[edge_in].edge_costs,
+                    &self.edges[edge_out].edge_costs,
+                );
+                if self.needs_shortcut(source, target, node, &costs)? {
+                    shortcuts.push((source, target, costs, edge_in, edge_out));
+                }
+            }
+        }
+
+        for (source, target, costs, edge_in, edge_out) in shortcuts {
+            let id = self.edges.len() as u32;
+            self.edges.push(Edge::new(
+                id,
+                source,
+                target,
+                costs,
+                Some((edge_in, edge_out)),
+            ));
+            self.edges_out[source].push(id);
+            self.edges_in[target].push(id);
+        }
+
+        for &e in &ins {
+            self.contracted_neighbours[self.edges[e].source_id] += 1;
+        }
+        for &e in &outs {
+            self.contracted_neighbours[self.edges[e].target_id] += 1;
+        }
+        self.contracted[node] = true;
+        Ok(())
+    }
+
+    /// Checks whether some preference exists, for which the path from
+    /// `source` to `target` via `node` with `costs` is strictly better than
+    /// any witness path avoiding `node`.
+    fn needs_shortcut(
+        &mut self,
+        source: u32,
+        target: u32,
+        node: u32,
+        costs: &[f64],
+    ) -> MyResult<bool> {
+        let accuracy = 0.000_001;
+
+        match self.lp.as_mut() {
+            Some(lp) => lp.reset()?,
+            None => return Ok(true),
+        }
+        let mut alpha = equal_weights(self.dim);
+        loop {
+            let witness = match self.find_witness(source, target, node, &alpha, costs) {
+                Some(witness) => witness,
+                None => return Ok(true),
+            };
+            // Witness is at least as good for all preferences
+            if witness.iter().zip(costs).all(|(w, c)| w <= c) {
+                return Ok(false);
+            }
+
+            let difference: Costs = witness.iter().zip(costs).map(|(w, c)| w - c).collect();
+            let lp = self.lp.as_mut().expect("checked above");
+            lp.add_constraint(&difference)?;
+            match lp.solve()? {
+                Some((pref, delta)) => {
+                    if delta < accuracy {
+                        return Ok(false);
+                    }
+                    // The LP did not move away from the last witness, so
+                    // rather keep the shortcut than loop forever
+                    if pref == alpha {
+                        return Ok(true);
+                    }
+                    alpha = pref;
+                }
+                None => return Ok(true),
+            }
+        }
+    }
+
+    /// Dijkstra from `source` to `target` over uncontracted nodes except
+    /// `node`. Returns the costs of a path not more expensive than `costs`
+    /// for `alpha`.
+    fn find_witness(
+        &mut self,
+        source: u32,
+        target: u32,
+        node: u32,
+        alpha: &[f64],
+        costs: &[f64],
+    ) -> Option<Costs> {
+        for &t in &self.touched {
+            self.dist[t] = f64::MAX;
+            self.prev[t] = None;
+        }
+        self.touched.clear();
+        self.heap.clear();
+
+        let bound = costs_by_alpha(costs, alpha);
+        self.dist[source] = 0.0;
+        self.touched.push(source);
+        self.heap.push(Reverse((OrderedFloat(0.0), source)));
+
+        let mut settled = 0;
+        while let Some(Reverse((OrderedFloat(dist), current))) = self.heap.pop() {
+            if dist > self.dist[current] {
+                continue;
+            }
+            if dist > bound || settled >= WITNESS_SETTLE_LIMIT {
+                return None;
+            }
+            if current == target {
+                let mut witness = vec![0.0; self.dim];
+                let mut cur_node = target;
+                while let Some(edge) = self.prev[cur_node] {
+                    witness = add_edge_costs(&witness, &self.edges[edge].edge_costs);
+                    cur_node = self.edges[edge].source_id;
+                }
+                return Some(witness);
+            }
+            settled += 1;
+
+            for &edge in &self.edges_out[current] {
+                let next = self.edges[edge].target_id;
+                if next == node || self.contracted[next] {
+                    continue;
+                }
+                let alt = dist + costs_by_alpha(&self.edges[edge].edge_costs, alpha);
+                if alt < self.dist[next] {
+                    if self.dist[next] == f64::MAX {
+                        self.touched.push(next);
+                    }
+                    self.dist[next] = alt;
+                    self.prev[next] = Some(edge);
+                    self.heap.push(Reverse((OrderedFloat(alt), next)));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dijkstra::{Dijkstra, NDijkstra};
+    use crate::graph::{parse_graph_file, Coordinates};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn contracted_graph_finds_all_shortest_paths() {
+        let graph = parse_graph_file("./src/test_graphs/testGraph").unwrap();
+        let mut lp = LpProcess::new(graph.dim()).unwrap();
+        let contracted = contract(&graph, &mut lp).unwrap();
+        assert!(contracted.validate().is_valid());
+
+        assert_eq!(contracted.nodes.len(), graph.nodes.len());
+        assert!(contracted.edges.len() >= graph.edges.len());
+
+        let mut dijkstra = NDijkstra::new(&contracted);
+        let mut dijkstra_ch = Dijkstra::new(&contracted);
+        let alphas = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+            [0.25, 0.25, 0.25, 0.25],
+            [0.1, 0.6, 0.2, 0.1],
+        ];
+
+        for alpha in &alphas {
+            for s in 0..(contracted.nodes.len() as u32) {
+                for t in 0..(contracted.nodes.len() as u32) {
+                    let ch_cost = dijkstra_ch.run(s, t, alpha).map(|r| r.total_cost);
+                    let n_cost = dijkstra.run(s, t, alpha);
+                    match (ch_cost, n_cost) {
+                        (Some(a), Some(b)) => assert!((a - b).abs() < 0.0001),
+                        (a, b) => assert_eq!(a, b),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn customized_graph_finds_all_shortest_paths() {
+        let graph = parse_graph_file("./src/test_graphs/testGraph").unwrap();
+        let mut lp = LpProcess::new(graph.dim()).unwrap();
+        let mut witnessed = contract(&graph, &mut lp).unwrap();
+        assert!(!witnessed.is_customizable());
+        assert!(witnessed.customize().is_err());
+
+        let mut contracted = contract_customizable(&graph).unwrap();
+        assert!(contracted.is_customizable());
+
+        // Replace a metric with costs unrelated to the old ones
+        let mut rng = StdRng::seed_from_u64(42);
+        contracted
+            .edges
+            .iter_mut()
+            .filter(|e| e.replaced_edges.is_none())
+            .for_each(|e| e.edge_costs[1] = rng.gen_range(0.0, 100.0));
+        contracted.customize().unwrap();
+
+        for e in contracted.edges.iter() {
+            let unpacked_costs = contracted
+                .unpack_edge(e.id)
+                .into_iter()
+                .fold(vec![0.0; contracted.dim()], |acc, edge| {
+                    add_edge_costs(&acc, &contracted.edges[edge].edge_costs)
+                });
+            assert_eq!(e.edge_costs, unpacked_costs);
+        }
+        for half_edge in contracted.half_edges_out.iter() {
+            assert_eq!(
+                half_edge.edge_costs,
+                contracted.edges[half_edge.edge_id].edge_costs
+            );
+        }
+
+        let mut dijkstra = NDijkstra::new(&contracted);
+        let mut dijkstra_ch = Dijkstra::new(&contracted);
+        for alpha in &[[0.0, 1.0, 0.0, 0.0], [0.1, 0.6, 0.2, 0.1]] {
+            for s in 0..(contracted.nodes.len() as u32) {
+                for t in 0..(contracted.nodes.len() as u32) {
+                    let ch_cost = dijkstra_ch.run(s, t, alpha).map(|r| r.total_cost);
+                    let n_cost = dijkstra.run(s, t, alpha);
+                    match (ch_cost, n_cost) {
+                        (Some(a), Some(b)) => assert!((a - b).abs() < 0.0001),
+                        (a, b) => assert_eq!(a, b),
+                    }
+                }
+            }
+        }
+
+        let original = contracted
+            .edges
+            .iter_mut()
+            .find(|e| e.replaced_edges.is_none())
+            .unwrap();
+        original.edge_costs.push(1.0);
+        assert!(contracted.customize().is_err());
+    }
+
+    #[test]
+    fn shortcut_geometry_is_unpacked() {
+        let mut graph = parse_graph_file("./src/test_graphs/testGraph").unwrap();
+        for n in graph.nodes.iter_mut() {
+            n.coordinates = Some(Coordinates::new(f64::from(n.id), 0.0));
+        }
+        for e in graph.edges.iter_mut() {
+            let (source, target) = (f64::from(e.source_id), f64::from(e.target_id));
+            e.geometry = Some(vec![
+                Coordinates::new(source, 0.0),
+                Coordinates::new((source + target) / 2.0, 1.0),
+                Coordinates::new(target, 0.0),
+            ]);
+        }
+        let mut lp = LpProcess::new(graph.dim()).unwrap();
+        let contracted = contract(&graph, &mut lp).unwrap();
+
+        for e in contracted.edges.iter() {
+            let geometry = contracted.edge_geometry(e.id).unwrap();
+            let unpacked = contracted.unpack_edge(e.id);
+            assert_eq!(geometry.len(), 2 * unpacked.len() + 1);
+            assert_eq!(Some(geometry[0]), contracted.nodes[e.source_id].coordinates);
+            assert_eq!(
+                geometry.last().copied(),
+                contracted.nodes[e.target_id].coordinates
+            );
+        }
+    }
+
+    #[test]
+    fn contracted_graph_is_rejected() {
+        let graph = parse_graph_file("./src/test_graphs/concTestGraph").unwrap();
+        let mut lp = LpProcess::new(graph.dim()).unwrap();
+        assert!(contract(&graph, &mut lp).is_err());
+    }
+}
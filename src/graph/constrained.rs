@@ -0,0 +1,184 @@
+use std::collections::BinaryHeap;
+
+use crate::graph::dijkstra::{metric_distances, Direction, Label, MinHeapItem};
+use crate::graph::path::{Path, PathSplit};
+use crate::graph::Graph;
+use crate::helpers::{add_edge_costs, costs_by_alpha, MyVec, ACCURACY};
+
+impl Graph {
+    /// Path from `from` to `to` with the lowest costs in the `objective`
+    /// metric whose costs in other metrics stay within `budgets`, given as
+    /// (metric, limit).
+    ///
+    /// Labels carry full cost vectors and are only dropped if another label
+    /// at the same node is at least as good in the objective and all
+    /// budgeted metrics. The exact distances to `to` in each metric prune
+    /// labels that cannot stay within a budget and guide the search, so the
+    /// first label to reach `to` is optimal. Shortcuts are followed like
+    /// any other edge and unpacked in the result.
+    pub fn find_constrained_path(
+        &self,
+        from: u32,
+        to: u32,
+        objective: usize,
+        budgets: &[(usize, f64)],
+    ) -> Option<Path> {
+        let to_target = metric_distances(self, to, Direction::BACKWARD);
+        let mut metrics = vec![objective];
+        metrics.extend(budgets.iter().map(|&(metric, _)| metric));
+
+        let fits = |node: u32, costs: &[f64]| {
+            to_target[objective][node as usize] < f64::MAX
+                && budgets.iter().all(|&(metric, limit)| {
+                    let bound = to_target[metric][node as usize];
+                    bound < f64::MAX && costs[metric] + bound <= limit + ACCURACY
+                })
+        };
+        let start = vec![0.0; self.dim()];
+        if !fits(from, &start) {
+            return None;
+        }
+
+        let mut labels = vec![Label::new(from, start, None)];
+        let mut bags: MyVec<Vec<usize>> = MyVec(vec![Vec::new(); self.nodes.len()]);
+        bags[from].push(0);
+        // Labels by objective costs plus the distance to the target
+        let mut heap = BinaryHeap::new();
+        heap.push(MinHeapItem::new(to_target[objective][from as usize], 0));
+
+        let dominates = |a: &[f64], b: &[f64]| metrics.iter().all(|&m| a[m] <= b[m]);
+        while let Some(MinHeapItem { value: label, .. }) = heap.pop() {
+            if labels[label].dominated {
+                continue;
+            }
+            let node = labels[label].node;
+            if node == to {
+                return Some(self.constrained_path(&labels, label, from, objective));
+            }
+
+            for half_edge in self.get_ch_edges_out(node) {
+                let next = half_edge.target_id;
+                let costs = add_edge_costs(&labels[label].costs, &half_edge.edge_costs);
+                if !fits(next, &costs) {
+                    continue;
+                }
+                if bags[next]
+                    .iter()
+                    .any(|&l| dominates(&labels[l].costs, &costs))
+                {
+                    continue;
+                }
+
+                let key = costs[objective] + to_target[objective][next as usize];
+                let new_label = Label::new(next, costs, Some((label, half_edge.edge_id)));
+                let id = Label::insert(&mut labels, &mut bags[next], new_label, dominates);
+                heap.push(MinHeapItem::new(key, id));
+            }
+        }
+        None
+    }
+
+    fn constrained_path(
+        &self,
+        labels: &[Label],
+        label: usize,
+        from: u32,
+        objective: usize,
+    ) -> Path {
+        let (_, edges) = Label::trace(labels, label);
+        let edges: Vec<u32> = edges
+            .into_iter()
+            .flat_map(|edge| self.unpacked_edges(edge))
+            .collect();
+
+        let mut nodes = vec![from];
+        nodes.extend(edges.iter().map(|&edge| self.edges[edge].target_id));
+        let mut alpha = vec![0.0; self.dim()];
+        alpha[objective] = 1.0;
+        let costs = labels[label].costs.clone();
+
+        Path {
+            id: vec![(None, 0)],
+            nodes: MyVec(nodes),
+            user_split: PathSplit {
+                cuts: MyVec(vec![edges.len() as u32]),
+                costs_by_alpha: MyVec(vec![costs_by_alpha(&costs, &alpha)]),
+                alphas: MyVec(vec![alpha]),
+                dimension_costs: MyVec(vec![costs.clone()]),
+            },
+            edges: MyVec(edges),
+            algo_split: None,
+            total_dimension_costs: costs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::parse_graph_file;
+    use crate::graph::test_helpers::simple_path_costs;
+    use crate::helpers::{add_edge_costs, Costs, ACCURACY};
+
+    #[test]
+    fn constrained_paths_match_enumeration() {
+        let graph = parse_graph_file("./src/test_graphs/concTestGraph").unwrap();
+        let mut found_paths = 0;
+        // Budgets that rule out the path with the best objective
+        let mut binding_budgets = 0;
+        for s in 0..graph.nodes.len() as u32 {
+            for t in 0..graph.nodes.len() as u32 {
+                let paths = simple_path_costs(&graph, s, t);
+                for (objective, budgets) in budget_cases(&paths) {
+                    let fits = |c: &Costs| budgets.iter().all(|&(m, l)| c[m] <= l + ACCURACY);
+                    let best = paths.iter().map(|c| c[objective]).fold(f64::MAX, f64::min);
+                    let expected = paths
+                        .iter()
+                        .filter(|c| fits(c))
+                        .map(|c| c[objective])
+                        .fold(f64::MAX, f64::min);
+
+                    let path = match graph.find_constrained_path(s, t, objective, &budgets) {
+                        Some(path) => path,
+                        None => {
+                            assert_eq!(expected, f64::MAX);
+                            continue;
+                        }
+                    };
+                    found_paths += 1;
+                    if expected > best + ACCURACY {
+                        binding_budgets += 1;
+                    }
+                    let costs = &path.total_dimension_costs;
+                    assert!((costs[objective] - expected).abs() < ACCURACY);
+                    assert!(fits(costs));
+                    assert_eq!(path.nodes.first(), Some(&s));
+                    assert_eq!(path.nodes.last(), Some(&t));
+                    let summed = path.edges.iter().fold(vec![0.0; graph.dim()], |sum, &e| {
+                        assert!(graph.edges[e].replaced_edges.is_none());
+                        add_edge_costs(&sum, &graph.edges[e].edge_costs)
+                    });
+                    assert!(summed
+                        .iter()
+                        .zip(costs)
+                        .all(|(a, b)| (a - b).abs() < ACCURACY));
+                }
+            }
+        }
+        assert!(found_paths > 0);
+        assert!(binding_budgets > 0);
+    }
+
+    /// Objectives with budgets at the costs of each path. In concTestGraph
+    /// metrics 0 and 1 are lengths, while 2 and 3 count the edges.
+    fn budget_cases(paths: &[Costs]) -> Vec<(usize, Vec<(usize, f64)>)> {
+        let mut cases = Vec::new();
+        for &(objective, metric) in &[(0, 2), (2, 0)] {
+            let limits = paths.iter().map(|c| c[metric]).chain(Some(0.0));
+            for limit in limits {
+                cases.push((objective, vec![(metric, limit)]));
+                cases.push((objective, vec![(metric, limit), (1, limit)]));
+            }
+        }
+        cases
+    }
+}
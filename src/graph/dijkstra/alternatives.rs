@@ -0,0 +1,397 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use super::many_to_many::UpwardSearch;
+use super::state::Direction::{BACKWARD, FORWARD};
+use super::state::State;
+use super::Dijkstra;
+use crate::graph::path::{Path, PathSplit};
+use crate::graph::Graph;
+use crate::helpers::{add_edge_costs, costs_by_alpha, Costs, MyVec, ACCURACY};
+
+use ordered_float::OrderedFloat;
+
+/// Quality criteria for via-node alternatives. All of them are relative to
+/// the cost of the optimal path under the preference of the query.
+#[derive(Debug, Clone, Copy)]
+pub struct AlternativeFilters {
+    /// Largest part of an alternative that may be shared with the optimal
+    /// path and the alternatives accepted before it
+    pub max_sharing: f64,
+    /// Largest detour compared to the optimal path
+    pub max_stretch: f64,
+    /// Length of the subpath around the via node that has to be optimal
+    pub local_optimality: f64,
+}
+
+impl Default for AlternativeFilters {
+    fn default() -> Self {
+        AlternativeFilters {
+            max_sharing: 0.8,
+            max_stretch: 0.25,
+            local_optimality: 0.25,
+        }
+    }
+}
+
+/// The optimal path from `from` to `to` under `alpha`, followed by up to
+/// `count` alternatives that pass `filters`.
+///
+/// Candidates are the concatenations of the optimal paths to and from the
+/// nodes in both upward search spaces of the hierarchy, tried from the
+/// cheapest one. Local optimality is checked with `dijkstra` on the subpath
+/// around the via node (the T-test).
+pub fn via_node_alternatives(
+    graph: &Graph,
+    dijkstra: &mut Dijkstra,
+    from: u32,
+    to: u32,
+    alpha: &[f64],
+    count: usize,
+    filters: &AlternativeFilters,
+) -> Vec<Path> {
+    let mut forward = UpwardSearch::new(graph);
+    let mut backward = UpwardSearch::new(graph);
+    let forward_space = forward.run(from, FORWARD, alpha);
+    let backward_space = backward.run(to, BACKWARD, alpha);
+
+    let backward_index: HashMap<u32, usize> = backward_space
+        .iter()
+        .enumerate()
+        .map(|(j, (node, _, _))| (*node, j))
+        .collect();
+    // (total cost, forward index, backward index) of every via node
+    let mut candidates: Vec<(f64, usize, usize)> = forward_space
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (node, total, _))| {
+            let &j = backward_index.get(node)?;
+            Some((total + backward_space[j].1, i, j))
+        })
+        .collect();
+    candidates.sort_by_key(|&(total, _, _)| OrderedFloat(total));
+
+    let via_path = |&(_, i, j): &(f64, usize, usize)| -> Vec<u32> {
+        let mut edges = forward.edges_to(i);
+        edges.extend(backward.edges_to(j).into_iter().rev());
+        edges
+            .into_iter()
+            .flat_map(|edge| graph.unpacked_edges(edge))
+            .collect()
+    };
+
+    let optimal_cost = match candidates.first() {
+        Some(&(total, _, _)) => total,
+        None => return Vec::new(),
+    };
+    let mut accepted = vec![via_path(&candidates[0])];
+    let mut shared_edges: HashSet<u32> = accepted[0].iter().copied().collect();
+    for candidate in &candidates[1..] {
+        if accepted.len() > count || candidate.0 > (1.0 + filters.max_stretch) * optimal_cost {
+            break;
+        }
+        let edges = via_path(candidate);
+        let nodes = path_nodes(graph, from, &edges);
+        let mut seen = HashSet::new();
+        if !nodes.iter().all(|&node| seen.insert(node)) {
+            continue;
+        }
+
+        let shared: f64 = edges
+            .iter()
+            .filter(|edge| shared_edges.contains(edge))
+            .map(|&edge| costs_by_alpha(&graph.edges[edge].edge_costs, alpha))
+            .sum();
+        if shared > filters.max_sharing * optimal_cost {
+            continue;
+        }
+
+        let via = forward_space[candidate.1].0;
+        let radius = filters.local_optimality * optimal_cost;
+        if !is_locally_optimal(graph, dijkstra, &edges, &nodes, via, alpha, radius) {
+            continue;
+        }
+
+        shared_edges.extend(&edges);
+        accepted.push(edges);
+    }
+
+    accepted
+        .into_iter()
+        .map(|edges| to_path(graph, from, edges, alpha))
+        .collect()
+}
+
+/// The `count` best simple paths from `from` to `to` under `alpha`, by
+/// Yen's algorithm on the original edges.
+pub fn k_shortest_paths(
+    graph: &Graph,
+    from: u32,
+    to: u32,
+    alpha: &[f64],
+    count: usize,
+) -> Vec<Path> {
+    let no_edges = HashSet::new();
+    let no_nodes = HashSet::new();
+    let mut found = match restricted_path(graph, from, to, alpha, &no_edges, &no_nodes) {
+        Some(edges) if count > 0 => vec![edges],
+        _ => return Vec::new(),
+    };
+    let mut candidates: Vec<(f64, Vec<u32>)> = Vec::new();
+
+    while found.len() < count {
+        let last = &found[found.len() - 1];
+        let nodes = path_nodes(graph, from, last);
+        for i in 0..last.len() {
+            // Deviate at the i-th node from every path found with this root
+            let root = &last[..i];
+            let banned_edges = found
+                .iter()
+                .filter(|path| path.len() > i && &path[..i] == root)
+                .map(|path| path[i])
+                .collect();
+            let banned_nodes = nodes[..i].iter().copied().collect();
+            let spur = restricted_path(graph, nodes[i], to, alpha, &banned_edges, &banned_nodes);
+            if let Some(spur) = spur {
+                let mut edges = root.to_vec();
+                edges.extend(spur);
+                if !candidates.iter().any(|(_, c)| c == &edges) {
+                    let total = edges
+                        .iter()
+                        .map(|&edge| costs_by_alpha(&graph.edges[edge].edge_costs, alpha))
+                        .sum();
+                    candidates.push((total, edges));
+                }
+            }
+        }
+
+        let best = candidates
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (total, _))| OrderedFloat(*total))
+            .map(|(index, _)| index);
+        match best {
+            Some(index) => found.push(candidates.swap_remove(index).1),
+            None => break,
+        }
+    }
+
+    found
+        .into_iter()
+        .map(|edges| to_path(graph, from, edges, alpha))
+        .collect()
+}
+
+/// Best path over original edges that avoids the banned edges and nodes.
+fn restricted_path(
+    graph: &Graph,
+    from: u32,
+    to: u32,
+    alpha: &[f64],
+    banned_edges: &HashSet<u32>,
+    banned_nodes: &HashSet<u32>,
+) -> Option<Vec<u32>> {
+    let mut dist: HashMap<u32, f64> = HashMap::new();
+    let mut prev: HashMap<u32, u32> = HashMap::new();
+    let mut settled: HashSet<u32> = HashSet::new();
+    let mut heap = BinaryHeap::new();
+    dist.insert(from, 0.0);
+    heap.push(State::new(from, FORWARD));
+
+    while let Some(State {
+        node_id: node,
+        total_cost,
+        ..
+    }) = heap.pop()
+    {
+        if !settled.insert(node) {
+            continue;
+        }
+        if node == to {
+            let mut edges = Vec::new();
+            let mut current = to;
+            while let Some(&edge) = prev.get(&current) {
+                edges.push(edge);
+                current = graph.edges[edge].source_id;
+            }
+            edges.reverse();
+            return Some(edges);
+        }
+
+        for half_edge in graph.get_ch_edges_out(node) {
+            let next = half_edge.target_id;
+            if graph.edges[half_edge.edge_id].replaced_edges.is_some()
+                || banned_edges.contains(&half_edge.edge_id)
+                || banned_nodes.contains(&next)
+            {
+                continue;
+            }
+            let total = total_cost + costs_by_alpha(&half_edge.edge_costs, alpha);
+            if dist.get(&next).map_or(true, |&d| total < d) {
+                dist.insert(next, total);
+                prev.insert(next, half_edge.edge_id);
+                heap.push(State {
+                    node_id: next,
+                    total_cost: total,
+                    direction: FORWARD,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Whether the subpath reaching `radius` to both sides of `via` is optimal.
+fn is_locally_optimal(
+    graph: &Graph,
+    dijkstra: &mut Dijkstra,
+    edges: &[u32],
+    nodes: &[u32],
+    via: u32,
+    alpha: &[f64],
+    radius: f64,
+) -> bool {
+    let costs: Vec<f64> = edges
+        .iter()
+        .map(|&edge| costs_by_alpha(&graph.edges[edge].edge_costs, alpha))
+        .collect();
+    let position = nodes.iter().position(|&node| node == via).unwrap();
+
+    let (mut start, mut end) = (position, position);
+    let mut covered = 0.0;
+    while start > 0 && covered < radius {
+        start -= 1;
+        covered += costs[start];
+    }
+    covered = 0.0;
+    while end < edges.len() && covered < radius {
+        covered += costs[end];
+        end += 1;
+    }
+    if start == end {
+        return true;
+    }
+
+    let subpath_cost: f64 = costs[start..end].iter().sum();
+    match dijkstra.run(nodes[start], nodes[end], alpha) {
+        Some(result) => subpath_cost <= result.total_cost * (1.0 + ACCURACY) + ACCURACY,
+        None => false,
+    }
+}
+
+fn path_nodes(graph: &Graph, from: u32, edges: &[u32]) -> Vec<u32> {
+    let mut nodes = vec![from];
+    nodes.extend(edges.iter().map(|&edge| graph.edges[edge].target_id));
+    nodes
+}
+
+fn to_path(graph: &Graph, from: u32, edges: Vec<u32>, alpha: &[f64]) -> Path {
+    let nodes = path_nodes(graph, from, &edges);
+    let costs: Costs = edges.iter().fold(vec![0.0; graph.dim()], |sum, &edge| {
+        add_edge_costs(&sum, &graph.edges[edge].edge_costs)
+    });
+
+    Path {
+        id: vec![(None, 0)],
+        nodes: MyVec(nodes),
+        user_split: PathSplit {
+            cuts: MyVec(vec![edges.len() as u32]),
+            alphas: MyVec(vec![alpha.to_vec()]),
+            dimension_costs: MyVec(vec![costs.clone()]),
+            costs_by_alpha: MyVec(vec![costs_by_alpha(&costs, alpha)]),
+        },
+        edges: MyVec(edges),
+        algo_split: None,
+        total_dimension_costs: costs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dijkstra::NDijkstra;
+    use crate::graph::parse_graph_file;
+    use crate::graph::test_helpers::simple_path_costs;
+
+    #[test]
+    fn k_shortest_paths_match_enumeration() {
+        let graph = parse_graph_file("./src/test_graphs/concTestGraph").unwrap();
+        let mut dijkstra = NDijkstra::new(&graph);
+        for alpha in &[[0.25, 0.25, 0.25, 0.25], [0.7, 0.1, 0.1, 0.1]] {
+            for s in 0..graph.nodes.len() as u32 {
+                for t in 0..graph.nodes.len() as u32 {
+                    let paths = k_shortest_paths(&graph, s, t, alpha, 4);
+                    let mut expected: Vec<_> = simple_path_costs(&graph, s, t)
+                        .iter()
+                        .map(|costs| costs_by_alpha(costs, alpha))
+                        .collect();
+                    expected.sort_by_key(|&c| OrderedFloat(c));
+                    expected.truncate(4);
+                    let found: Vec<_> = paths
+                        .iter()
+                        .map(|p| costs_by_alpha(&p.total_dimension_costs, alpha))
+                        .collect();
+                    assert_eq!(found.len(), expected.len());
+                    for (found, expected) in found.iter().zip(&expected) {
+                        assert!((found - expected).abs() < ACCURACY);
+                    }
+                    if let Some(best) = dijkstra.run(s, t, alpha) {
+                        assert!((found[0] - best).abs() < ACCURACY);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn alternatives_pass_the_filters() {
+        let graph = parse_graph_file("./src/test_graphs/concTestGraph").unwrap();
+        let mut dijkstra = Dijkstra::new(&graph);
+        let mut n_dijkstra = NDijkstra::new(&graph);
+        let alpha = [0.25, 0.25, 0.25, 0.25];
+        let filters = AlternativeFilters {
+            max_sharing: 0.9,
+            max_stretch: 0.5,
+            local_optimality: 0.1,
+        };
+
+        let mut found_alternatives = false;
+        for s in 0..graph.nodes.len() as u32 {
+            for t in 0..graph.nodes.len() as u32 {
+                let paths = via_node_alternatives(&graph, &mut dijkstra, s, t, &alpha, 3, &filters);
+                let optimal = match n_dijkstra.run(s, t, &alpha) {
+                    Some(optimal) => optimal,
+                    None => {
+                        assert!(paths.is_empty());
+                        continue;
+                    }
+                };
+                assert!(paths.len() <= 4);
+                found_alternatives |= paths.len() > 1;
+
+                let mut shared_edges = HashSet::new();
+                for (i, path) in paths.iter().enumerate() {
+                    assert_eq!(path.nodes.first(), Some(&s));
+                    assert_eq!(path.nodes.last(), Some(&t));
+                    let mut seen = HashSet::new();
+                    assert!(path.nodes.iter().all(|&node| seen.insert(node)));
+
+                    let cost = costs_by_alpha(&path.total_dimension_costs, &alpha);
+                    if i == 0 {
+                        assert!((cost - optimal).abs() < ACCURACY);
+                    } else {
+                        assert!(cost <= (1.0 + filters.max_stretch) * optimal + ACCURACY);
+                        let shared: f64 = path
+                            .edges
+                            .iter()
+                            .filter(|edge| shared_edges.contains(*edge))
+                            .map(|&edge| costs_by_alpha(&graph.edges[edge].edge_costs, &alpha))
+                            .sum();
+                        assert!(shared <= filters.max_sharing * optimal + ACCURACY);
+                    }
+                    shared_edges.extend(path.edges.iter().copied());
+                }
+            }
+        }
+        assert!(found_alternatives);
+    }
+}
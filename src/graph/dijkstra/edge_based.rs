@@ -0,0 +1,155 @@
+use super::state::MinHeapItem;
+use crate::graph::{turns::Turns, Graph};
+use crate::helpers::{add_edge_costs, costs_by_alpha, equal_weights, Costs, MyVec, Preference};
+
+use std::collections::BinaryHeap;
+
+/// Dijkstra that settles edges instead of nodes, so the turns between
+/// consecutive edges can be restricted and priced. Shortcuts are ignored,
+/// because the contraction does not know about turns.
+///
+/// Like `NDijkstra`, a search is continued if the next query starts at the
+/// same node with the same preference.
+pub struct EdgeSearch<'a> {
+    g: &'a Graph,
+    turns: &'a Turns,
+    // Best dist to the end of an edge, including its own costs
+    dist: MyVec<f64>,
+    prev: MyVec<Option<u32>>,
+    // First settled edge into a node
+    reached_by: MyVec<Option<u32>>,
+    // Edges with the one before them
+    heap: BinaryHeap<MinHeapItem<(u32, Option<u32>)>>,
+    touched_edges: Vec<u32>,
+    touched_nodes: Vec<u32>,
+    last_from: u32,
+    last_pref: Preference,
+}
+
+impl<'a> EdgeSearch<'a> {
+    pub fn new(g: &'a Graph, turns: &'a Turns) -> Self {
+        EdgeSearch {
+            g,
+            turns,
+            dist: vec![f64::MAX; g.edges.len()].into(),
+            prev: vec![None; g.edges.len()].into(),
+            reached_by: vec![None; g.nodes.len()].into(),
+            heap: BinaryHeap::new(),
+            touched_edges: Vec::new(),
+            touched_nodes: Vec::new(),
+            last_from: u32::MAX,
+            last_pref: equal_weights(g.dim()),
+        }
+    }
+
+    fn reset_state(&mut self) {
+        for &e in &self.touched_edges {
+            self.dist[e] = f64::MAX;
+            self.prev[e] = None;
+        }
+        for &n in &self.touched_nodes {
+            self.reached_by[n] = None;
+        }
+        self.heap.clear();
+        self.touched_edges.clear();
+        self.touched_nodes.clear();
+    }
+
+    pub fn run(&mut self, from: u32, to: u32, alpha: &[f64]) -> Option<f64> {
+        let reuse = self.last_from == from && self.last_pref == alpha;
+        if !reuse {
+            self.last_from = from;
+            self.last_pref = alpha.to_vec();
+            self.reset_state();
+
+            for half_edge in self.g.get_ch_edges_out(from) {
+                if self.g.edges[half_edge.edge_id].replaced_edges.is_none() {
+                    let dist = costs_by_alpha(&half_edge.edge_costs, alpha);
+                    self.heap
+                        .push(MinHeapItem::new(dist, (half_edge.edge_id, None)));
+                }
+            }
+        }
+        // The empty path wins even if a loop already reached `to`
+        if from == to {
+            return Some(0.0);
+        }
+        if let Some(edge) = self.reached_by[to] {
+            return Some(self.dist[edge]);
+        }
+
+        while let Some(MinHeapItem {
+            key: dist,
+            value: (edge, prev_edge),
+        }) = self.heap.pop()
+        {
+            if dist >= self.dist[edge] {
+                continue;
+            }
+            self.dist[edge] = dist;
+            self.prev[edge] = prev_edge;
+            self.touched_edges.push(edge);
+
+            let current = &self.g.edges[edge];
+            let node = current.target_id;
+            if self.reached_by[node].is_none() {
+                self.reached_by[node] = Some(edge);
+                self.touched_nodes.push(node);
+            }
+
+            for half_edge in self.g.get_ch_edges_out(node) {
+                let next = &self.g.edges[half_edge.edge_id];
+                if next.replaced_edges.is_some() || !self.turns.is_allowed(current, next) {
+                    continue;
+                }
+                let turn_cost = self
+                    .turns
+                    .costs(edge, next.id)
+                    .map_or(0.0, |costs| costs_by_alpha(costs, alpha));
+                let alt = dist + turn_cost + costs_by_alpha(&next.edge_costs, alpha);
+                if alt < self.dist[next.id] {
+                    self.heap.push(MinHeapItem::new(alt, (next.id, Some(edge))));
+                }
+            }
+            // Checked last to leave the heap in a consistent state, see
+            // `NDijkstra::run`
+            if node == to {
+                return Some(dist);
+            }
+        }
+
+        None
+    }
+
+    pub fn turns(&self) -> &'a Turns {
+        self.turns
+    }
+
+    /// Edges of the best path from the last source to `to` and their costs,
+    /// including turn costs.
+    pub fn path(&mut self, to: u32) -> Option<(MyVec<u32>, Costs)> {
+        if to == self.last_from {
+            return Some((MyVec::new(), vec![0.0; self.g.dim()]));
+        }
+        if self.reached_by[to].is_none() {
+            let alpha = self.last_pref.clone();
+            self.run(self.last_from, to, &alpha);
+        }
+
+        let mut edges = MyVec::new();
+        let mut costs = vec![0.0; self.g.dim()];
+        let mut current = self.reached_by[to];
+        while let Some(edge) = current {
+            edges.push(edge);
+            costs = add_edge_costs(&self.g.edges[edge].edge_costs, &costs);
+            current = self.prev[edge];
+        }
+        if edges.is_empty() {
+            return None;
+        }
+        edges.reverse();
+        costs = add_edge_costs(&self.turns.path_costs(self.g, &edges), &costs);
+
+        Some((edges, costs))
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::BinaryHeap;
+
+use super::state::Direction::{self, BACKWARD, FORWARD};
+use super::state::State;
+use crate::graph::Graph;
+
+/// Landmarks with their distances to and from every node in each metric,
+/// for lower bounds by the triangle inequality (ALT).
+///
+/// For one metric, `d(v, t) >= d(l, t) - d(l, v)` and
+/// `d(v, t) >= d(v, l) - d(t, l)` hold for every landmark `l`. As the cost
+/// of a path under a preference is the weighted sum of its metric costs,
+/// the weighted sum of the per-metric bounds is a lower bound for every
+/// preference, so the distances only have to be computed once.
+///
+/// This needs `2 * landmarks * nodes * dim` floats of memory.
+#[derive(Debug, Clone)]
+pub struct Landmarks {
+    nodes: Vec<u32>,
+    node_count: usize,
+    dim: usize,
+    // Distances from and to each landmark by landmark, node and metric
+    from_landmark: Vec<f64>,
+    to_landmark: Vec<f64>,
+}
+
+impl Landmarks {
+    /// Selects `count` landmarks greedily, each one as far as possible from
+    /// the ones before, measured by the sum of its metrics. The search for
+    /// the first one starts at node 0.
+    pub fn select(graph: &Graph, count: usize) -> Landmarks {
+        let mut landmarks = Landmarks::new(graph, &[]);
+        if graph.nodes.is_empty() {
+            return landmarks;
+        }
+
+        // Distance of every node to the closest landmark so far
+        let mut closest = vec![f64::MAX; graph.nodes.len()];
+        update_closest(&mut closest, &metric_distances(graph, 0, FORWARD));
+
+        for _ in 0..count {
+            let next = closest
+                .iter()
+                .enumerate()
+                .filter(|&(_, &d)| d < f64::MAX && d > 0.0)
+                .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+                .map(|(node, _)| node as u32);
+            let next = match next {
+                Some(next) => next,
+                None => break,
+            };
+
+            let from_landmark = metric_distances(graph, next, FORWARD);
+            if landmarks.nodes.is_empty() {
+                // Node 0 was only the starting point
+                closest.iter_mut().for_each(|c| *c = f64::MAX);
+            }
+            update_closest(&mut closest, &from_landmark);
+            landmarks.push(
+                next,
+                &from_landmark,
+                &metric_distances(graph, next, BACKWARD),
+            );
+        }
+
+        landmarks
+    }
+
+    /// Precomputes the distances of the given landmark nodes.
+    pub fn new(graph: &Graph, nodes: &[u32]) -> Landmarks {
+        let mut landmarks = Landmarks {
+            nodes: Vec::new(),
+            node_count: graph.nodes.len(),
+            dim: graph.dim(),
+            from_landmark: Vec::new(),
+            to_landmark: Vec::new(),
+        };
+        for &node in nodes {
+            landmarks.push(
+                node,
+                &metric_distances(graph, node, FORWARD),
+                &metric_distances(graph, node, BACKWARD),
+            );
+        }
+        landmarks
+    }
+
+    pub fn nodes(&self) -> &[u32] {
+        &self.nodes
+    }
+
+    /// Lower bound of the cost of any path from `from` to `to` under
+    /// `alpha`, which has to be non-negative.
+    pub fn lower_bound(&self, from: u32, to: u32, alpha: &[f64]) -> f64 {
+        // Called for every relaxed edge, so the best bound per metric is
+        // found without collecting the bounds first
+        let mut total = 0.0;
+        for (metric, a) in alpha.iter().enumerate().filter(|&(_, &a)| a > 0.0) {
+            let mut bound: f64 = 0.0;
+            for landmark in 0..self.nodes.len() {
+                let forward = |node| self.distance(&self.from_landmark, landmark, node, metric);
+                let backward = |node| self.distance(&self.to_landmark, landmark, node, metric);
+                for &(m, s) in &[(forward(to), forward(from)), (backward(from), backward(to))] {
+                    if m < f64::MAX && s < f64::MAX {
+                        bound = bound.max(m - s);
+                    }
+                }
+            }
+            total += a * bound;
+        }
+        total
+    }
+
+    fn distance(&self, table: &[f64], landmark: usize, node: u32, metric: usize) -> f64 {
+        table[(landmark * self.node_count + node as usize) * self.dim + metric]
+    }
+
+    fn push(&mut self, node: u32, from_landmark: &[Vec<f64>], to_landmark: &[Vec<f64>]) {
+        self.nodes.push(node);
+        for v in 0..self.node_count {
+            self.from_landmark
+                .extend(from_landmark.iter().map(|distances| distances[v]));
+            self.to_landmark
+                .extend(to_landmark.iter().map(|distances| distances[v]));
+        }
+    }
+}
+
+fn update_closest(closest: &mut [f64], distances: &[Vec<f64>]) {
+    for (node, closest) in closest.iter_mut().enumerate() {
+        if distances.iter().all(|d| d[node] < f64::MAX) {
+            let sum: f64 = distances.iter().map(|d| d[node]).sum();
+            *closest = closest.min(sum);
+        }
+    }
+}
+
+/// Distances from (or to) `start` to all nodes, for each metric separately.
+pub(in crate::graph) fn metric_distances(
+    graph: &Graph,
+    start: u32,
+    direction: Direction,
+) -> Vec<Vec<f64>> {
+    (0..graph.dim())
+        .map(|metric| {
+            let mut dist = vec![f64::MAX; graph.nodes.len()];
+            let mut heap = BinaryHeap::new();
+            dist[start as usize] = 0.0;
+            heap.push(State::new(start, direction));
+
+            while let Some(state) = heap.pop() {
+                let node = state.node_id;
+                if state.total_cost > dist[node as usize] {
+                    continue;
+                }
+                let edges = match direction {
+                    FORWARD => graph.get_ch_edges_out(node),
+                    BACKWARD => graph.get_ch_edges_in(node),
+                };
+                for half_edge in edges {
+                    let next = half_edge.target_id as usize;
+                    let total = state.total_cost + half_edge.edge_costs[metric];
+                    if total < dist[next] {
+                        dist[next] = total;
+                        heap.push(State {
+                            node_id: half_edge.target_id,
+                            total_cost: total,
+                            direction,
+                        });
+                    }
+                }
+            }
+            dist
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dijkstra::NDijkstra;
+    use crate::graph::test_helpers::{node_pairs, test_graphs};
+
+    #[test]
+    fn a_star_matches_dijkstra() {
+        for graph in &test_graphs() {
+            let landmarks = Landmarks::select(graph, 3);
+            assert!(!landmarks.nodes().is_empty());
+            let mut dijkstra = NDijkstra::new(graph);
+            let mut alt = NDijkstra::with_landmarks(graph, &landmarks);
+
+            let mut alpha = vec![0.0; graph.dim()];
+            alpha[0] = 1.0;
+            for alpha in &[alpha, vec![1.0 / graph.dim() as f64; graph.dim()]] {
+                for (s, t) in node_pairs(graph) {
+                    let expected = dijkstra.run(s, t, alpha);
+                    let found = alt.run(s, t, alpha);
+                    assert_eq!(found.is_some(), expected.is_some());
+                    if let (Some(found), Some(expected)) = (found, expected) {
+                        assert!((found - expected).abs() < 1e-9);
+                        assert!(landmarks.lower_bound(s, t, alpha) <= expected + 1e-9);
+                        let path = alt.path(t).unwrap();
+                        assert_eq!(path.nodes.first(), Some(&s));
+                        assert_eq!(path.nodes.last(), Some(&t));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::BinaryHeap;
+
+use super::state::Direction::{self, BACKWARD, FORWARD};
+use super::state::State;
+use crate::graph::Graph;
+use crate::helpers::{add_edge_costs, costs_by_alpha, Costs, MyVec};
+
+/// Costs of the best paths from every source to every target under one
+/// preference, as computed by `many_to_many`.
+#[derive(Debug, Clone)]
+pub struct CostMatrix {
+    pub sources: Vec<u32>,
+    pub targets: Vec<u32>,
+    // (total cost, cost vector) by source and target
+    entries: Vec<Option<(f64, Costs)>>,
+}
+
+impl CostMatrix {
+    /// Cost vector of the best path from `sources[source]` to
+    /// `targets[target]`, if there is one.
+    pub fn costs(&self, source: usize, target: usize) -> Option<&Costs> {
+        self.entry(source, target).map(|(_, costs)| costs)
+    }
+
+    /// Cost of the best path under the preference of the query.
+    pub fn total_cost(&self, source: usize, target: usize) -> Option<f64> {
+        self.entry(source, target).map(|&(total, _)| total)
+    }
+
+    fn entry(&self, source: usize, target: usize) -> Option<&(f64, Costs)> {
+        self.entries[source * self.targets.len() + target].as_ref()
+    }
+}
+
+/// Best paths between all `sources` and `targets` under `alpha`, using
+/// buckets: every target stores its backward search space in buckets at the
+/// nodes it settles, the forward search of every source then only scans the
+/// buckets of its own search space. This needs one upward search per source
+/// and target instead of one query per pair.
+pub fn many_to_many(graph: &Graph, sources: &[u32], targets: &[u32], alpha: &[f64]) -> CostMatrix {
+    let mut search = UpwardSearch::new(graph);
+
+    // (target index, total cost, cost vector) by node
+    let mut buckets: MyVec<Vec<(usize, f64, Costs)>> = MyVec(vec![Vec::new(); graph.nodes.len()]);
+    for (j, &target) in targets.iter().enumerate() {
+        for (node, total, costs) in search.run(target, BACKWARD, alpha) {
+            buckets[node].push((j, total, costs));
+        }
+    }
+
+    let mut entries: Vec<Option<(f64, Costs)>> = vec![None; sources.len() * targets.len()];
+    for (i, &source) in sources.iter().enumerate() {
+        let row = &mut entries[i * targets.len()..(i + 1) * targets.len()];
+        for (node, total, costs) in search.run(source, FORWARD, alpha) {
+            for (j, other_total, other_costs) in buckets[node].iter() {
+                let merged = total + other_total;
+                if row[*j].as_ref().map_or(true, |(best, _)| merged < *best) {
+                    row[*j] = Some((merged, add_edge_costs(&costs, other_costs)));
+                }
+            }
+        }
+    }
+
+    CostMatrix {
+        sources: sources.to_vec(),
+        targets: targets.to_vec(),
+        entries,
+    }
+}
+
+/// Cost vectors of the best paths from `source` to each of `targets`.
+pub fn one_to_many(
+    graph: &Graph,
+    source: u32,
+    targets: &[u32],
+    alpha: &[f64],
+) -> Vec<Option<Costs>> {
+    let matrix = many_to_many(graph, &[source], targets, alpha);
+    (0..targets.len())
+        .map(|j| matrix.costs(0, j).cloned())
+        .collect()
+}
+
+/// Dijkstra that only follows edges upwards in the hierarchy, like each
+/// half of the bidirectional `Dijkstra`, but without stopping early.
+pub(super) struct UpwardSearch<'a> {
+    graph: &'a Graph,
+    dist: MyVec<f64>,
+    settled: MyVec<bool>,
+    touched: Vec<u32>,
+    // Search space index of the previous node and the edge from it, by
+    // search space index of the last run
+    parents: Vec<Option<(usize, u32)>>,
+}
+
+impl<'a> UpwardSearch<'a> {
+    pub(super) fn new(graph: &'a Graph) -> Self {
+        UpwardSearch {
+            graph,
+            dist: MyVec(vec![f64::MAX; graph.nodes.len()]),
+            settled: MyVec(vec![false; graph.nodes.len()]),
+            touched: Vec::new(),
+            parents: Vec::new(),
+        }
+    }
+
+    /// Search space of `start` as (node, total cost, cost vector).
+    pub(super) fn run(
+        &mut self,
+        start: u32,
+        direction: Direction,
+        alpha: &[f64],
+    ) -> Vec<(u32, f64, Costs)> {
+        for &node in &self.touched {
+            self.dist[node] = f64::MAX;
+            self.settled[node] = false;
+        }
+        self.touched.clear();
+        self.parents.clear();
+
+        let graph = self.graph;
+        let mut space: Vec<(u32, f64, Costs)> = Vec::new();
+        // states with the search space index of their previous node and the
+        // edge from it
+        let mut heap: BinaryHeap<(State, Option<(usize, u32)>)> = BinaryHeap::new();
+        heap.push((State::new(start, direction), None));
+        self.dist[start] = 0.0;
+        self.touched.push(start);
+
+        while let Some((state, parent)) = heap.pop() {
+            let node = state.node_id;
+            if self.settled[node] || state.total_cost > self.dist[node] {
+                continue;
+            }
+            let costs = match parent {
+                Some((index, edge)) => {
+                    add_edge_costs(&space[index].2, &graph.edges[edge].edge_costs)
+                }
+                None => vec![0.0; graph.dim()],
+            };
+            self.settled[node] = true;
+            space.push((node, state.total_cost, costs));
+            self.parents.push(parent);
+
+            let edges = match direction {
+                FORWARD => graph.get_ch_edges_out(node),
+                BACKWARD => graph.get_ch_edges_in(node),
+            };
+            for half_edge in edges {
+                let next = half_edge.target_id;
+                if graph.nodes[node].ch_level > graph.nodes[next].ch_level {
+                    break;
+                }
+                let total = state.total_cost + costs_by_alpha(&half_edge.edge_costs, alpha);
+                if total < self.dist[next] {
+                    self.dist[next] = total;
+                    self.touched.push(next);
+                    let parent = Some((space.len() - 1, half_edge.edge_id));
+                    heap.push((
+                        State {
+                            node_id: next,
+                            total_cost: total,
+                            direction,
+                        },
+                        parent,
+                    ));
+                }
+            }
+        }
+
+        space
+    }
+
+    /// Edges of the last run from its start to the node at `index` of the
+    /// search space, ordered from the start.
+    pub(super) fn edges_to(&self, index: usize) -> Vec<u32> {
+        let mut edges = Vec::new();
+        let mut current = self.parents[index];
+        while let Some((prev, edge)) = current {
+            edges.push(edge);
+            current = self.parents[prev];
+        }
+        edges.reverse();
+        edges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dijkstra::Dijkstra;
+    use crate::graph::parse_minimal_graph_file;
+
+    #[test]
+    fn matrix_matches_single_queries() {
+        let graph = parse_minimal_graph_file("./src/test_graphs/minimalTestGraph")
+            .unwrap()
+            .graph;
+        let nodes: Vec<u32> = (0..graph.nodes.len() as u32).collect();
+        let alpha = &[0.1, 0.2, 0.3, 0.4];
+
+        let matrix = many_to_many(&graph, &nodes, &nodes, alpha);
+        let mut dijkstra = Dijkstra::new(&graph);
+        for (i, &s) in nodes.iter().enumerate() {
+            for (j, &t) in nodes.iter().enumerate() {
+                let expected = dijkstra.run(s, t, alpha);
+                assert_eq!(
+                    matrix.total_cost(i, j).is_some(),
+                    expected.is_some(),
+                    "reachability of {} from {}",
+                    t,
+                    s
+                );
+                if let Some(expected) = expected {
+                    let total = matrix.total_cost(i, j).unwrap();
+                    assert!((total - expected.total_cost).abs() < 1e-9);
+                    let costs = matrix.costs(i, j).unwrap();
+                    assert!((costs_by_alpha(costs, alpha) - total).abs() < 1e-9);
+                }
+            }
+        }
+    }
+}
@@ -1,16 +1,32 @@
 use std::collections::binary_heap::BinaryHeap;
 
+use edge_based::EdgeSearch;
 use state::Direction::{BACKWARD, FORWARD};
 use state::State;
 
-use crate::graph::Graph;
-use crate::helpers::{add_edge_costs, costs_by_alpha, Costs, MyVec, Preference};
-use crate::EDGE_COST_DIMENSION;
+use crate::graph::{turns::Turns, Graph};
+use crate::helpers::{add_edge_costs, costs_by_alpha, Costs, MyVec};
 
+mod alternatives;
+mod edge_based;
+mod landmarks;
+mod many_to_many;
 mod ndijkstra;
+mod pareto;
+mod phast;
 mod state;
+mod time_dependent;
 
+pub use alternatives::{k_shortest_paths, via_node_alternatives, AlternativeFilters};
+pub use landmarks::Landmarks;
+pub use many_to_many::{many_to_many, one_to_many, CostMatrix};
 pub use ndijkstra::NDijkstra;
+pub use pareto::{ParetoPath, ParetoSearch};
+pub use phast::Phast;
+pub use time_dependent::TimeDependentDijkstra;
+
+pub(super) use landmarks::metric_distances;
+pub(super) use state::{Direction, Label, MinHeapItem};
 
 pub struct HalfPath {
     pub edges: MyVec<MyVec<u32>>,
@@ -43,6 +59,9 @@ pub struct Dijkstra<'a> {
 
     // (node_id, cost array, total_cost)
     best_node: Option<(u32, f64)>,
+
+    // Replaces the CH search if turns are respected
+    edge_search: Option<EdgeSearch<'a>>,
 }
 
 impl<'a> Dijkstra<'a> {
@@ -59,9 +78,29 @@ impl<'a> Dijkstra<'a> {
             previous_f: MyVec(vec![None; num_of_nodes]),
             previous_b: MyVec(vec![None; num_of_nodes]),
             best_node: None,
+            edge_search: None,
         }
     }
 
+    /// Edge-based search that respects `turns`. It runs on the original
+    /// edges only, so it does not profit from the contraction hierarchy.
+    pub fn with_turns(graph: &'a Graph, turns: &'a Turns) -> Dijkstra<'a> {
+        let mut dijkstra = Dijkstra::new(graph);
+        dijkstra.edge_search = Some(EdgeSearch::new(graph, turns));
+        dijkstra
+    }
+
+    /// Whether this search respects turns, see `with_turns`.
+    pub fn respects_turns(&self) -> bool {
+        self.edge_search.is_some()
+    }
+
+    /// The turns this search respects. The costs of the paths it finds
+    /// include their turn costs, see `Turns::path_costs`.
+    pub fn turns(&self) -> Option<&'a Turns> {
+        self.edge_search.as_ref().map(|search| search.turns())
+    }
+
     fn prepare(&mut self, source: u32, target: u32) {
         // Candidates
         self.candidates = BinaryHeap::new();
@@ -90,7 +129,16 @@ impl<'a> Dijkstra<'a> {
         self.best_node = None;
     }
 
-    pub fn run(&mut self, source: u32, target: u32, alpha: Preference) -> Option<DijkstraResult> {
+    pub fn run(&mut self, source: u32, target: u32, alpha: &[f64]) -> Option<DijkstraResult> {
+        if let Some(search) = self.edge_search.as_mut() {
+            let total_cost = search.run(source, target, alpha)?;
+            let (edges, costs) = search.path(target)?;
+            return Some(DijkstraResult {
+                edges,
+                costs,
+                total_cost,
+            });
+        }
         self.prepare(source, target);
 
         // let now = Instant::now();
@@ -100,7 +148,7 @@ impl<'a> Dijkstra<'a> {
             if self.found_best_f && self.found_best_b {
                 break;
             }
-            self.process_state(candidate, &alpha);
+            self.process_state(candidate, alpha);
         }
 
         match self.best_node {
@@ -128,7 +176,7 @@ impl<'a> Dijkstra<'a> {
         }
     }
 
-    fn process_state(&mut self, candidate: State, alpha: &Preference) {
+    fn process_state(&mut self, candidate: State, alpha: &[f64]) {
         let State {
             node_id,
             total_cost,
@@ -179,7 +227,7 @@ impl<'a> Dijkstra<'a> {
             }
 
             let next_node = half_edge.target_id;
-            let next_total_cost = total_cost + costs_by_alpha(&half_edge.edge_costs, &alpha);
+            let next_total_cost = total_cost + costs_by_alpha(&half_edge.edge_costs, alpha);
 
             if next_total_cost < my_costs[next_node] {
                 my_costs[next_node] = next_total_cost;
@@ -199,7 +247,7 @@ impl<'a> Dijkstra<'a> {
         let mut previous_edge = self.previous_f[connector];
         let mut successive_edge = self.previous_b[connector];
 
-        let mut costs = [0.0; EDGE_COST_DIMENSION];
+        let mut costs = vec![0.0; self.graph.dim()];
 
         // backwards
         while let Some(edge_id) = previous_edge {
@@ -219,11 +267,11 @@ impl<'a> Dijkstra<'a> {
     }
 }
 
-pub fn find_path(dijkstra: &mut Dijkstra, include: &[u32], alpha: Preference) -> Option<HalfPath> {
+pub fn find_path(dijkstra: &mut Dijkstra, include: &[u32], alpha: &[f64]) -> Option<HalfPath> {
     // println!("=== Running Dijkstra search ===");
     let mut edges = MyVec::new();
     let mut dimension_costs = MyVec::new();
-    let mut total_dimension_costs = [0.0; EDGE_COST_DIMENSION];
+    let mut total_dimension_costs = vec![0.0; dijkstra.graph.dim()];
     let mut costs_by_alpha = MyVec::new();
 
     for win in include.windows(2) {
@@ -251,7 +299,7 @@ pub fn find_path(dijkstra: &mut Dijkstra, include: &[u32], alpha: Preference) ->
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::graph::{parse_graph_file, Graph};
+    use crate::graph::{parse_graph_file, Edge, Graph, Node};
 
     fn get_conc_graph() -> Graph {
         parse_graph_file("./src/test_graphs/concTestGraph").unwrap()
@@ -271,7 +319,7 @@ mod tests {
 
         for s in 0..(conc_graph.nodes.len() as u32) {
             for t in 0..(conc_graph.nodes.len() as u32) {
-                let ch_path = dijkstra_conc.run(s, t, alpha);
+                let ch_path = dijkstra_conc.run(s, t, &alpha);
                 let n_costs = dijkstra.run(s, t, &alpha);
                 assert_eq!(ch_path.as_ref().map(|r| r.total_cost), n_costs);
 
@@ -336,4 +384,27 @@ mod tests {
         // assert_eq!(path_conc.edges.0, vec![21]);
         // assert_eq!(path_conc.total_cost, 3.0);
     }
+
+    #[test]
+    fn two_metric_graph() {
+        let graph = Graph::new(
+            vec![Node::new(0, 0), Node::new(1, 0), Node::new(2, 0)],
+            vec![
+                Edge::new(0, 0, 1, vec![1.0, 5.0], None),
+                Edge::new(1, 1, 2, vec![1.0, 5.0], None),
+                Edge::new(2, 0, 2, vec![4.0, 1.0], None),
+            ],
+        );
+        assert_eq!(2, graph.dim());
+
+        let mut dijkstra = Dijkstra::new(&graph);
+
+        let first = dijkstra.run(0, 2, &[1.0, 0.0]).unwrap();
+        assert_eq!(vec![0, 1], first.edges.0);
+        assert_eq!(vec![2.0, 10.0], first.costs);
+
+        let second = dijkstra.run(0, 2, &[0.0, 1.0]).unwrap();
+        assert_eq!(vec![2], second.edges.0);
+        assert_eq!(vec![4.0, 1.0], second.costs);
+    }
 }
@@ -1,10 +1,12 @@
+use super::edge_based::EdgeSearch;
+use super::landmarks::Landmarks;
 use crate::{
     graph::{
         path::{Path, PathSplit},
+        turns::Turns,
         Graph,
     },
-    helpers::{add_edge_costs, costs_by_alpha, MyVec, Preference, EQUAL_WEIGHTS},
-    EDGE_COST_DIMENSION,
+    helpers::{add_edge_costs, costs_by_alpha, equal_weights, Costs, MyVec, Preference},
 };
 
 use ordered_float::OrderedFloat;
@@ -17,7 +19,10 @@ pub struct NDijkstra<'a> {
     heap: BinaryHeap<HeapElement>,
     touched: Vec<u32>,
     last_from: u32,
+    last_to: u32,
     last_pref: Preference,
+    edge_search: Option<EdgeSearch<'a>>,
+    landmarks: Option<&'a Landmarks>,
 }
 
 impl<'a> NDijkstra<'a> {
@@ -33,10 +38,28 @@ impl<'a> NDijkstra<'a> {
             heap,
             touched,
             last_from: u32::MAX,
-            last_pref: EQUAL_WEIGHTS,
+            last_to: u32::MAX,
+            last_pref: equal_weights(g.dim()),
+            edge_search: None,
+            landmarks: None,
         }
     }
 
+    /// Edge-based search that respects `turns`.
+    pub fn with_turns(g: &'a Graph, turns: &'a Turns) -> Self {
+        let mut dijkstra = NDijkstra::new(g);
+        dijkstra.edge_search = Some(EdgeSearch::new(g, turns));
+        dijkstra
+    }
+
+    /// A* search that is guided towards the target by the lower bounds of
+    /// `landmarks` (ALT). Preferences must not have negative weights.
+    pub fn with_landmarks(g: &'a Graph, landmarks: &'a Landmarks) -> Self {
+        let mut dijkstra = NDijkstra::new(g);
+        dijkstra.landmarks = Some(landmarks);
+        dijkstra
+    }
+
     pub fn reset_state(&mut self) {
         for &t in &self.touched {
             self.dist[t] = f64::MAX;
@@ -46,29 +69,40 @@ impl<'a> NDijkstra<'a> {
         self.touched.clear();
     }
 
-    pub fn run(&mut self, from: u32, to: u32, alpha: &Preference) -> Option<f64> {
+    pub fn run(&mut self, from: u32, to: u32, alpha: &[f64]) -> Option<f64> {
+        if let Some(search) = self.edge_search.as_mut() {
+            self.last_from = from;
+            self.last_pref = alpha.to_vec();
+            return search.run(from, to, alpha);
+        }
+
         // If the query starts from the same node as before we can reuse it
-        if self.last_from == from && self.last_pref == *alpha {
-            if self.dist[to] < f64::MAX {
-                return Some(self.dist[to]);
-            }
-        } else {
+        let same_start = self.last_from == from && self.last_pref == alpha;
+        if same_start && self.dist[to] < f64::MAX {
+            return Some(self.dist[to]);
+        }
+        // With landmarks the heap is ordered towards the last target, so it
+        // can only be continued for that one
+        if !same_start || (self.landmarks.is_some() && self.last_to != to) {
             // If not we initialize it normally
             self.last_from = from;
-            self.last_pref = *alpha;
+            self.last_pref = alpha.to_vec();
             self.reset_state();
 
             self.heap.push(HeapElement {
+                key: self.potential(from, to, alpha),
                 dist: 0.0,
                 node: from,
                 prev_edge: from,
             });
         }
+        self.last_to = to;
 
         while let Some(HeapElement {
             dist: u_dist,
             node: u,
             prev_edge,
+            ..
         }) = self.heap.pop()
         {
             // If your heap does not support a decrease key operation, you can
@@ -87,6 +121,7 @@ impl<'a> NDijkstra<'a> {
                 let alt = u_dist + costs_by_alpha(&edge.edge_costs, alpha);
                 if alt < self.dist[edge.target_id] {
                     self.heap.push(HeapElement {
+                        key: alt + self.potential(edge.target_id, to, alpha),
                         dist: alt,
                         node: edge.target_id,
                         prev_edge: edge.edge_id,
@@ -103,9 +138,50 @@ impl<'a> NDijkstra<'a> {
         None
     }
 
+    fn potential(&self, node: u32, to: u32, alpha: &[f64]) -> f64 {
+        self.landmarks
+            .map_or(0.0, |landmarks| landmarks.lower_bound(node, to, alpha))
+    }
+
     pub fn path(&mut self, to: u32) -> Option<Path> {
+        let (edges, nodes, total_dimension_costs) = match self.edge_search.as_mut() {
+            Some(search) => {
+                let (edges, costs) = search.path(to)?;
+                let mut nodes = MyVec(vec![self.last_from]);
+                nodes.extend(edges.iter().map(|&e| self.g.edges[e].target_id));
+                (edges, nodes, costs)
+            }
+            None => self.node_path(to)?,
+        };
+
+        let edges = edges
+            .0
+            .into_iter()
+            .flat_map(|e| self.g.unpacked_edges(e))
+            .collect::<Vec<_>>()
+            .into();
+
+        Some(Path {
+            id: vec![(None, 0)],
+            nodes,
+            edges,
+            user_split: PathSplit {
+                cuts: MyVec(vec![0]),
+                alphas: MyVec(vec![self.last_pref.clone()]),
+                dimension_costs: MyVec(vec![total_dimension_costs.clone()]),
+                costs_by_alpha: MyVec(vec![costs_by_alpha(
+                    &total_dimension_costs,
+                    &self.last_pref,
+                )]),
+            },
+            algo_split: None,
+            total_dimension_costs,
+        })
+    }
+
+    fn node_path(&mut self, to: u32) -> Option<(MyVec<u32>, MyVec<u32>, Costs)> {
         if self.prev[to] == None {
-            let alpha = self.last_pref;
+            let alpha = self.last_pref.clone();
             self.run(self.last_from, to, &alpha);
         }
         // early return if `to` is unreachable
@@ -113,7 +189,7 @@ impl<'a> NDijkstra<'a> {
 
         let mut edges = MyVec::new();
         let mut nodes = MyVec::new();
-        let mut total_dimension_costs = [0.0; EDGE_COST_DIMENSION];
+        let mut total_dimension_costs = vec![0.0; self.g.dim()];
         let mut cur_node = to;
 
         while cur_node != self.last_from {
@@ -129,34 +205,14 @@ impl<'a> NDijkstra<'a> {
 
         edges.reverse();
         nodes.reverse();
-        let edges = edges
-            .0
-            .into_iter()
-            .flat_map(|e| self.g.unpack_edge(e))
-            .collect::<Vec<_>>()
-            .into();
-
-        Some(Path {
-            id: vec![(None, 0)],
-            nodes,
-            edges,
-            user_split: PathSplit {
-                cuts: MyVec(vec![0]),
-                alphas: MyVec(vec![self.last_pref]),
-                dimension_costs: MyVec(vec![total_dimension_costs]),
-                costs_by_alpha: MyVec(vec![costs_by_alpha(
-                    &total_dimension_costs,
-                    &self.last_pref,
-                )]),
-            },
-            algo_split: None,
-            total_dimension_costs,
-        })
+        Some((edges, nodes, total_dimension_costs))
     }
 }
 
 #[derive(Debug, PartialEq)]
 struct HeapElement {
+    // `dist` plus the lower bound to the target when using landmarks
+    key: f64,
     dist: f64,
     node: u32,
     prev_edge: u32,
@@ -168,7 +224,7 @@ impl Eq for HeapElement {}
 // custom ordering which reverses the sorting.
 impl Ord for HeapElement {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        OrderedFloat(other.dist).cmp(&OrderedFloat(self.dist))
+        OrderedFloat(other.key).cmp(&OrderedFloat(self.key))
     }
 }
 
@@ -0,0 +1,206 @@
+use super::state::{Label, MinHeapItem};
+use crate::graph::Graph;
+use crate::helpers::{add_edge_costs, Costs, MyVec, ACCURACY};
+
+use ordered_float::OrderedFloat;
+use std::collections::BinaryHeap;
+
+/// A Pareto-optimal path, see `ParetoSearch`.
+#[derive(Debug, Clone)]
+pub struct ParetoPath {
+    pub nodes: MyVec<u32>,
+    pub edges: MyVec<u32>,
+    pub costs: Costs,
+}
+
+/// Multi-criteria Dijkstra that finds all Pareto-optimal paths between two
+/// nodes, regardless of whether any preference makes them optimal.
+/// Shortcuts are ignored, as they only repeat the costs of original paths.
+///
+/// The number of Pareto-optimal paths can grow exponentially. With an
+/// epsilon, labels that are within a factor of `1 + epsilon` of another
+/// label in every metric are dropped, which keeps the label sets small and
+/// yields a set of paths that covers every Pareto-optimal path within that
+/// factor.
+pub struct ParetoSearch<'a> {
+    g: &'a Graph,
+    epsilon: f64,
+    labels: Vec<Label>,
+    // Live labels by node
+    bags: MyVec<Vec<usize>>,
+    // Labels by the sum of their costs, which a dominating label can never
+    // exceed
+    heap: BinaryHeap<MinHeapItem<usize>>,
+    touched: Vec<u32>,
+}
+
+impl<'a> ParetoSearch<'a> {
+    pub fn new(g: &'a Graph) -> Self {
+        ParetoSearch {
+            g,
+            epsilon: 0.0,
+            labels: Vec::new(),
+            bags: MyVec(vec![Vec::new(); g.nodes.len()]),
+            heap: BinaryHeap::new(),
+            touched: Vec::new(),
+        }
+    }
+
+    /// Search with epsilon-dominance pruning.
+    pub fn with_epsilon(g: &'a Graph, epsilon: f64) -> Self {
+        let mut search = ParetoSearch::new(g);
+        search.epsilon = epsilon;
+        search
+    }
+
+    /// All Pareto-optimal paths from `from` to `to`, ordered by the sum of
+    /// their costs.
+    pub fn run(&mut self, from: u32, to: u32) -> Vec<ParetoPath> {
+        self.search(from, to, None);
+        let mut paths: Vec<_> = self.bags[to].iter().map(|&l| self.path(l)).collect();
+        paths.sort_by_key(|p| OrderedFloat(p.costs.iter().sum::<f64>()));
+        paths
+    }
+
+    /// Whether no other path between the ends of `edges` is better in one
+    /// metric without being worse in another. Only exact without epsilon,
+    /// otherwise a dominating path might have been pruned.
+    pub fn is_pareto_optimal(&mut self, edges: &[u32]) -> bool {
+        let (from, to) = match (edges.first(), edges.last()) {
+            (Some(&first), Some(&last)) => {
+                (self.g.edges[first].source_id, self.g.edges[last].target_id)
+            }
+            _ => return true,
+        };
+        let costs = edges.iter().fold(vec![0.0; self.g.dim()], |sum, &e| {
+            add_edge_costs(&sum, &self.g.edges[e].edge_costs)
+        });
+
+        // Only labels that are nowhere worse than the path can dominate it
+        self.search(from, to, Some(&costs));
+        !self.bags[to]
+            .iter()
+            .any(|&l| strictly_dominates(&self.labels[l].costs, &costs))
+    }
+
+    fn reset_state(&mut self) {
+        for &node in &self.touched {
+            self.bags[node].clear();
+        }
+        self.labels.clear();
+        self.heap.clear();
+        self.touched.clear();
+    }
+
+    fn search(&mut self, from: u32, to: u32, bound: Option<&[f64]>) {
+        self.reset_state();
+        self.insert(Label::new(from, vec![0.0; self.g.dim()], None));
+
+        while let Some(MinHeapItem { value: label, .. }) = self.heap.pop() {
+            if self.labels[label].dominated {
+                continue;
+            }
+            let node = self.labels[label].node;
+            if node == to {
+                continue;
+            }
+
+            for half_edge in self.g.get_ch_edges_out(node) {
+                if self.g.edges[half_edge.edge_id].replaced_edges.is_some() {
+                    continue;
+                }
+                let costs = add_edge_costs(&self.labels[label].costs, &half_edge.edge_costs);
+                if let Some(bound) = bound {
+                    if costs.iter().zip(bound).any(|(c, b)| *c > b + ACCURACY) {
+                        continue;
+                    }
+                }
+                // Labels that cannot beat a path to the target are useless
+                let covered = |bag: &[usize]| {
+                    bag.iter()
+                        .any(|&l| self.covers(&self.labels[l].costs, &costs))
+                };
+                if covered(&self.bags[half_edge.target_id]) || covered(&self.bags[to]) {
+                    continue;
+                }
+
+                self.insert(Label::new(
+                    half_edge.target_id,
+                    costs,
+                    Some((label, half_edge.edge_id)),
+                ));
+            }
+        }
+    }
+
+    /// Adds `label` to its node, removing the labels it dominates.
+    fn insert(&mut self, label: Label) {
+        let node = label.node;
+        if self.bags[node].is_empty() {
+            self.touched.push(node);
+        }
+        let key = label.costs.iter().sum();
+        let id = Label::insert(&mut self.labels, &mut self.bags[node], label, dominates);
+        self.heap.push(MinHeapItem::new(key, id));
+    }
+
+    fn covers(&self, a: &[f64], b: &[f64]) -> bool {
+        a.iter()
+            .zip(b)
+            .all(|(a, b)| *a <= b * (1.0 + self.epsilon) + ACCURACY)
+    }
+
+    fn path(&self, label: usize) -> ParetoPath {
+        let (nodes, edges) = Label::trace(&self.labels, label);
+        ParetoPath {
+            nodes: MyVec(nodes),
+            edges: MyVec(edges),
+            costs: self.labels[label].costs.clone(),
+        }
+    }
+}
+
+fn dominates(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b).all(|(a, b)| *a <= *b)
+}
+
+fn strictly_dominates(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b).all(|(a, b)| *a <= b + ACCURACY)
+        && a.iter().zip(b).any(|(a, b)| *a < b - ACCURACY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Edge, Node};
+
+    #[test]
+    fn skyline_is_found() {
+        // 0 -> 1 directly, via 2, via 3 (dominated by 2) or via 4 (close to 2)
+        let nodes = (0..5).map(|id| Node::new(id, 0)).collect();
+        let edges = vec![
+            Edge::new(0, 0, 1, vec![10.0, 1.0], None),
+            Edge::new(1, 0, 2, vec![2.0, 3.0], None),
+            Edge::new(2, 2, 1, vec![2.0, 3.0], None),
+            Edge::new(3, 0, 3, vec![3.0, 3.0], None),
+            Edge::new(4, 3, 1, vec![3.0, 3.0], None),
+            Edge::new(5, 0, 4, vec![2.1, 2.9], None),
+            Edge::new(6, 4, 1, vec![2.1, 3.0], None),
+        ];
+        let graph = Graph::new(nodes, edges);
+
+        let mut search = ParetoSearch::new(&graph);
+        let paths = search.run(0, 1);
+        let costs: Vec<_> = paths.iter().map(|p| p.costs.clone()).collect();
+        assert_eq!(costs, vec![vec![4.0, 6.0], vec![4.2, 5.9], vec![10.0, 1.0]]);
+        assert_eq!(paths[0].nodes.0, vec![0, 2, 1]);
+        assert_eq!(paths[0].edges.0, vec![1, 2]);
+
+        assert!(search.is_pareto_optimal(&[0]));
+        assert!(search.is_pareto_optimal(&[5, 6]));
+        assert!(!search.is_pareto_optimal(&[3, 4]));
+
+        let mut search = ParetoSearch::with_epsilon(&graph, 0.1);
+        assert_eq!(search.run(0, 1).len(), 2);
+    }
+}
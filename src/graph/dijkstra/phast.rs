@@ -0,0 +1,127 @@
+use super::many_to_many::UpwardSearch;
+use super::state::Direction::FORWARD;
+use crate::graph::Graph;
+use crate::helpers::{costs_by_alpha, Costs, MyVec};
+
+/// One-to-all queries on the contraction hierarchy (PHAST): an upward
+/// search from the source followed by one sweep over all nodes from the
+/// highest to the lowest level, relaxing the downward edges into each node.
+///
+/// Since `Graph::new` sorts the nodes by level, the sweep is a linear scan
+/// over node ids without any priority queue.
+pub struct Phast<'a> {
+    graph: &'a Graph,
+    upward: UpwardSearch<'a>,
+    dist: MyVec<f64>,
+    // Cost vectors of all nodes, one after the other
+    costs: Vec<f64>,
+}
+
+impl<'a> Phast<'a> {
+    pub fn new(graph: &'a Graph) -> Self {
+        Phast {
+            graph,
+            upward: UpwardSearch::new(graph),
+            dist: MyVec(vec![f64::MAX; graph.nodes.len()]),
+            costs: vec![0.0; graph.nodes.len() * graph.dim()],
+        }
+    }
+
+    /// Computes the best paths from `source` to all nodes under `alpha`.
+    /// Their costs can be read with `total_cost` and `costs` until the next
+    /// run.
+    pub fn run(&mut self, source: u32, alpha: &[f64]) {
+        let graph = self.graph;
+        let dim = graph.dim();
+        for d in self.dist.iter_mut() {
+            *d = f64::MAX;
+        }
+
+        for (node, total, costs) in self.upward.run(source, FORWARD, alpha) {
+            self.dist[node] = total;
+            let start = node as usize * dim;
+            self.costs[start..start + dim].copy_from_slice(&costs);
+        }
+
+        for node in 0..graph.nodes.len() as u32 {
+            let level = graph.nodes[node].ch_level;
+            let mut best: Option<(f64, u32, u32)> = None;
+            for half_edge in graph.get_ch_edges_in(node) {
+                let prev = half_edge.target_id;
+                if graph.nodes[prev].ch_level <= level {
+                    break;
+                }
+                if self.dist[prev] == f64::MAX {
+                    continue;
+                }
+                let total = self.dist[prev] + costs_by_alpha(&half_edge.edge_costs, alpha);
+                if total < best.map_or(self.dist[node], |(total, _, _)| total) {
+                    best = Some((total, prev, half_edge.edge_id));
+                }
+            }
+
+            if let Some((total, prev, edge)) = best {
+                self.dist[node] = total;
+                // Higher levels come first, so `prev` is stored before `node`
+                let (before, rest) = self.costs.split_at_mut(node as usize * dim);
+                let prev_costs = &before[prev as usize * dim..][..dim];
+                let edge_costs = &graph.edges[edge].edge_costs;
+                for ((c, p), e) in rest[..dim].iter_mut().zip(prev_costs).zip(edge_costs) {
+                    *c = p + e;
+                }
+            }
+        }
+    }
+
+    /// Cost of the best path to `node` under the preference of the last run.
+    pub fn total_cost(&self, node: u32) -> Option<f64> {
+        Some(self.dist[node]).filter(|&d| d < f64::MAX)
+    }
+
+    /// Cost vector of the best path to `node` in the last run.
+    pub fn costs(&self, node: u32) -> Option<&[f64]> {
+        let dim = self.graph.dim();
+        self.total_cost(node)
+            .map(|_| &self.costs[node as usize * dim..(node as usize + 1) * dim])
+    }
+
+    /// Cost vectors of the best paths from `source` to all nodes, by node
+    /// id.
+    pub fn one_to_all(&mut self, source: u32, alpha: &[f64]) -> MyVec<Option<Costs>> {
+        self.run(source, alpha);
+        (0..self.graph.nodes.len() as u32)
+            .map(|node| self.costs(node).map(<[f64]>::to_vec))
+            .collect::<Vec<_>>()
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dijkstra::NDijkstra;
+    use crate::graph::test_helpers::test_graphs;
+
+    #[test]
+    fn sweep_matches_dijkstra() {
+        for graph in &test_graphs() {
+            let alpha = vec![1.0 / graph.dim() as f64; graph.dim()];
+            let mut phast = Phast::new(graph);
+            let mut dijkstra = NDijkstra::new(graph);
+
+            for source in 0..graph.nodes.len() as u32 {
+                let all_costs = phast.one_to_all(source, &alpha);
+                for target in 0..graph.nodes.len() as u32 {
+                    let expected = dijkstra.run(source, target, &alpha);
+                    assert_eq!(phast.total_cost(target).is_some(), expected.is_some());
+                    if let Some(expected) = expected {
+                        let total = phast.total_cost(target).unwrap();
+                        assert!((total - expected).abs() < 1e-9);
+                        let costs = all_costs[target].as_ref().unwrap();
+                        assert!((costs_by_alpha(costs, &alpha) - total).abs() < 1e-9);
+                    }
+                }
+            }
+        }
+    }
+}
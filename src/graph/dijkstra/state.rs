@@ -2,6 +2,8 @@ use std::cmp::Ordering;
 
 use ordered_float::OrderedFloat;
 
+use crate::helpers::Costs;
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum Direction {
     FORWARD,
@@ -39,3 +41,95 @@ impl std::cmp::PartialOrd for State {
         Some(self.cmp(other))
     }
 }
+
+/// Element of a `BinaryHeap` that pops the smallest `key` first. The `value`
+/// does not take part in comparisons.
+#[derive(Debug)]
+pub struct MinHeapItem<T> {
+    pub key: f64,
+    pub value: T,
+}
+
+impl<T> MinHeapItem<T> {
+    pub fn new(key: f64, value: T) -> Self {
+        MinHeapItem { key, value }
+    }
+}
+
+impl<T> PartialEq for MinHeapItem<T> {
+    fn eq(&self, other: &Self) -> bool {
+        OrderedFloat(self.key) == OrderedFloat(other.key)
+    }
+}
+
+impl<T> Eq for MinHeapItem<T> {}
+
+impl<T> Ord for MinHeapItem<T> {
+    // switch comparison, because we want a min-heap
+    fn cmp(&self, other: &Self) -> Ordering {
+        OrderedFloat(other.key).cmp(&OrderedFloat(self.key))
+    }
+}
+
+impl<T> PartialOrd for MinHeapItem<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Path of a multi-criteria search, which keeps several labels per node.
+#[derive(Debug)]
+pub struct Label {
+    pub node: u32,
+    pub costs: Costs,
+    // Previous label and the edge from it
+    pub prev: Option<(usize, u32)>,
+    // Removed from its bag by a better label
+    pub dominated: bool,
+}
+
+impl Label {
+    pub fn new(node: u32, costs: Costs, prev: Option<(usize, u32)>) -> Self {
+        Label {
+            node,
+            costs,
+            prev,
+            dominated: false,
+        }
+    }
+
+    /// Adds `label` to `labels` and to `bag`, the labels at its node, and
+    /// removes the labels from `bag` whose costs it `dominates`. Returns the
+    /// id of the new label.
+    pub fn insert(
+        labels: &mut Vec<Label>,
+        bag: &mut Vec<usize>,
+        label: Label,
+        dominates: impl Fn(&[f64], &[f64]) -> bool,
+    ) -> usize {
+        bag.retain(|&l| {
+            let dominated = dominates(&label.costs, &labels[l].costs);
+            labels[l].dominated |= dominated;
+            !dominated
+        });
+        let id = labels.len();
+        bag.push(id);
+        labels.push(label);
+        id
+    }
+
+    /// Nodes and edges of the path to `label`, from its start.
+    pub fn trace(labels: &[Label], label: usize) -> (Vec<u32>, Vec<u32>) {
+        let mut nodes = vec![labels[label].node];
+        let mut edges = Vec::new();
+        let mut current = &labels[label];
+        while let Some((prev, edge)) = current.prev {
+            edges.push(edge);
+            current = &labels[prev];
+            nodes.push(current.node);
+        }
+        nodes.reverse();
+        edges.reverse();
+        (nodes, edges)
+    }
+}
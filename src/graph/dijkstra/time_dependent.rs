@@ -0,0 +1,162 @@
+use super::state::MinHeapItem;
+use crate::{
+    graph::{
+        path::{Path, PathSplit},
+        profiles::TravelTimeProfiles,
+        Graph,
+    },
+    helpers::{costs_by_alpha, equal_weights, MyVec, Preference},
+};
+
+use std::collections::BinaryHeap;
+
+/// `NDijkstra` with travel times that depend on the time an edge is
+/// entered. Shortcuts are ignored, as their costs are static.
+///
+/// Labels are compared by their costs under the preference, so the search
+/// is exact for FIFO profiles if only travel time is weighted. For mixed
+/// preferences it is a heuristic, since a costlier path to a node might
+/// arrive in time to avoid congestion further on.
+pub struct TimeDependentDijkstra<'a> {
+    g: &'a Graph,
+    profiles: &'a TravelTimeProfiles,
+    dist: MyVec<f64>,
+    arrival: MyVec<f64>,
+    prev: MyVec<Option<u32>>,
+    // Costs of a label with its arrival time, node and edge into it
+    heap: BinaryHeap<MinHeapItem<(f64, u32, Option<u32>)>>,
+    touched: Vec<u32>,
+    last_from: u32,
+    last_departure: f64,
+    last_pref: Preference,
+}
+
+impl<'a> TimeDependentDijkstra<'a> {
+    pub fn new(g: &'a Graph, profiles: &'a TravelTimeProfiles) -> Self {
+        TimeDependentDijkstra {
+            g,
+            profiles,
+            dist: vec![f64::MAX; g.nodes.len()].into(),
+            arrival: vec![f64::MAX; g.nodes.len()].into(),
+            prev: vec![None; g.nodes.len()].into(),
+            heap: BinaryHeap::new(),
+            touched: Vec::new(),
+            last_from: u32::MAX,
+            last_departure: f64::NAN,
+            last_pref: equal_weights(g.dim()),
+        }
+    }
+
+    fn reset_state(&mut self) {
+        for &t in &self.touched {
+            self.dist[t] = f64::MAX;
+            self.arrival[t] = f64::MAX;
+            self.prev[t] = None;
+        }
+        self.heap.clear();
+        self.touched.clear();
+    }
+
+    /// Costs of the best path from `from` to `to` when departing at
+    /// `departure` seconds since midnight.
+    pub fn run(&mut self, from: u32, to: u32, departure: f64, alpha: &[f64]) -> Option<f64> {
+        // Continue the last search if only the target changed
+        if self.last_from == from && self.last_departure == departure && self.last_pref == alpha {
+            if self.dist[to] < f64::MAX {
+                return Some(self.dist[to]);
+            }
+        } else {
+            self.last_from = from;
+            self.last_departure = departure;
+            self.last_pref = alpha.to_vec();
+            self.reset_state();
+
+            self.heap
+                .push(MinHeapItem::new(0.0, (departure, from, None)));
+        }
+
+        while let Some(MinHeapItem {
+            key: u_dist,
+            value: (time, u, prev_edge),
+        }) = self.heap.pop()
+        {
+            if u_dist >= self.dist[u] {
+                continue;
+            }
+            self.dist[u] = u_dist;
+            self.arrival[u] = time;
+            self.prev[u] = prev_edge;
+            self.touched.push(u);
+
+            for half_edge in self.g.get_ch_edges_out(u) {
+                let edge = &self.g.edges[half_edge.edge_id];
+                if edge.replaced_edges.is_some() {
+                    continue;
+                }
+                let costs = self.profiles.edge_costs(edge, time);
+                let alt = u_dist + costs_by_alpha(&costs, alpha);
+                if alt < self.dist[edge.target_id] {
+                    let arrival = time + costs[self.profiles.metric];
+                    self.heap.push(MinHeapItem::new(
+                        alt,
+                        (arrival, edge.target_id, Some(edge.id)),
+                    ));
+                }
+            }
+            // See `NDijkstra::run`
+            if u == to {
+                return Some(u_dist);
+            }
+        }
+
+        None
+    }
+
+    /// Arrival time at `node` in the last search, if it was reached.
+    pub fn arrival(&self, node: u32) -> Option<f64> {
+        Some(self.arrival[node]).filter(|&a| a < f64::MAX)
+    }
+
+    pub fn path(&mut self, to: u32) -> Option<Path> {
+        if self.dist[to] == f64::MAX {
+            let alpha = self.last_pref.clone();
+            self.run(self.last_from, to, self.last_departure, &alpha);
+        }
+        // early return if `to` is unreachable
+        if self.dist[to] == f64::MAX {
+            return None;
+        }
+
+        let mut edges = MyVec::new();
+        let mut nodes = MyVec(vec![to]);
+        let mut cur_node = to;
+        while let Some(edge) = self.prev[cur_node] {
+            edges.push(edge);
+            cur_node = self.g.edges[edge].source_id;
+            nodes.push(cur_node);
+        }
+        edges.reverse();
+        nodes.reverse();
+
+        let total_dimension_costs = self
+            .profiles
+            .path_costs(self.g, &edges, self.last_departure);
+
+        Some(Path {
+            id: vec![(None, 0)],
+            nodes,
+            edges,
+            user_split: PathSplit {
+                cuts: MyVec(vec![0]),
+                alphas: MyVec(vec![self.last_pref.clone()]),
+                dimension_costs: MyVec(vec![total_dimension_costs.clone()]),
+                costs_by_alpha: MyVec(vec![costs_by_alpha(
+                    &total_dimension_costs,
+                    &self.last_pref,
+                )]),
+            },
+            algo_split: None,
+            total_dimension_costs,
+        })
+    }
+}
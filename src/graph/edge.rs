@@ -1,5 +1,5 @@
+use super::Coordinates;
 use crate::helpers::Costs;
-use crate::EDGE_COST_DIMENSION;
 
 #[derive(Debug)]
 pub struct Edge {
@@ -8,14 +8,12 @@ pub struct Edge {
     pub target_id: u32,
     pub edge_costs: Costs,
     pub replaced_edges: Option<(u32, u32)>,
+    /// Polyline from source to target, if known
+    pub geometry: Option<Vec<Coordinates>>,
 }
 
-pub fn parse_costs(tokens: &[&str]) -> Costs {
-    let mut edge_costs: Costs = [0.0; EDGE_COST_DIMENSION];
-    for (index, token) in tokens.iter().enumerate() {
-        edge_costs[index] = token.parse().unwrap();
-    }
-    edge_costs
+pub fn parse_costs(tokens: &[&str]) -> Result<Costs, std::num::ParseFloatError> {
+    tokens.iter().map(|token| token.parse()).collect()
 }
 
 impl Edge {
@@ -32,6 +30,7 @@ impl Edge {
             target_id,
             edge_costs,
             replaced_edges,
+            geometry: None,
         }
     }
 }
@@ -1,18 +1,32 @@
 pub use edge::Edge;
-use edge::HalfEdge;
+pub(crate) use edge::HalfEdge;
 
-use dijkstra::Dijkstra;
-pub use node::Node;
+use dijkstra::{Dijkstra, TimeDependentDijkstra};
+pub use node::{Coordinates, Node};
+pub use unpacking::UnpackedEdges;
 use path::{Path, PathSplit};
 
+use crate::cache;
 use crate::graphml::{EdgeLookup, GraphData, GraphmlAttribute};
-use crate::helpers::{MyVec, Preference};
-use std::collections::HashMap;
+use crate::helpers::{add_edge_costs, Costs, MyVec};
+use crate::{MyError, MyResult};
+use std::collections::{HashMap, HashSet};
 
+pub mod ch;
+mod constrained;
 pub mod dijkstra;
 mod edge;
 mod node;
 pub mod path;
+pub mod profiles;
+pub mod scc;
+pub mod subgraph;
+pub mod supported_paths;
+#[cfg(test)]
+mod test_helpers;
+pub mod turns;
+mod unpacking;
+pub mod validation;
 
 pub mod trajectory_analysis;
 
@@ -20,15 +34,37 @@ pub mod trajectory_analysis;
 pub struct Graph {
     pub nodes: MyVec<Node>,
     pub edges: MyVec<Edge>,
+    dim: usize,
     offsets_in: MyVec<u32>,
     offsets_out: MyVec<u32>,
     half_edges_in: MyVec<HalfEdge>,
     half_edges_out: MyVec<HalfEdge>,
+    unpacking: Option<unpacking::UnpackingTable>,
 }
 
 impl Graph {
     pub fn new(nodes: Vec<Node>, edges: Vec<Edge>) -> Graph {
+        let dim = edges.first().map_or(0, |e| e.edge_costs.len());
+        if edges.iter().any(|e| e.edge_costs.len() != dim) {
+            panic!("Edges have differing numbers of metrics");
+        }
+        let node_ids: HashSet<_> = nodes.iter().map(|n| n.id).collect();
+        if let Some(e) = edges
+            .iter()
+            .find(|e| !node_ids.contains(&e.source_id) || !node_ids.contains(&e.target_id))
+        {
+            panic!("Edge {} has an unknown source or target node", e.id);
+        }
+        Graph::new_unchecked(nodes, edges)
+    }
+
+    /// Like `Graph::new`, but edges with unknown nodes or a differing number
+    /// of metrics are kept instead of panicking, so `validate` can report
+    /// them. Unknown nodes get the id `u32::MAX` and such edges get no half
+    /// edges, so queries ignore them.
+    pub fn new_unchecked(nodes: Vec<Node>, edges: Vec<Edge>) -> Graph {
         println!("Constructing graph...");
+        let dim = edges.first().map_or(0, |e| e.edge_costs.len());
         let mut nodes = MyVec(nodes);
         let mut edges = MyVec(edges);
         let offsets_inner = vec![0; nodes.len() + 1];
@@ -45,41 +81,42 @@ impl Graph {
         }
 
         edges.iter_mut().for_each(|e| {
-            e.source_id = id_map[&e.source_id];
-            e.target_id = id_map[&e.target_id]
+            e.source_id = id_map.get(&e.source_id).copied().unwrap_or(u32::MAX);
+            e.target_id = id_map.get(&e.target_id).copied().unwrap_or(u32::MAX);
         });
+        let level = |node: u32| nodes.get(node as usize).map(|n| n.ch_level);
+        let is_known =
+            |edge: &&Edge| level(edge.source_id).is_some() && level(edge.target_id).is_some();
 
         // half_edges and offsets out
         edges.sort_by(|a, b| {
-            a.source_id.cmp(&b.source_id).then_with(|| {
-                nodes[b.target_id]
-                    .ch_level
-                    .cmp(&nodes[a.target_id].ch_level)
-            })
+            a.source_id
+                .cmp(&b.source_id)
+                .then_with(|| level(b.target_id).cmp(&level(a.target_id)))
         });
 
         edges
             .iter()
+            .filter(is_known)
             // .filter(|edge| nodes[edge.target_id].ch_level >= nodes[edge.source_id].ch_level)
             .for_each(|edge| {
                 offsets_out[edge.source_id + 1] += 1;
-                half_edges_out.push(HalfEdge::new(edge.id, edge.target_id, edge.edge_costs));
+                half_edges_out.push(HalfEdge::new(edge.id, edge.target_id, edge.edge_costs.clone()));
             });
 
         // half_edges and offsets in
         edges.sort_by(|a, b| {
-            a.target_id.cmp(&b.target_id).then_with(|| {
-                nodes[b.source_id]
-                    .ch_level
-                    .cmp(&nodes[a.source_id].ch_level)
-            })
+            a.target_id
+                .cmp(&b.target_id)
+                .then_with(|| level(b.source_id).cmp(&level(a.source_id)))
         });
         edges
             .iter()
+            .filter(is_known)
             // .filter(|edge| nodes[edge.source_id].ch_level >= nodes[edge.target_id].ch_level)
             .for_each(|edge| {
                 offsets_in[edge.target_id + 1] += 1;
-                half_edges_in.push(HalfEdge::new(edge.id, edge.source_id, edge.edge_costs));
+                half_edges_in.push(HalfEdge::new(edge.id, edge.source_id, edge.edge_costs.clone()));
             });
 
         // finish offset arrays
@@ -93,19 +130,57 @@ impl Graph {
         Graph {
             nodes,
             edges,
+            dim,
             offsets_in,
             offsets_out,
             half_edges_in,
             half_edges_out,
+            unpacking: None,
         }
     }
 
+    /// Graph from the parts of one built by `new_unchecked` before: nodes
+    /// sorted by level with their index as id, edges sorted by id, and the
+    /// offsets and half edges out of and into every node. Nothing is sorted
+    /// or remapped again, so the parts have to be consistent. Used to load
+    /// graph caches.
+    pub(crate) fn from_parts(
+        nodes: Vec<Node>,
+        edges: Vec<Edge>,
+        (offsets_out, half_edges_out): (Vec<u32>, Vec<HalfEdge>),
+        (offsets_in, half_edges_in): (Vec<u32>, Vec<HalfEdge>),
+    ) -> Graph {
+        Graph {
+            dim: edges.first().map_or(0, |e| e.edge_costs.len()),
+            nodes: MyVec(nodes),
+            edges: MyVec(edges),
+            offsets_in: MyVec(offsets_in),
+            offsets_out: MyVec(offsets_out),
+            half_edges_in: MyVec(half_edges_in),
+            half_edges_out: MyVec(half_edges_out),
+            unpacking: None,
+        }
+    }
+
+    /// Offsets and half edges out of and into the nodes, see `from_parts`.
+    pub(crate) fn half_edges(&self) -> [(&[u32], &[HalfEdge]); 2] {
+        [
+            (&self.offsets_out, &self.half_edges_out),
+            (&self.offsets_in, &self.half_edges_in),
+        ]
+    }
+
+    /// Number of metrics every edge of this graph carries.
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
     pub fn find_shortest_path(
         &self,
         dijkstra: &mut Dijkstra,
         id: u32,
         include: &[u32],
-        alpha: Preference,
+        alpha: &[f64],
     ) -> Option<Path> {
         if let Some(result) = dijkstra::find_path(dijkstra, &include, alpha) {
             let unpacked_edges: Vec<Vec<u32>> = result
@@ -114,7 +189,7 @@ impl Graph {
                 .map(|subpath_edges| {
                     subpath_edges
                         .iter()
-                        .flat_map(|edge| self.unpack_edge(*edge))
+                        .flat_map(|&edge| self.unpacked_edges(edge))
                         .collect()
                 })
                 .collect();
@@ -138,7 +213,7 @@ impl Graph {
                 edges: MyVec(edges),
                 user_split: PathSplit {
                     cuts,
-                    alphas: MyVec(vec![alpha]),
+                    alphas: MyVec(vec![alpha.to_vec()]),
                     dimension_costs: result.dimension_costs,
                     costs_by_alpha: result.costs_by_alpha,
                 },
@@ -149,6 +224,54 @@ impl Graph {
         None
     }
 
+    /// Like `find_shortest_path`, but with time-dependent travel times.
+    /// Every subpath departs when the previous one arrives.
+    pub fn find_time_dependent_path(
+        &self,
+        dijkstra: &mut TimeDependentDijkstra,
+        id: u32,
+        include: &[u32],
+        departure: f64,
+        alpha: &[f64],
+    ) -> Option<Path> {
+        let mut time = departure;
+        let mut edges = Vec::new();
+        let mut cuts = MyVec::new();
+        let mut dimension_costs = MyVec::new();
+        let mut costs_by_alpha = MyVec::new();
+        let mut total_dimension_costs = vec![0.0; self.dim()];
+
+        for win in include.windows(2) {
+            dijkstra.run(win[0], win[1], time, alpha)?;
+            let subpath = dijkstra.path(win[1])?;
+            time = dijkstra.arrival(win[1])?;
+
+            cuts.push(subpath.edges.len() as u32);
+            total_dimension_costs =
+                add_edge_costs(&total_dimension_costs, &subpath.total_dimension_costs);
+            costs_by_alpha.push(subpath.user_split.get_total_cost());
+            dimension_costs.push(subpath.total_dimension_costs);
+            edges.extend(subpath.edges.0);
+        }
+
+        let mut nodes: Vec<u32> = edges.iter().map(|&e| self.edges[e].source_id).collect();
+        nodes.push(*include.last()?);
+
+        Some(Path {
+            id: vec![(Some(id), 0)],
+            nodes: MyVec(nodes),
+            edges: MyVec(edges),
+            user_split: PathSplit {
+                cuts,
+                alphas: MyVec(vec![alpha.to_vec()]),
+                dimension_costs,
+                costs_by_alpha,
+            },
+            algo_split: None,
+            total_dimension_costs,
+        })
+    }
+
     fn get_ch_edges_out(&self, node_id: u32) -> &[HalfEdge] {
         &self.half_edges_out[self.offsets_out[node_id]..self.offsets_out[node_id + 1]]
     }
@@ -157,6 +280,9 @@ impl Graph {
         &self.half_edges_in[self.offsets_in[node_id]..self.offsets_in[node_id + 1]]
     }
 
+    /// Original edges of `edge`, checking that every shortcut on the way
+    /// connects its replaced edges. See `unpacked_edges` for a faster
+    /// variant without checks.
     pub fn unpack_edge(&self, edge: u32) -> Vec<u32> {
         if let Some((edge1, edge2)) = self.edges[edge].replaced_edges {
             if self.edges[edge1].source_id != self.edges[edge].source_id {
@@ -180,169 +306,278 @@ impl Graph {
         }
         vec![edge]
     }
+
+    /// Whether the hierarchy has a shortcut for every pair of higher
+    /// neighbours of every node, as built by `ch::contract_customizable`.
+    /// Only then it is independent of the metrics and can be customized.
+    pub fn is_customizable(&self) -> bool {
+        let shortcuts: HashSet<(u32, u32)> =
+            self.edges.iter().filter_map(|e| e.replaced_edges).collect();
+        (0..self.nodes.len() as u32).all(|node| {
+            let level = self.nodes[node].ch_level;
+            let is_higher =
+                |half_edge: &&HalfEdge| self.nodes[half_edge.target_id].ch_level > level;
+            let outs: Vec<_> = self.get_ch_edges_out(node).iter().filter(is_higher).collect();
+            self.get_ch_edges_in(node)
+                .iter()
+                .filter(is_higher)
+                .all(|edge_in| {
+                    outs.iter().all(|edge_out| {
+                        edge_in.target_id == edge_out.target_id
+                            || shortcuts.contains(&(edge_in.edge_id, edge_out.edge_id))
+                    })
+                })
+        })
+    }
+
+    /// Recomputes the costs of all shortcuts from the current costs of the
+    /// edges they replace, keeping the node order and shortcut topology.
+    /// Call this after changing the `edge_costs` of original edges.
+    ///
+    /// Fails for hierarchies that left out shortcuts because of witness
+    /// paths (see `is_customizable`), as these witnesses only hold for the
+    /// metrics the graph was contracted with.
+    pub fn customize(&mut self) -> MyResult<()> {
+        if !self.is_customizable() {
+            return Err(Box::new(MyError::NotCustomizable));
+        }
+
+        let mut base_edges = self.edges.iter().filter(|e| e.replaced_edges.is_none());
+        let dim = base_edges.next().map_or(0, |e| e.edge_costs.len());
+        if base_edges.any(|e| e.edge_costs.len() != dim) {
+            return Err(Box::new(MyError::DifferingMetricCounts));
+        }
+        self.dim = dim;
+
+        let mut customized = MyVec(vec![false; self.edges.len()]);
+        for edge in 0..self.edges.len() as u32 {
+            self.customize_edge(edge, &mut customized);
+        }
+
+        let edges = &self.edges;
+        self.half_edges_out
+            .iter_mut()
+            .chain(self.half_edges_in.iter_mut())
+            .for_each(|half_edge| {
+                half_edge.edge_costs = edges[half_edge.edge_id].edge_costs.clone()
+            });
+        Ok(())
+    }
+
+    /// Polyline of `edge` (shortcuts are unpacked). Edges without a stored
+    /// geometry are represented by the coordinates of their end points.
+    pub fn edge_geometry(&self, edge: u32) -> Option<Vec<Coordinates>> {
+        let mut line: Vec<Coordinates> = Vec::new();
+        for e in self.unpacked_edges(edge) {
+            let e = &self.edges[e];
+            let part = match &e.geometry {
+                Some(geometry) => geometry.clone(),
+                None => vec![
+                    self.nodes[e.source_id].coordinates?,
+                    self.nodes[e.target_id].coordinates?,
+                ],
+            };
+            let skip = if line.last() == part.first() { 1 } else { 0 };
+            line.extend(part.into_iter().skip(skip));
+        }
+        Some(line)
+    }
+
+    fn customize_edge(&mut self, edge: u32, customized: &mut MyVec<bool>) {
+        if customized[edge] {
+            return;
+        }
+        if let Some((edge1, edge2)) = self.edges[edge].replaced_edges {
+            self.customize_edge(edge1, customized);
+            self.customize_edge(edge2, customized);
+            self.edges[edge].edge_costs = add_edge_costs(
+                &self.edges[edge1].edge_costs,
+                &self.edges[edge2].edge_costs,
+            );
+        }
+        customized[edge] = true;
+    }
 }
 
 pub fn parse_graph_file(file_path: &str) -> Result<Graph, Box<dyn std::error::Error>> {
-    use crate::EDGE_COST_DIMENSION;
     use std::fs::File;
-    use std::io::{BufRead, BufReader};
+    use std::io::BufReader;
+
+    if cache::is_graph_cache(file_path)? {
+        return Ok(cache::read_graph_cache(file_path)?.graph);
+    }
 
     println!("Parsing graph...");
     let mut nodes: Vec<Node> = Vec::new();
     let mut edges: Vec<Edge> = Vec::new();
     let file = File::open(file_path)?;
-    let reader = BufReader::new(file);
-    let mut lines = reader.lines();
+    let mut lines = GraphFileLines::new(file_path, BufReader::new(file));
     for _i in 0..4 {
         // comments and blanks
-        lines.next();
-    }
-    let cost_dim: usize = lines.next().expect("No edge cost dim given")?.parse()?;
-    assert_eq!(EDGE_COST_DIMENSION, cost_dim);
-    let num_of_nodes = lines
-        .next()
-        .expect("Number of nodes not present in file")?
-        .parse()?;
-    let num_of_edges = lines
-        .next()
-        .expect("Number of edges not present in file")?
-        .parse()?;
+        lines.next_line("header")?;
+    }
+    let cost_dim: usize = lines.parse_line("edge cost dim")?;
+    let num_of_nodes: usize = lines.parse_line("number of nodes")?;
+    let num_of_edges: u32 = lines.parse_line("number of edges")?;
 
     let mut parsed_nodes: usize = 0;
     let mut parsed_edges: u32 = 0;
-    while let Some(Ok(line)) = lines.next() {
+    let mut node_ids = HashSet::new();
+    let mut edge_lines = Vec::new();
+    while let Some(line) = lines.next()? {
         let tokens: Vec<&str> = line.split_whitespace().collect();
-        if tokens[0] == "#" || tokens[0] == "\n" {
+        if tokens.is_empty() || tokens[0] == "#" {
             continue;
         }
         if parsed_nodes < num_of_nodes {
-            nodes.push(Node::new(
-                tokens[0].parse()?,
-                // tokens[2].parse()?,
-                // tokens[3].parse()?,
-                // tokens[4].parse()?,
-                tokens[5].parse()?,
+            if tokens.len() != 6 {
+                return Err(lines.error("not right amount of information for a node"));
+            }
+            let mut node = Node::new(
+                lines.parse(tokens[0], "node id")?,
+                lines.parse(tokens[5], "ch level")?,
+            );
+            node.coordinates = Some(Coordinates::new(
+                lines.parse(tokens[2], "latitude")?,
+                lines.parse(tokens[3], "longitude")?,
             ));
+            node_ids.insert(node.id);
+            nodes.push(node);
             parsed_nodes += 1;
         } else if parsed_edges < num_of_edges {
-            let replaced_edges = if tokens[tokens.len() - 2] == "-1" {
-                None
-            } else {
-                Some((
-                    tokens[tokens.len() - 2].parse()?,
-                    tokens[tokens.len() - 1].parse()?,
-                ))
-            };
+            if tokens.len() != 4 + cost_dim {
+                return Err(lines.error("not right amount of information for an edge"));
+            }
             edges.push(Edge::new(
                 parsed_edges,
-                tokens[0].parse()?,
-                tokens[1].parse()?,
-                edge::parse_costs(&tokens[2..tokens.len() - 2]),
-                replaced_edges,
+                lines.parse_node_id(tokens[0], "source id", Some(&node_ids))?,
+                lines.parse_node_id(tokens[1], "target id", Some(&node_ids))?,
+                lines.parse_costs(&tokens[2..tokens.len() - 2])?,
+                lines.parse_replaced_edges(
+                    &tokens[tokens.len() - 2..],
+                    parsed_edges,
+                    num_of_edges,
+                )?,
             ));
+            edge_lines.push(lines.line);
             parsed_edges += 1;
         } else {
-            panic!("Something doesn't add up with the amount of nodes and edges in graph file");
+            return Err(lines.error("more nodes and edges than announced in the header"));
         }
     }
+    lines.check_counts(num_of_nodes, parsed_nodes, num_of_edges, parsed_edges)?;
+    lines.check_cycles(&edges, &edge_lines)?;
     Ok(Graph::new(nodes, edges))
 }
 
 pub fn parse_minimal_graph_file(
     file_path: impl AsRef<std::path::Path>,
 ) -> Result<GraphData, Box<dyn std::error::Error>> {
-    use crate::EDGE_COST_DIMENSION;
+    read_minimal_graph_file(file_path, true)
+}
+
+/// Like `parse_minimal_graph_file`, but edges with unknown nodes are kept
+/// (see `Graph::new_unchecked`), so `Graph::validate` can report them.
+pub fn parse_minimal_graph_file_unchecked(
+    file_path: impl AsRef<std::path::Path>,
+) -> MyResult<GraphData> {
+    read_minimal_graph_file(file_path, false)
+}
+
+fn read_minimal_graph_file(
+    file_path: impl AsRef<std::path::Path>,
+    check_nodes: bool,
+) -> MyResult<GraphData> {
     use std::fs::File;
-    use std::io::{BufRead, BufReader};
+    use std::io::BufReader;
+
+    if cache::is_graph_cache(&file_path)? {
+        return cache::read_graph_cache(file_path);
+    }
 
     println!("Parsing graph...");
     let mut nodes: Vec<Node> = Vec::new();
     let mut edges: Vec<Edge> = Vec::new();
-    let file = File::open(file_path)?;
-    let reader = BufReader::new(file);
-    let mut lines = reader.lines();
+    let file = File::open(&file_path)?;
+    let mut lines = GraphFileLines::new(file_path, BufReader::new(file));
 
     let mut edge_lookup: EdgeLookup = Default::default();
 
-    loop {
-        if let Some(Ok(line)) = lines.next() {
-            if !line.starts_with('#') {
-                break;
-            }
-        }
-    }
+    while lines.next_line("header")?.starts_with('#') {}
 
-    let cost_dim: usize = lines.next().expect("No edge cost dim given")?.parse()?;
-    if EDGE_COST_DIMENSION != cost_dim {
-        panic!("Graph has wrong dimension");
-    }
+    let cost_dim: usize = lines.parse_line("edge cost dim")?;
 
-    let metric_name_line = lines.next().expect("No metric names given")?;
+    let metric_name_line = lines.next_line("metric names")?;
     let metric_names: Vec<_> = metric_name_line.split(' ').collect();
-    if metric_names.len() != EDGE_COST_DIMENSION {
-        panic!("Wrong number of metric names in graph file");
+    if metric_names.len() != cost_dim {
+        return Err(lines.error("wrong number of metric names"));
     }
     let keys = metric_names
         .into_iter()
         .enumerate()
-        .map(|(i, n)| (n.to_string(), GraphmlAttribute::new("edge", n, "double", i)))
+        .map(|(i, n)| (n.to_string(), GraphmlAttribute::metric(n, i)))
         .collect();
 
-    let num_of_nodes = lines
-        .next()
-        .expect("Number of nodes not present in file")?
-        .parse()?;
-    let num_of_edges = lines
-        .next()
-        .expect("Number of edges not present in file")?
-        .parse()?;
+    let num_of_nodes: usize = lines.parse_line("number of nodes")?;
+    let num_of_edges: u32 = lines.parse_line("number of edges")?;
 
     let mut parsed_nodes: usize = 0;
     let mut parsed_edges = 0;
-    while let Some(Ok(line)) = lines.next() {
+    let mut node_ids = HashSet::new();
+    let mut edge_lines = Vec::new();
+    while let Some(line) = lines.next()? {
         let tokens: Vec<&str> = line.split_whitespace().collect();
-        if tokens[0] == "#" || tokens[0] == "\n" {
+        if tokens.is_empty() || tokens[0] == "#" {
             continue;
         }
         if parsed_nodes < num_of_nodes {
-            if tokens.len() != 2 {
-                panic!("Not right amount of information for a node");
+            if tokens.len() != 2 && tokens.len() != 4 {
+                return Err(lines.error("not right amount of information for a node"));
             }
-            nodes.push(Node::new(
-                tokens[0].parse()?,
+            let mut node = Node::new(
+                lines.parse(tokens[0], "node id")?,
                 tokens[1].parse().unwrap_or(0),
-            ));
+            );
+            if tokens.len() == 4 {
+                node.coordinates = Some(Coordinates::new(
+                    lines.parse(tokens[2], "latitude")?,
+                    lines.parse(tokens[3], "longitude")?,
+                ));
+            }
+            node_ids.insert(node.id);
+            nodes.push(node);
             parsed_nodes += 1;
         } else if parsed_edges < num_of_edges {
-            if tokens.len() != 5 + EDGE_COST_DIMENSION {
-                panic!("Not right amount of information for an edge");
+            if tokens.len() != 5 + cost_dim {
+                return Err(lines.error(format!(
+                    "not right amount of information for edge {}",
+                    tokens[0]
+                )));
             }
-            let replaced_edges = if tokens[tokens.len() - 2] == "-1" {
-                None
-            } else {
-                Some((
-                    tokens[tokens.len() - 2].parse()?,
-                    tokens[tokens.len() - 1].parse()?,
-                ))
-            };
+            let known_ids = if check_nodes { Some(&node_ids) } else { None };
             edges.push(Edge::new(
                 parsed_edges,
-                tokens[1].parse()?,
-                tokens[2].parse()?,
-                edge::parse_costs(&tokens[3..tokens.len() - 2]),
-                replaced_edges,
+                lines.parse_node_id(tokens[1], "source id", known_ids)?,
+                lines.parse_node_id(tokens[2], "target id", known_ids)?,
+                lines.parse_costs(&tokens[3..tokens.len() - 2])?,
+                lines.parse_replaced_edges(
+                    &tokens[tokens.len() - 2..],
+                    parsed_edges,
+                    num_of_edges,
+                )?,
             ));
+            edge_lines.push(lines.line);
             edge_lookup.insert(tokens[0].to_string(), parsed_edges);
             parsed_edges += 1;
         } else {
-            panic!("Something doesn't add up with the amount of nodes and edges in graph file");
-        }
-
-        if nodes.len() != parsed_nodes || edges.len() != parsed_edges as usize {
-            panic!("Not enough nodes or edges parsed");
+            return Err(lines.error("more nodes and edges than announced in the header"));
         }
     }
-    let graph = Graph::new(nodes, edges);
+    lines.check_counts(num_of_nodes, parsed_nodes, num_of_edges, parsed_edges)?;
+    if check_nodes {
+        lines.check_cycles(&edges, &edge_lines)?;
+    }
+    let graph = Graph::new_unchecked(nodes, edges);
 
     Ok(GraphData {
         graph,
@@ -350,3 +585,226 @@ pub fn parse_minimal_graph_file(
         keys,
     })
 }
+
+/// Lines of an fmi graph file, keeping track of the line number for errors.
+struct GraphFileLines<R> {
+    file: String,
+    lines: std::io::Lines<R>,
+    line: usize,
+}
+
+impl<R: std::io::BufRead> GraphFileLines<R> {
+    fn new(file_path: impl AsRef<std::path::Path>, reader: R) -> Self {
+        GraphFileLines {
+            file: file_path.as_ref().display().to_string(),
+            lines: reader.lines(),
+            line: 0,
+        }
+    }
+
+    fn next(&mut self) -> MyResult<Option<String>> {
+        match self.lines.next() {
+            Some(line) => {
+                self.line += 1;
+                Ok(Some(line?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_line(&mut self, what: &str) -> MyResult<String> {
+        self.next()?
+            .ok_or_else(|| self.error(format!("{} not present in file", what)))
+    }
+
+    fn parse_line<T: std::str::FromStr>(&mut self, what: &str) -> MyResult<T> {
+        let line = self.next_line(what)?;
+        self.parse(line.trim(), what)
+    }
+
+    fn parse<T: std::str::FromStr>(&self, token: &str, what: &str) -> MyResult<T> {
+        token
+            .parse()
+            .map_err(|_| self.error(format!("could not parse {} '{}'", what, token)))
+    }
+
+    /// Parses a node id and checks that it is one of `known_ids`, if given.
+    fn parse_node_id(
+        &self,
+        token: &str,
+        what: &str,
+        known_ids: Option<&HashSet<u32>>,
+    ) -> MyResult<u32> {
+        let id = self.parse(token, what)?;
+        if known_ids.map_or(false, |ids| !ids.contains(&id)) {
+            return Err(self.error(format!("unknown node {} as {}", id, what)));
+        }
+        Ok(id)
+    }
+
+    fn parse_costs(&self, tokens: &[&str]) -> MyResult<Costs> {
+        edge::parse_costs(tokens).map_err(|e| self.error(format!("invalid edge costs: {}", e)))
+    }
+
+    /// Parses the replaced edges of edge `edge`, which have to be other
+    /// edges of the file.
+    fn parse_replaced_edges(
+        &self,
+        tokens: &[&str],
+        edge: u32,
+        edge_count: u32,
+    ) -> MyResult<Option<(u32, u32)>> {
+        if tokens[0] == "-1" {
+            return Ok(None);
+        }
+        let mut replaced = [0; 2];
+        for (id, token) in replaced.iter_mut().zip(tokens) {
+            *id = self.parse(token, "replaced edge")?;
+            if *id >= edge_count {
+                return Err(self.error(format!("unknown replaced edge {}", id)));
+            }
+            if *id == edge {
+                return Err(self.error("shortcut replaces itself"));
+            }
+        }
+        Ok(Some((replaced[0], replaced[1])))
+    }
+
+    /// Rejects shortcuts that transitively replace themselves, reporting
+    /// the line of the first one.
+    fn check_cycles(&self, edges: &[Edge], edge_lines: &[usize]) -> MyResult<()> {
+        let report = validation::check_replaced_edges(edges);
+        match report.cyclic_shortcuts.first() {
+            Some(&edge) => Err(self.error_at(
+                edge_lines[edge as usize],
+                format!("shortcut {} (transitively) replaces itself", edge),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn check_counts(
+        &self,
+        num_of_nodes: usize,
+        parsed_nodes: usize,
+        num_of_edges: u32,
+        parsed_edges: u32,
+    ) -> MyResult<()> {
+        if parsed_nodes != num_of_nodes || parsed_edges != num_of_edges {
+            return Err(self.error(format!(
+                "expected {} nodes and {} edges, found {} and {}",
+                num_of_nodes, num_of_edges, parsed_nodes, parsed_edges
+            )));
+        }
+        Ok(())
+    }
+
+    fn error(&self, reason: impl Into<String>) -> Box<dyn std::error::Error> {
+        self.error_at(self.line, reason)
+    }
+
+    fn error_at(&self, line: usize, reason: impl Into<String>) -> Box<dyn std::error::Error> {
+        Box::new(MyError::InvalidGraphFile {
+            file: self.file.clone(),
+            line,
+            reason: reason.into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_graph_file_reports_line() {
+        let path = std::env::temp_dir().join("ppts_malformed_graph");
+        std::fs::write(
+            &path,
+            "# comment\n\n2\na b\n2\n1\n0 0\n1 0\n0 0 1 1.5 x -1 -1\n",
+        )
+        .unwrap();
+
+        let error = parse_minimal_graph_file(&path).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Invalid graph file {} in line 9: invalid edge costs: invalid float literal",
+                path.display()
+            )
+        );
+
+        std::fs::write(&path, "# comment\n\n2\na b\n2\n1\n0 0\n1 0\n").unwrap();
+        let error = parse_minimal_graph_file(&path).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with("expected 2 nodes and 1 edges, found 2 and 0"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn unknown_nodes_and_short_costs_are_errors() {
+        let path = std::env::temp_dir().join("ppts_inconsistent_graph");
+        let header = "# comment\n\n2\na b\n2\n1\n0 0\n1 0\n";
+
+        std::fs::write(&path, format!("{}0 0 5 1.5 2 -1 -1\n", header)).unwrap();
+        let error = parse_minimal_graph_file(&path).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Invalid graph file {} in line 9: unknown node 5 as target id",
+                path.display()
+            )
+        );
+
+        std::fs::write(&path, format!("{}0 0 1 1.5 -1 -1\n", header)).unwrap();
+        let error = parse_minimal_graph_file(&path).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with("in line 9: not right amount of information for edge 0"));
+
+        let header = "#\n#\n\n\n2\n2\n1\n0 0 48.0 9.0 0 0\n1 0 48.1 9.1 0 0\n";
+        std::fs::write(&path, format!("{}0 3 1.5 2 -1 -1\n", header)).unwrap();
+        let error = parse_graph_file(path.to_str().unwrap()).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with("in line 10: unknown node 3 as target id"));
+
+        std::fs::write(&path, format!("{}0 1 1.5 -1 -1\n", header)).unwrap();
+        let error = parse_graph_file(path.to_str().unwrap()).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with("in line 10: not right amount of information for an edge"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn malformed_shortcuts_are_errors() {
+        let path = std::env::temp_dir().join("ppts_malformed_shortcuts");
+        let header = "# comment\n\n2\na b\n2\n2\n0 0\n1 0\n";
+        let error = |edges: &str| {
+            std::fs::write(&path, format!("{}{}", header, edges)).unwrap();
+            parse_minimal_graph_file(&path).err().unwrap().to_string()
+        };
+
+        assert!(error("0 0 1 1.5 2 -1 -1\n1 1 0 3 4 7 0\n")
+            .ends_with("in line 10: unknown replaced edge 7"));
+        assert!(error("0 0 1 1.5 2 -1 -1\n1 1 0 3 4 0 1\n")
+            .ends_with("in line 10: shortcut replaces itself"));
+        assert!(error("0 0 1 3 4 1 1\n1 1 0 3 4 0 0\n")
+            .ends_with("in line 10: shortcut 1 (transitively) replaces itself"));
+        let graph = parse_minimal_graph_file_unchecked(&path).unwrap().graph;
+        assert_eq!(graph.validate().cyclic_shortcuts, vec![1]);
+
+        let header = "#\n#\n\n\n2\n2\n2\n0 0 48.0 9.0 0 0\n1 0 48.1 9.1 0 0\n";
+        std::fs::write(&path, format!("{}0 1 3 4 1 1\n1 0 3 4 0 0\n", header)).unwrap();
+        let error = parse_graph_file(path.to_str().unwrap()).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with("in line 11: shortcut 1 (transitively) replaces itself"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
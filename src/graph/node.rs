@@ -1,11 +1,28 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Coordinates {
+    pub fn new(lat: f64, lon: f64) -> Coordinates {
+        Coordinates { lat, lon }
+    }
+}
+
 #[derive(Debug)]
 pub struct Node {
     pub id: u32,
     pub ch_level: u32,
+    pub coordinates: Option<Coordinates>,
 }
 
 impl Node {
     pub fn new(id: u32, ch_level: u32) -> Node {
-        Node { id, ch_level }
+        Node {
+            id,
+            ch_level,
+            coordinates: None,
+        }
     }
 }
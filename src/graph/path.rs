@@ -1,6 +1,5 @@
 use crate::graph::Graph;
 use crate::helpers::{add_edge_costs, Costs, MyVec, Preference};
-use crate::EDGE_COST_DIMENSION;
 
 #[derive(Clone, Debug)]
 pub struct PathSplit {
@@ -31,7 +30,7 @@ pub struct Path {
 impl Path {
     pub fn get_subpath_costs(&self, graph: &Graph, start: u32, end: u32) -> Costs {
         let edges = &self.edges[start..end];
-        edges.iter().fold([0.0; EDGE_COST_DIMENSION], |acc, edge| {
+        edges.iter().fold(vec![0.0; graph.dim()], |acc, edge| {
             add_edge_costs(&acc, &graph.edges[*edge].edge_costs)
         })
     }
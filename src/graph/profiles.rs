@@ -0,0 +1,250 @@
+//! Time-dependent travel times for one metric of a graph.
+//!
+//! Profiles are read from a text file with one edge per line, given by its
+//! external name and the breakpoints of its profile as pairs of seconds
+//! since midnight and travel time in seconds:
+//!
+//! ```text
+//! # edge time travel_time [time travel_time...]
+//! 17 0 30 28800 90 36000 30
+//! ```
+//!
+//! Between breakpoints the travel time is interpolated linearly, after the
+//! last breakpoint it wraps around to the first one of the next day.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::graph::{Edge, Graph};
+use crate::graphml::GraphData;
+use crate::helpers::{add_edge_costs, Costs};
+use crate::{MyError, MyResult};
+
+/// Length of a day in seconds, the period of all profiles.
+pub const DAY: f64 = 86_400.0;
+
+/// Piecewise-linear travel time over the day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TravelTimeProfile {
+    points: Vec<(f64, f64)>,
+}
+
+impl TravelTimeProfile {
+    /// Profile through `points` of (seconds since midnight, travel time).
+    /// Times have to be increasing within one day, and the profile has to
+    /// be FIFO: departing later never means arriving earlier.
+    pub fn new(points: Vec<(f64, f64)>) -> Result<TravelTimeProfile, MyError> {
+        let error = |reason: &str| Err(MyError::InvalidProfile(reason.to_owned()));
+        if points.is_empty() {
+            return error("no breakpoints");
+        }
+        if points
+            .iter()
+            .any(|&(t, v)| !(0.0..DAY).contains(&t) || v.is_nan() || v < 0.0)
+        {
+            return error("times have to be within one day and travel times non-negative");
+        }
+        if points.windows(2).any(|w| w[0].0 >= w[1].0) {
+            return error("times have to be increasing");
+        }
+
+        let (first, last) = (points[0], points[points.len() - 1]);
+        let wrap = (first.0 + DAY, first.1);
+        let segments = points.windows(2).map(|w| (w[0], w[1]));
+        if points.len() > 1
+            && segments
+                .chain(Some((last, wrap)))
+                .any(|(a, b)| b.1 - a.1 < a.0 - b.0)
+        {
+            return error("arrival times have to be non-decreasing (FIFO)");
+        }
+
+        Ok(TravelTimeProfile { points })
+    }
+
+    /// Travel time when entering the edge at `time` seconds since midnight
+    /// of any day.
+    pub fn travel_time(&self, time: f64) -> f64 {
+        let time = time.rem_euclid(DAY);
+        let (first, last) = (self.points[0], self.points[self.points.len() - 1]);
+        let next = self.points.partition_point(|&(t, _)| t <= time);
+
+        let (t0, v0) = match next {
+            0 => (last.0 - DAY, last.1),
+            _ => self.points[next - 1],
+        };
+        let (t1, v1) = self
+            .points
+            .get(next)
+            .copied()
+            .unwrap_or((first.0 + DAY, first.1));
+
+        if t1 > t0 {
+            v0 + (v1 - v0) * (time - t0) / (t1 - t0)
+        } else {
+            v0
+        }
+    }
+}
+
+/// Travel time profiles of some edges, which replace their static costs in
+/// the travel time metric.
+#[derive(Debug, Default, Clone)]
+pub struct TravelTimeProfiles {
+    /// Index of the travel time metric in the cost vectors
+    pub metric: usize,
+    profiles: HashMap<u32, TravelTimeProfile>,
+}
+
+impl TravelTimeProfiles {
+    pub fn new(metric: usize) -> TravelTimeProfiles {
+        TravelTimeProfiles {
+            metric,
+            profiles: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, edge: u32, profile: TravelTimeProfile) {
+        self.profiles.insert(edge, profile);
+    }
+
+    pub fn get(&self, edge: u32) -> Option<&TravelTimeProfile> {
+        self.profiles.get(&edge)
+    }
+
+    pub fn len(&self) -> usize {
+        self.profiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+
+    /// Travel time of `edge` when entering it at `time`. Edges without a
+    /// profile keep their static travel time.
+    pub fn travel_time(&self, edge: &Edge, time: f64) -> f64 {
+        match self.profiles.get(&edge.id) {
+            Some(profile) => profile.travel_time(time),
+            None => edge.edge_costs[self.metric],
+        }
+    }
+
+    /// Costs of `edge` when entering it at `time`.
+    pub fn edge_costs(&self, edge: &Edge, time: f64) -> Costs {
+        let mut costs = edge.edge_costs.clone();
+        costs[self.metric] = self.travel_time(edge, time);
+        costs
+    }
+
+    /// Costs of traversing `edges` one after the other, departing at
+    /// `departure`.
+    pub fn path_costs(&self, graph: &Graph, edges: &[u32], departure: f64) -> Costs {
+        let mut time = departure;
+        edges.iter().fold(vec![0.0; graph.dim()], |sum, &edge| {
+            let costs = self.edge_costs(&graph.edges[edge], time);
+            time += costs[self.metric];
+            add_edge_costs(&sum, &costs)
+        })
+    }
+}
+
+/// Reads travel time profiles for the `metric` of `graph_data` from a file
+/// in the format described in the module documentation.
+pub fn read_profiles<P: AsRef<Path>>(
+    file_path: P,
+    graph_data: &GraphData,
+    metric: &str,
+) -> MyResult<TravelTimeProfiles> {
+    let file_name = file_path.as_ref().display().to_string();
+    let metric = graph_data
+        .metric_names()
+        .iter()
+        .position(|m| m == metric)
+        .ok_or_else(|| MyError::InvalidProfile(format!("unknown metric {}", metric)))?;
+    let reader = BufReader::new(File::open(file_path)?);
+
+    let mut profiles = TravelTimeProfiles::new(metric);
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let error = |reason: String| {
+            Box::new(MyError::InvalidGraphFile {
+                file: file_name.clone(),
+                line: i + 1,
+                reason,
+            })
+        };
+        let tokens: Vec<_> = line.split_whitespace().collect();
+        if tokens.is_empty() || tokens[0].starts_with('#') {
+            continue;
+        }
+
+        let edge = graph_data
+            .edge_lookup
+            .get(tokens[0])
+            .ok_or_else(|| error(format!("unknown edge {}", tokens[0])))?;
+        let values = tokens[1..]
+            .iter()
+            .map(|v| v.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| error(e.to_string()))?;
+        if values.len() % 2 != 0 {
+            return Err(error("expected pairs of time and travel time".to_owned()));
+        }
+        let points = values.chunks(2).map(|p| (p[0], p[1])).collect();
+        let profile = TravelTimeProfile::new(points).map_err(|e| error(e.to_string()))?;
+        profiles.insert(edge, profile);
+    }
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dijkstra::TimeDependentDijkstra;
+    use crate::graph::Node;
+
+    #[test]
+    fn profiles_interpolate_and_wrap() {
+        let profile =
+            TravelTimeProfile::new(vec![(3600.0, 10.0), (7200.0, 40.0), (82800.0, 10.0)]).unwrap();
+        assert_eq!(profile.travel_time(3600.0), 10.0);
+        assert_eq!(profile.travel_time(5400.0), 25.0);
+        assert_eq!(profile.travel_time(7200.0 + DAY), 40.0);
+        assert_eq!(profile.travel_time(0.0), 10.0);
+
+        assert!(TravelTimeProfile::new(vec![(0.0, 100.0), (10.0, 0.0)]).is_err());
+        assert!(TravelTimeProfile::new(vec![(10.0, 1.0), (10.0, 2.0)]).is_err());
+    }
+
+    #[test]
+    fn departure_time_changes_route() {
+        // 0 -> 1 directly or via 2, the direct edge is congested in the
+        // morning
+        let nodes = (0..3).map(|id| Node::new(id, 0)).collect();
+        let edges = vec![
+            Edge::new(0, 0, 1, vec![10.0, 1.0], None),
+            Edge::new(1, 0, 2, vec![8.0, 1.0], None),
+            Edge::new(2, 2, 1, vec![8.0, 1.0], None),
+        ];
+        let graph = Graph::new(nodes, edges);
+        let mut profiles = TravelTimeProfiles::new(0);
+        let rush_hour = vec![(0.0, 10.0), (28800.0, 30.0), (36000.0, 10.0)];
+        profiles.insert(0, TravelTimeProfile::new(rush_hour).unwrap());
+
+        let mut d = TimeDependentDijkstra::new(&graph, &profiles);
+        let alpha = [1.0, 0.0];
+        assert_eq!(d.run(0, 1, 0.0, &alpha), Some(10.0));
+        assert_eq!(d.run(0, 1, 28800.0, &alpha), Some(16.0));
+        assert_eq!(d.arrival(1), Some(28816.0));
+        let path = d.path(1).unwrap();
+        assert_eq!(path.edges.0, vec![1, 2]);
+        assert_eq!(path.total_dimension_costs, vec![16.0, 2.0]);
+
+        let path = graph
+            .find_time_dependent_path(&mut d, 0, &[0, 2, 1], 28790.0, &alpha)
+            .unwrap();
+        assert_eq!(path.user_split.cuts.0, vec![1, 1]);
+        assert_eq!(path.total_dimension_costs, vec![16.0, 2.0]);
+    }
+}
@@ -0,0 +1,107 @@
+use crate::graph::Graph;
+use crate::helpers::MyVec;
+
+const UNVISITED: u32 = u32::MAX;
+
+/// Strongly connected components of `graph`, as a component id per node.
+/// Components are numbered from 0 in the order Tarjan's algorithm finishes
+/// them.
+pub fn strongly_connected_components(graph: &Graph) -> MyVec<u32> {
+    let node_count = graph.nodes.len();
+    let mut index = MyVec(vec![UNVISITED; node_count]);
+    let mut lowlink = MyVec(vec![0; node_count]);
+    let mut on_stack = MyVec(vec![false; node_count]);
+    let mut component = MyVec(vec![0; node_count]);
+    let mut stack = Vec::new();
+    let mut next_index = 0;
+    let mut next_component = 0;
+
+    for root in 0..node_count as u32 {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        // (node, position in its outgoing edges) instead of recursion, so
+        // large road networks do not overflow the stack
+        let mut calls = vec![(root, 0)];
+        index[root] = next_index;
+        lowlink[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some(&mut (node, ref mut position)) = calls.last_mut() {
+            let edges = graph.get_ch_edges_out(node);
+            if let Some(half_edge) = edges.get(*position) {
+                *position += 1;
+                let next = half_edge.target_id;
+                if index[next] == UNVISITED {
+                    index[next] = next_index;
+                    lowlink[next] = next_index;
+                    next_index += 1;
+                    stack.push(next);
+                    on_stack[next] = true;
+                    calls.push((next, 0));
+                } else if on_stack[next] {
+                    lowlink[node] = lowlink[node].min(index[next]);
+                }
+                continue;
+            }
+
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                lowlink[parent] = lowlink[parent].min(lowlink[node]);
+            }
+            if lowlink[node] == index[node] {
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component[member] = next_component;
+                    if member == node {
+                        break;
+                    }
+                }
+                next_component += 1;
+            }
+        }
+    }
+
+    component
+}
+
+/// Marks the nodes of the largest strongly connected component of `graph`.
+pub fn largest_scc(graph: &Graph) -> MyVec<bool> {
+    let component = strongly_connected_components(graph);
+    let mut sizes = vec![0usize; graph.nodes.len()];
+    for &c in component.iter() {
+        sizes[c as usize] += 1;
+    }
+    let largest = (0..sizes.len()).max_by_key(|&c| sizes[c]).unwrap_or(0) as u32;
+
+    MyVec(component.iter().map(|&c| c == largest).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Edge, Node};
+
+    #[test]
+    fn components_are_found() {
+        // 0 <-> 1 <-> 2 form a cycle, 3 is only reachable, 4 only reaches it
+        let nodes = (0..5).map(|id| Node::new(id, 0)).collect();
+        let edges = [(0, 1), (1, 2), (2, 0), (2, 3), (4, 1)]
+            .iter()
+            .enumerate()
+            .map(|(id, &(s, t))| Edge::new(id as u32, s, t, vec![1.0], None))
+            .collect();
+        let graph = Graph::new(nodes, edges);
+
+        let component = strongly_connected_components(&graph);
+        assert_eq!(component[0_u32], component[1_u32]);
+        assert_eq!(component[0_u32], component[2_u32]);
+        assert_ne!(component[0_u32], component[3_u32]);
+        assert_ne!(component[0_u32], component[4_u32]);
+        assert_ne!(component[3_u32], component[4_u32]);
+
+        assert_eq!(largest_scc(&graph).0, vec![true, true, true, false, false]);
+    }
+}
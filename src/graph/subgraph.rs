@@ -0,0 +1,306 @@
+use crate::graph::{Coordinates, Edge, Graph, Node};
+use crate::graphml::{EdgeLookup, GraphData, GraphmlAttribute};
+use crate::helpers::MyVec;
+use crate::trajectories::Trajectory;
+use crate::MyError;
+
+const REMOVED: u32 = u32::MAX;
+
+/// How `induced_subgraph` treats the contraction hierarchy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shortcuts {
+    /// Keep the node levels and all shortcuts whose replaced edges remain.
+    /// Only allowed for closed node sets (see `is_closed`), e.g. strongly
+    /// connected components, as otherwise witness paths of left out
+    /// shortcuts could be cut and queries would be wrong.
+    Keep,
+    /// Remove all shortcuts and reset the node levels, so the subgraph can
+    /// be contracted again with `ch::contract`.
+    Drop,
+}
+
+/// Subgraph of `graph_data` with the nodes marked in `keep` and all edges
+/// between them. Shortcuts are only kept if all edges they replace are.
+///
+/// Node and edge ids are renumbered in their original order, the edge
+/// lookup only contains the remaining edges under their new ids.
+pub fn induced_subgraph(
+    graph_data: &GraphData,
+    keep: &MyVec<bool>,
+    shortcuts: Shortcuts,
+) -> Result<GraphData, MyError> {
+    let graph = &graph_data.graph;
+    if shortcuts == Shortcuts::Keep && !is_closed(graph, keep) {
+        return Err(MyError::OpenSubgraph);
+    }
+
+    let mut kept_edges: MyVec<bool> = MyVec(
+        graph
+            .edges
+            .iter()
+            .map(|e| {
+                keep[e.source_id]
+                    && keep[e.target_id]
+                    && (shortcuts == Shortcuts::Keep || e.replaced_edges.is_none())
+            })
+            .collect(),
+    );
+    // shortcuts might come before the edges they replace, so repeat until
+    // nothing changes
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for e in graph.edges.iter() {
+            if let Some((a, b)) = e.replaced_edges {
+                if kept_edges[e.id] && !(kept_edges[a] && kept_edges[b]) {
+                    kept_edges[e.id] = false;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    let mut node_ids = MyVec(vec![REMOVED; graph.nodes.len()]);
+    let mut nodes = Vec::new();
+    for n in graph.nodes.iter().filter(|n| keep[n.id]) {
+        node_ids[n.id] = nodes.len() as u32;
+        let ch_level = match shortcuts {
+            Shortcuts::Keep => n.ch_level,
+            Shortcuts::Drop => 0,
+        };
+        let mut node = Node::new(nodes.len() as u32, ch_level);
+        node.coordinates = n.coordinates;
+        nodes.push(node);
+    }
+
+    let mut edge_ids = MyVec(vec![REMOVED; graph.edges.len()]);
+    for (id, e) in graph.edges.iter().filter(|e| kept_edges[e.id]).enumerate() {
+        edge_ids[e.id] = id as u32;
+    }
+    let edges = graph
+        .edges
+        .iter()
+        .filter(|e| kept_edges[e.id])
+        .map(|e| {
+            let replaced_edges = e.replaced_edges.map(|(a, b)| (edge_ids[a], edge_ids[b]));
+            let mut edge = Edge::new(
+                edge_ids[e.id],
+                node_ids[e.source_id],
+                node_ids[e.target_id],
+                e.edge_costs.clone(),
+                replaced_edges,
+            );
+            edge.geometry = e.geometry.clone();
+            edge
+        })
+        .collect();
+
+    let edge_lookup: EdgeLookup = graph_data
+        .edge_lookup
+        .iter()
+        .filter(|&(_, id)| kept_edges[id])
+        .map(|(name, id)| (name.clone(), edge_ids[id]))
+        .collect();
+
+    let keys = graph_data
+        .metric_names()
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.to_string(), GraphmlAttribute::metric(n, i)))
+        .collect();
+
+    Ok(GraphData {
+        graph: Graph::new(nodes, edges),
+        edge_lookup,
+        keys,
+    })
+}
+
+/// Whether every path between two nodes in `keep` only passes nodes in
+/// `keep`, so that cutting out the subgraph removes no witness paths.
+pub fn is_closed(graph: &Graph, keep: &MyVec<bool>) -> bool {
+    // Nodes outside that can be reached from the subgraph
+    let mut reached = MyVec(vec![false; graph.nodes.len()]);
+    let mut stack: Vec<u32> = (0..graph.nodes.len() as u32).filter(|&n| keep[n]).collect();
+    while let Some(node) = stack.pop() {
+        for half_edge in graph.get_ch_edges_out(node) {
+            let next = half_edge.target_id;
+            if keep[next] {
+                if !keep[node] {
+                    return false;
+                }
+            } else if !reached[next] {
+                reached[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    true
+}
+
+/// Nodes with coordinates within the bounding box spanned by `min` and
+/// `max`.
+pub fn nodes_in_bounding_box(graph: &Graph, min: Coordinates, max: Coordinates) -> MyVec<bool> {
+    MyVec(
+        graph
+            .nodes
+            .iter()
+            .map(|n| match n.coordinates {
+                Some(c) => {
+                    min.lat <= c.lat && c.lat <= max.lat && min.lon <= c.lon && c.lon <= max.lon
+                }
+                None => false,
+            })
+            .collect(),
+    )
+}
+
+/// Nodes at most `hops` original edges away from any node in `nodes`,
+/// ignoring edge directions.
+pub fn nodes_within_hops(graph: &Graph, nodes: &[u32], hops: usize) -> MyVec<bool> {
+    let mut reached = MyVec(vec![false; graph.nodes.len()]);
+    let mut frontier = Vec::new();
+    for &n in nodes {
+        if !reached[n] {
+            reached[n] = true;
+            frontier.push(n);
+        }
+    }
+
+    for _ in 0..hops {
+        let mut next = Vec::new();
+        for node in frontier {
+            let neighbours = graph
+                .get_ch_edges_out(node)
+                .iter()
+                .chain(graph.get_ch_edges_in(node))
+                .filter(|half_edge| graph.edges[half_edge.edge_id].replaced_edges.is_none());
+            for half_edge in neighbours {
+                if !reached[half_edge.target_id] {
+                    reached[half_edge.target_id] = true;
+                    next.push(half_edge.target_id);
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    reached
+}
+
+/// Nodes at most `hops` original edges away from the path of `trajectory`.
+pub fn trajectory_corridor(
+    graph_data: &GraphData,
+    trajectory: &Trajectory,
+    hops: usize,
+) -> Result<MyVec<bool>, MyError> {
+    let path = trajectory.to_path(&graph_data.graph, &graph_data.edge_lookup)?;
+    Ok(nodes_within_hops(&graph_data.graph, &path.nodes, hops))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::ch::contract;
+    use crate::graph::dijkstra::{Dijkstra, NDijkstra};
+    use crate::graph::scc::largest_scc;
+    use crate::graph::{parse_graph_file, parse_minimal_graph_file};
+    use crate::helpers::equal_weights;
+    use crate::lp::LpProcess;
+
+    #[test]
+    fn shortcuts_are_only_kept_for_closed_subgraphs() {
+        let graph_data = parse_minimal_graph_file("./src/test_graphs/minimalTestGraph").unwrap();
+        let graph = &graph_data.graph;
+        let shortcut = graph
+            .edges
+            .iter()
+            .find(|e| e.replaced_edges.is_some())
+            .unwrap();
+        let middle = graph.edges[shortcut.replaced_edges.unwrap().0].target_id;
+
+        let keep = MyVec((0..graph.nodes.len() as u32).map(|n| n != middle).collect());
+        assert!(!is_closed(graph, &keep));
+        assert!(induced_subgraph(&graph_data, &keep, Shortcuts::Keep).is_err());
+
+        let keep = largest_scc(graph);
+        assert!(is_closed(graph, &keep));
+        let component = induced_subgraph(&graph_data, &keep, Shortcuts::Keep).unwrap();
+        assert!(component.graph.validate().is_valid());
+        assert!(component
+            .graph
+            .edges
+            .iter()
+            .any(|e| e.replaced_edges.is_some()));
+        for (name, id) in component.edge_lookup.iter() {
+            let e = &component.graph.edges[id];
+            let original = &graph.edges[graph_data.edge_lookup[name]];
+            assert_eq!(e.edge_costs, original.edge_costs);
+        }
+        assert_same_distances(&component.graph);
+    }
+
+    #[test]
+    fn cut_subgraph_finds_shortest_paths() {
+        let graph = parse_graph_file("./src/test_graphs/concTestGraph").unwrap();
+        let graph_data = GraphData {
+            edge_lookup: graph
+                .edges
+                .iter()
+                .map(|e| (e.id.to_string(), e.id))
+                .collect(),
+            graph,
+            keys: Default::default(),
+        };
+        let keep = nodes_within_hops(&graph_data.graph, &[0], 2);
+        assert!(keep.iter().filter(|&&k| k).count() > 1);
+        assert!(!is_closed(&graph_data.graph, &keep));
+        assert!(induced_subgraph(&graph_data, &keep, Shortcuts::Keep).is_err());
+
+        let subgraph = induced_subgraph(&graph_data, &keep, Shortcuts::Drop).unwrap();
+        assert!(subgraph
+            .graph
+            .edges
+            .iter()
+            .all(|e| e.replaced_edges.is_none()));
+        let mut lp = LpProcess::new(subgraph.graph.dim()).unwrap();
+        let contracted = contract(&subgraph.graph, &mut lp).unwrap();
+        assert!(contracted.validate().is_valid());
+        assert_same_distances(&contracted);
+    }
+
+    fn assert_same_distances(graph: &Graph) {
+        let mut dijkstra = NDijkstra::new(graph);
+        let mut dijkstra_ch = Dijkstra::new(graph);
+        let alpha = equal_weights(graph.dim());
+        for s in 0..graph.nodes.len() as u32 {
+            for t in 0..graph.nodes.len() as u32 {
+                let expected = dijkstra.run(s, t, &alpha);
+                let found = dijkstra_ch.run(s, t, &alpha).map(|r| r.total_cost);
+                match (found, expected) {
+                    (Some(a), Some(b)) => assert!((a - b).abs() < 0.000001),
+                    (a, b) => assert_eq!(a, b),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bounding_box_uses_coordinates() {
+        let mut graph_data =
+            parse_minimal_graph_file("./src/test_graphs/minimalTestGraph").unwrap();
+        for n in graph_data.graph.nodes.iter_mut() {
+            n.coordinates = Some(Coordinates::new(f64::from(n.id), f64::from(n.id)));
+        }
+        let keep = nodes_in_bounding_box(
+            &graph_data.graph,
+            Coordinates::new(2.0, 2.0),
+            Coordinates::new(5.5, 5.5),
+        );
+        assert_eq!(
+            keep.iter().filter(|&&k| k).count(),
+            4,
+            "nodes 2 to 5 lie in the box"
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! All paths between two nodes that are optimal for some preference.
+//!
+//! The cost of the best path as a function of the preference is the lower
+//! envelope of the linear functions `alpha * costs` of all paths. Every
+//! path on that envelope is optimal in a convex region of preferences, and
+//! these regions partition the simplex. The regions of the paths found so
+//! far are refined by querying the preferences at their corners until no
+//! corner has a better path, at which point the envelope is exact.
+use crate::graph::dijkstra::Dijkstra;
+use crate::graph::path::Path;
+use crate::graph::Graph;
+use crate::helpers::{costs_by_alpha, equal_weights, Costs, Preference, ACCURACY};
+
+/// A path together with the corners of the region of preferences under
+/// which it is optimal.
+#[derive(Debug, Clone)]
+pub struct SupportedPath {
+    pub path: Path,
+    pub region: Vec<Preference>,
+}
+
+/// The paths between two nodes that are optimal for some preference, see
+/// `SupportedPaths::find`.
+#[derive(Debug, Clone, Default)]
+pub struct SupportedPaths {
+    pub paths: Vec<SupportedPath>,
+}
+
+impl SupportedPaths {
+    /// Finds every path from `from` to `to` that is optimal for some
+    /// preference. Paths with equal costs are only reported once, as the
+    /// queries cannot tell them apart.
+    pub fn find(graph: &Graph, dijkstra: &mut Dijkstra, from: u32, to: u32) -> SupportedPaths {
+        let mut query = |alpha: &[f64]| graph.find_shortest_path(dijkstra, 0, &[from, to], alpha);
+        let mut paths = match query(&equal_weights(graph.dim())) {
+            Some(path) => vec![path],
+            None => return SupportedPaths::default(),
+        };
+        let mut regions = vec![Region::simplex(graph.dim())];
+
+        // Preferences whose optimal path is known
+        let mut queried: Vec<Preference> = Vec::new();
+        loop {
+            let next = regions
+                .iter()
+                .flat_map(|region| region.corners.iter())
+                .find(|corner| !queried.iter().any(|q| same_preference(q, &corner.alpha)))
+                .map(|corner| corner.alpha.clone());
+            let alpha = match next {
+                Some(alpha) => alpha,
+                None => break,
+            };
+            let best = query(&alpha).expect("target was reachable before");
+            queried.push(alpha.clone());
+
+            let envelope = paths
+                .iter()
+                .map(|p| costs_by_alpha(&p.total_dimension_costs, &alpha))
+                .fold(f64::MAX, f64::min);
+            if costs_by_alpha(&best.total_dimension_costs, &alpha) < envelope - ACCURACY {
+                // Only regions with a corner where the new path is better
+                // change, the new region is cut out of the whole simplex
+                let costs = &best.total_dimension_costs;
+                let mut region = Region::simplex(graph.dim());
+                for (path, other) in paths.iter().zip(regions.iter_mut()) {
+                    other.cut(difference(&path.total_dimension_costs, costs));
+                    region.cut(difference(costs, &path.total_dimension_costs));
+                }
+                paths.push(best);
+                regions.push(region);
+            }
+        }
+
+        let paths = paths
+            .into_iter()
+            .zip(regions)
+            .filter(|(_, region)| !region.corners.is_empty())
+            .map(|(path, region)| SupportedPath {
+                path,
+                region: region
+                    .corners
+                    .into_iter()
+                    .map(|c| c.alpha.iter().map(|a| a.max(0.0)).collect())
+                    .collect(),
+            })
+            .collect();
+        SupportedPaths { paths }
+    }
+
+    /// A preference under which a path with `costs` between the same nodes
+    /// would be optimal, if there is one. Since the difference to the best
+    /// path is linear within each region, it is smallest at a corner of one,
+    /// so checking the corners is enough.
+    pub fn preference_for(&self, costs: &[f64]) -> Option<Preference> {
+        self.paths.iter().find_map(|supported| {
+            supported
+                .region
+                .iter()
+                .find(|alpha| {
+                    costs_by_alpha(costs, alpha)
+                        <= costs_by_alpha(&supported.path.total_dimension_costs, alpha) + ACCURACY
+                })
+                .cloned()
+        })
+    }
+}
+
+/// Convex region of preferences, given by the inequalities
+/// `row * alpha <= 0` that bound it within the simplex and by its corners.
+struct Region {
+    constraints: Vec<Costs>,
+    corners: Vec<Corner>,
+}
+
+struct Corner {
+    alpha: Preference,
+    // Indices of the constraints that hold with equality
+    tight: Vec<usize>,
+}
+
+impl Region {
+    /// All preferences, bounded by `alpha[i] >= 0`.
+    fn simplex(dim: usize) -> Region {
+        let constraints = (0..dim)
+            .map(|i| {
+                let mut row = vec![0.0; dim];
+                row[i] = -1.0;
+                row
+            })
+            .collect();
+        let corners = (0..dim)
+            .map(|i| {
+                let mut alpha = vec![0.0; dim];
+                alpha[i] = 1.0;
+                Corner {
+                    alpha,
+                    tight: (0..dim).filter(|&j| j != i).collect(),
+                }
+            })
+            .collect();
+        Region {
+            constraints,
+            corners,
+        }
+    }
+
+    /// Removes the preferences with `row * alpha > 0`. New corners lie on
+    /// the edges between a removed and a remaining corner, so only those
+    /// pairs are intersected with the new constraint (as in the double
+    /// description method).
+    fn cut(&mut self, row: Costs) {
+        let values: Vec<f64> = self
+            .corners
+            .iter()
+            .map(|c| costs_by_alpha(&row, &c.alpha))
+            .collect();
+        let tolerance = ACCURACY * row.iter().map(|r| r.abs()).fold(1.0, f64::max);
+        if values.iter().all(|&v| v <= tolerance) {
+            return;
+        }
+
+        let id = self.constraints.len();
+        self.constraints.push(row);
+        let mut corners = Vec::new();
+        for (outside, &v_out) in values.iter().enumerate().filter(|&(_, &v)| v > tolerance) {
+            for (inside, &v_in) in values.iter().enumerate().filter(|&(_, &v)| v < -tolerance) {
+                if !self.adjacent(outside, inside) {
+                    continue;
+                }
+                let alpha: Preference = self.corners[inside]
+                    .alpha
+                    .iter()
+                    .zip(&self.corners[outside].alpha)
+                    .map(|(a_in, a_out)| (v_out * a_in - v_in * a_out) / (v_out - v_in))
+                    .collect();
+                if !corners
+                    .iter()
+                    .any(|c: &Corner| same_preference(&c.alpha, &alpha))
+                {
+                    let tight = self.tight_at(&alpha);
+                    corners.push(Corner { alpha, tight });
+                }
+            }
+        }
+        for (mut corner, v) in std::mem::take(&mut self.corners).into_iter().zip(values) {
+            if v > tolerance {
+                continue;
+            }
+            if v >= -tolerance {
+                corner.tight.push(id);
+            }
+            if !corners
+                .iter()
+                .any(|c| same_preference(&c.alpha, &corner.alpha))
+            {
+                corners.push(corner);
+            }
+        }
+        self.corners = corners;
+    }
+
+    /// Whether two corners are joined by an edge of the region: they share
+    /// enough tight constraints for an edge, and no other corner lies on
+    /// all of them.
+    fn adjacent(&self, a: usize, b: usize) -> bool {
+        let dim = self.corners[a].alpha.len();
+        let common: Vec<_> = self.corners[a]
+            .tight
+            .iter()
+            .filter(|t| self.corners[b].tight.contains(t))
+            .collect();
+        common.len() + 2 >= dim
+            && !self
+                .corners
+                .iter()
+                .enumerate()
+                .filter(|&(c, _)| c != a && c != b)
+                .any(|(_, corner)| common.iter().all(|t| corner.tight.contains(t)))
+    }
+
+    fn tight_at(&self, alpha: &[f64]) -> Vec<usize> {
+        self.constraints
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| {
+                let tolerance = ACCURACY * row.iter().map(|r| r.abs()).fold(1.0, f64::max);
+                costs_by_alpha(row, alpha).abs() <= tolerance
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+fn difference(a: &[f64], b: &[f64]) -> Costs {
+    a.iter().zip(b).map(|(a, b)| a - b).collect()
+}
+
+fn same_preference(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b).all(|(a, b)| (a - b).abs() < ACCURACY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::test_helpers::{node_pairs, test_graphs};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn supported_paths_form_the_envelope() {
+        let mut rng = StdRng::seed_from_u64(23);
+        for graph in &test_graphs() {
+            let mut dijkstra = Dijkstra::new(graph);
+            let mut multiple_paths = false;
+            for (s, t) in node_pairs(graph) {
+                let supported = SupportedPaths::find(graph, &mut dijkstra, s, t);
+                multiple_paths |= supported.paths.len() > 1;
+                check_envelope(graph, &mut dijkstra, s, t, &supported, &mut rng);
+            }
+            assert!(multiple_paths);
+        }
+    }
+
+    fn check_envelope(
+        graph: &Graph,
+        dijkstra: &mut Dijkstra,
+        s: u32,
+        t: u32,
+        supported: &SupportedPaths,
+        rng: &mut StdRng,
+    ) {
+        let mut best_cost = |alpha: &[f64]| {
+            graph
+                .find_shortest_path(dijkstra, 0, &[s, t], alpha)
+                .map(|p| costs_by_alpha(&p.total_dimension_costs, alpha))
+        };
+        if best_cost(&equal_weights(graph.dim())).is_none() {
+            assert!(supported.paths.is_empty());
+            return;
+        }
+
+        // Every path is optimal at the corners of its region
+        for path in &supported.paths {
+            let costs = &path.path.total_dimension_costs;
+            assert!(!path.region.is_empty());
+            for alpha in &path.region {
+                assert!((alpha.iter().sum::<f64>() - 1.0).abs() < ACCURACY);
+                let best = best_cost(alpha).unwrap();
+                assert!(costs_by_alpha(costs, alpha) <= best + ACCURACY);
+            }
+            assert!(supported.preference_for(costs).is_some());
+        }
+
+        // and no other path is better anywhere
+        for _ in 0..20 {
+            let mut alpha: Preference = (0..graph.dim()).map(|_| rng.gen_range(0.0, 1.0)).collect();
+            let sum: f64 = alpha.iter().sum();
+            alpha.iter_mut().for_each(|a| *a /= sum);
+            let envelope = supported
+                .paths
+                .iter()
+                .map(|p| costs_by_alpha(&p.path.total_dimension_costs, &alpha))
+                .fold(f64::MAX, f64::min);
+            assert!((envelope - best_cost(&alpha).unwrap()).abs() < ACCURACY);
+        }
+        let worse: Costs = supported.paths[0]
+            .path
+            .total_dimension_costs
+            .iter()
+            .map(|c| c + 1.0)
+            .collect();
+        assert!(supported.preference_for(&worse).is_none());
+    }
+}
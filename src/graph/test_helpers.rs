@@ -0,0 +1,58 @@
+//! Helpers shared by the tests of the graph algorithms.
+use crate::graph::{parse_graph_file, parse_minimal_graph_file, Graph};
+use crate::helpers::{add_edge_costs, Costs};
+
+/// The contracted test graphs: minimalTestGraph and concTestGraph.
+pub(crate) fn test_graphs() -> Vec<Graph> {
+    vec![
+        parse_minimal_graph_file("./src/test_graphs/minimalTestGraph")
+            .unwrap()
+            .graph,
+        parse_graph_file("./src/test_graphs/concTestGraph").unwrap(),
+    ]
+}
+
+/// All pairs of nodes of `graph`, as sources and targets of queries.
+pub(crate) fn node_pairs(graph: &Graph) -> impl Iterator<Item = (u32, u32)> {
+    let n = graph.nodes.len() as u32;
+    (0..n).flat_map(move |s| (0..n).map(move |t| (s, t)))
+}
+
+/// Costs of all simple paths from `from` to `to` over original edges, by
+/// brute force.
+pub(crate) fn simple_path_costs(graph: &Graph, from: u32, to: u32) -> Vec<Costs> {
+    fn extend(
+        graph: &Graph,
+        node: u32,
+        to: u32,
+        costs: Costs,
+        visited: &mut Vec<u32>,
+        paths: &mut Vec<Costs>,
+    ) {
+        if node == to {
+            paths.push(costs);
+            return;
+        }
+        for half_edge in graph.get_ch_edges_out(node) {
+            let next = half_edge.target_id;
+            if graph.edges[half_edge.edge_id].replaced_edges.is_some() || visited.contains(&next) {
+                continue;
+            }
+            visited.push(next);
+            let next_costs = add_edge_costs(&costs, &half_edge.edge_costs);
+            extend(graph, next, to, next_costs, visited, paths);
+            visited.pop();
+        }
+    }
+
+    let mut paths = Vec::new();
+    extend(
+        graph,
+        from,
+        to,
+        vec![0.0; graph.dim()],
+        &mut vec![from],
+        &mut paths,
+    );
+    paths
+}
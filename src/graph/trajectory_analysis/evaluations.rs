@@ -1,6 +1,5 @@
 use super::{get_length, get_scalar_product};
 use crate::graph::path::Path;
-use crate::helpers::Costs;
 
 use std::collections::HashSet;
 
@@ -27,7 +26,7 @@ pub fn overlap(path1: &Path, path2: &Path) -> f64 {
 ///
 /// Identical vectors have an angle of 1.0 while orthogonal ones have
 /// an angle of 0.0.
-pub fn cost_angle(cost1: &Costs, cost2: &Costs) -> f64 {
+pub fn cost_angle(cost1: &[f64], cost2: &[f64]) -> f64 {
     let length1 = get_length(cost1);
     let length2 = get_length(cost2);
     let product = get_scalar_product(cost1, cost2);
@@ -37,7 +36,7 @@ pub fn cost_angle(cost1: &Costs, cost2: &Costs) -> f64 {
 /// Calculates length ratio between costs.
 ///
 /// 1.0 means same length, smaller values mean more difference.
-pub fn cost_length_ratio(cost1: &Costs, cost2: &Costs) -> f64 {
+pub fn cost_length_ratio(cost1: &[f64], cost2: &[f64]) -> f64 {
     let length1 = get_length(cost1);
     let length2 = get_length(cost2);
 
@@ -53,18 +52,17 @@ pub fn cost_length_ratio(cost1: &Costs, cost2: &Costs) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::EDGE_COST_DIMENSION;
 
     #[test]
     fn test_angle_between_identical_costs_is_1() {
-        let costs = [1.0; EDGE_COST_DIMENSION];
+        let costs = vec![1.0; 4];
         assert_eq!(1.0, cost_angle(&costs, &costs));
     }
 
     #[test]
     fn test_angle_between_orthongonal_costs_is_0() {
-        let mut costs1 = [0.0; EDGE_COST_DIMENSION];
-        let mut costs2 = [0.0; EDGE_COST_DIMENSION];
+        let mut costs1 = vec![0.0; 4];
+        let mut costs2 = vec![0.0; 4];
 
         costs1[0] = 1.0;
         costs2[1] = 1.0;
@@ -5,7 +5,6 @@ use super::Graph;
 use crate::helpers::{add_edge_costs, costs_by_alpha, Costs, MyVec, Preference};
 use crate::lp::{LpProcess, PreferenceEstimator};
 use crate::MyResult;
-use crate::EDGE_COST_DIMENSION;
 
 pub mod evaluations;
 
@@ -265,7 +264,7 @@ impl<'a, 'b> TrajectoryAnalysis<'a, 'b> {
                 if start == m {
                     let res = SinglePreferenceDecomposition {
                         cuts,
-                        preference: [-1.0; EDGE_COST_DIMENSION],
+                        preference: vec![-1.0; self.graph.dim()],
                     };
                     return Ok(res);
                 }
@@ -281,7 +280,7 @@ impl<'a, 'b> TrajectoryAnalysis<'a, 'b> {
                 let new_constraints_by_path = res.1;
                 for i in 0..new_constraints_by_path.len() - 1 {
                     for c in new_constraints_by_path[i].iter() {
-                        constraints.push(*c);
+                        constraints.push(c.clone());
                     }
                 }
                 let pref = res.0;
@@ -291,7 +290,7 @@ impl<'a, 'b> TrajectoryAnalysis<'a, 'b> {
                     best_cut = m;
                     best_subpath = subpath;
                     for c in new_constraints_by_path[new_constraints_by_path.len() - 1].iter() {
-                        constraints.push(*c);
+                        constraints.push(c.clone());
                     }
                 } else {
                     high = m;
@@ -320,12 +319,12 @@ impl<'a, 'b> TrajectoryAnalysis<'a, 'b> {
         let mut cuts = MyVec::new();
         let mut start = 0u32;
         let mut costs_until_edge = Vec::new();
-        let mut sum_costs: Costs = [0.0; EDGE_COST_DIMENSION];
+        let mut sum_costs: Costs = vec![0.0; self.graph.dim()];
         let accuracy = 0.0001;
-        costs_until_edge.push(sum_costs);
+        costs_until_edge.push(sum_costs.clone());
         for edge in path.edges.iter() {
             sum_costs = add_edge_costs(&sum_costs, &self.graph.edges[*edge as usize].edge_costs);
-            costs_until_edge.push(sum_costs);
+            costs_until_edge.push(sum_costs.clone());
         }
 
         while start < path_length - 1 {
@@ -344,17 +343,17 @@ impl<'a, 'b> TrajectoryAnalysis<'a, 'b> {
                         self.dijkstra,
                         0,
                         &[path.nodes[start], path.nodes[m]],
-                        preference,
+                        &preference,
                     )
                     .unwrap();
-                let mut costs_subpath = [0.0; EDGE_COST_DIMENSION];
-                for i in 0..EDGE_COST_DIMENSION {
+                let mut costs_subpath = vec![0.0; self.graph.dim()];
+                for i in 0..self.graph.dim() {
                     costs_subpath[i] =
                         costs_until_edge[m as usize][i] - costs_until_edge[start as usize][i];
                 }
                 //DEBUG
                 let subpath = path.get_subpath(self.graph, start, m + 1);
-                for i in 0..EDGE_COST_DIMENSION {
+                for i in 0..self.graph.dim() {
                     if subpath.total_dimension_costs[i] - costs_subpath[i] > accuracy
                         || subpath.total_dimension_costs[i] - costs_subpath[i] < -accuracy
                     {
@@ -364,6 +363,10 @@ impl<'a, 'b> TrajectoryAnalysis<'a, 'b> {
                     }
                 }
                 //DEBUG
+                if let Some(turns) = self.dijkstra.turns() {
+                    let turn_costs = turns.path_costs(self.graph, &path.edges[start..m]);
+                    costs_subpath = add_edge_costs(&costs_subpath, &turn_costs);
+                }
                 let diff = costs_by_alpha(&costs_subpath, &preference)
                     - costs_by_alpha(&optimal_path.total_dimension_costs, &preference);
                 if diff < accuracy {
@@ -427,7 +430,7 @@ pub struct SinglePreferenceDecomposition {
 pub fn get_linear_combination(costs_per_metric: &[Costs], real_costs: &[f64]) -> Preference {
     let mut finished: bool = false;
     let dim: usize = real_costs.len();
-    let mut alpha = [0.0; EDGE_COST_DIMENSION];
+    let mut alpha = vec![0.0; dim];
     let mut rest: Vec<f64> = real_costs.to_vec();
     //let mut distance: f64 = get_length(&real_costs); distance was set but never used
     let mut normalized_costs_per_metric: Vec<Vec<f64>> = Vec::new();
@@ -517,23 +520,22 @@ pub fn get_length(vec: &[f64]) -> f64 {
 mod tests {
     use super::*;
     use crate::graph::*;
-    use crate::helpers::EQUAL_WEIGHTS;
-    use crate::EDGE_COST_DIMENSION;
+    use crate::helpers::equal_weights;
 
     #[test]
     fn test_no_non_optimal_subpath() {
         let linegraph = Graph::new(
             vec![Node::new(0, 0), Node::new(1, 0)],
-            vec![Edge::new(0, 0, 1, [1.0; EDGE_COST_DIMENSION], None)],
+            vec![Edge::new(0, 0, 1, vec![1.0; 4], None)],
         );
 
         let mut d = Dijkstra::new(&linegraph);
 
         let mut path = linegraph
-            .find_shortest_path(&mut d, 0, &[0, 1], EQUAL_WEIGHTS)
+            .find_shortest_path(&mut d, 0, &[0, 1], &equal_weights(4))
             .unwrap();
 
-        let mut lp = LpProcess::new().unwrap();
+        let mut lp = LpProcess::new(4).unwrap();
 
         let mut ta = TrajectoryAnalysis::new(&linegraph, &mut d, &mut lp);
 
@@ -553,7 +555,7 @@ mod tests {
         //		     |	     1   |
         //		     +-----------+
 
-        let one_cost = [1.0; EDGE_COST_DIMENSION];
+        let one_cost = vec![1.0; 4];
 
         let graph = Graph::new(
             vec![
@@ -564,19 +566,19 @@ mod tests {
                 Node::new(4, 0), // t
             ],
             vec![
-                Edge::new(0, 0, 1, one_cost, None),
-                Edge::new(1, 1, 2, one_cost, None),
-                Edge::new(2, 2, 3, one_cost, None),
-                Edge::new(3, 3, 4, one_cost, None),
-                Edge::new(4, 1, 3, one_cost, None), // lower edge that skips one node
+                Edge::new(0, 0, 1, one_cost.clone(), None),
+                Edge::new(1, 1, 2, one_cost.clone(), None),
+                Edge::new(2, 2, 3, one_cost.clone(), None),
+                Edge::new(3, 3, 4, one_cost.clone(), None),
+                Edge::new(4, 1, 3, one_cost.clone(), None), // lower edge that skips one node
             ],
         );
 
         let mut d = Dijkstra::new(&graph);
         let mut path = graph
-            .find_shortest_path(&mut d, 0, &[0, 2, 4], EQUAL_WEIGHTS)
+            .find_shortest_path(&mut d, 0, &[0, 2, 4], &equal_weights(4))
             .unwrap();
-        let mut lp = LpProcess::new().unwrap();
+        let mut lp = LpProcess::new(4).unwrap();
         let mut ta = TrajectoryAnalysis::new(&graph, &mut d, &mut lp);
 
         let non_opts = ta.find_non_optimal_segments(&mut path).unwrap();
@@ -597,7 +599,7 @@ mod tests {
         //		     |	     1       |
         //		     +---------------+
 
-        let one_cost = [1.0; EDGE_COST_DIMENSION];
+        let one_cost = vec![1.0; 4];
 
         let graph = Graph::new(
             vec![
@@ -609,20 +611,20 @@ mod tests {
                 Node::new(5, 0), // t
             ],
             vec![
-                Edge::new(0, 0, 1, one_cost, None),
-                Edge::new(1, 1, 2, one_cost, None),
-                Edge::new(2, 2, 3, one_cost, None),
-                Edge::new(3, 3, 4, one_cost, None),
-                Edge::new(4, 4, 5, one_cost, None),
-                Edge::new(5, 1, 4, one_cost, None), // lower edge that skips two nodes
+                Edge::new(0, 0, 1, one_cost.clone(), None),
+                Edge::new(1, 1, 2, one_cost.clone(), None),
+                Edge::new(2, 2, 3, one_cost.clone(), None),
+                Edge::new(3, 3, 4, one_cost.clone(), None),
+                Edge::new(4, 4, 5, one_cost.clone(), None),
+                Edge::new(5, 1, 4, one_cost.clone(), None), // lower edge that skips two nodes
             ],
         );
 
         let mut d = Dijkstra::new(&graph);
         let mut path = graph
-            .find_shortest_path(&mut d, 0, &[0, 2, 5], EQUAL_WEIGHTS)
+            .find_shortest_path(&mut d, 0, &[0, 2, 5], &equal_weights(4))
             .unwrap();
-        let mut lp = LpProcess::new().unwrap();
+        let mut lp = LpProcess::new(4).unwrap();
         let mut ta = TrajectoryAnalysis::new(&graph, &mut d, &mut lp);
 
         let non_opts = ta.find_non_optimal_segments(&mut path).unwrap();
@@ -646,7 +648,7 @@ mod tests {
         //		         |	     1       |
         //		         +---------------+
 
-        let one_cost = [1.0; EDGE_COST_DIMENSION];
+        let one_cost = vec![1.0; 4];
 
         let graph = Graph::new(
             vec![
@@ -659,22 +661,22 @@ mod tests {
                 Node::new(6, 0), // t
             ],
             vec![
-                Edge::new(0, 0, 1, one_cost, None),
-                Edge::new(1, 1, 2, one_cost, None),
-                Edge::new(2, 2, 3, one_cost, None),
-                Edge::new(3, 3, 4, one_cost, None),
-                Edge::new(4, 4, 5, one_cost, None),
-                Edge::new(5, 4, 6, one_cost, None),
-                Edge::new(6, 1, 3, one_cost, None), // lower edge that skips one node
-                Edge::new(7, 2, 4, one_cost, None), // upper edge that skips one node
+                Edge::new(0, 0, 1, one_cost.clone(), None),
+                Edge::new(1, 1, 2, one_cost.clone(), None),
+                Edge::new(2, 2, 3, one_cost.clone(), None),
+                Edge::new(3, 3, 4, one_cost.clone(), None),
+                Edge::new(4, 4, 5, one_cost.clone(), None),
+                Edge::new(5, 4, 6, one_cost.clone(), None),
+                Edge::new(6, 1, 3, one_cost.clone(), None), // lower edge that skips one node
+                Edge::new(7, 2, 4, one_cost.clone(), None), // upper edge that skips one node
             ],
         );
 
         let mut d = Dijkstra::new(&graph);
         let mut path = graph
-            .find_shortest_path(&mut d, 0, &[0, 2, 3, 6], EQUAL_WEIGHTS)
+            .find_shortest_path(&mut d, 0, &[0, 2, 3, 6], &equal_weights(4))
             .unwrap();
-        let mut lp = LpProcess::new().unwrap();
+        let mut lp = LpProcess::new(4).unwrap();
         let mut ta = TrajectoryAnalysis::new(&graph, &mut d, &mut lp);
 
         let non_opts = ta.find_non_optimal_segments(&mut path).unwrap();
@@ -0,0 +1,209 @@
+//! Turn restrictions and turn costs between consecutive edges.
+//!
+//! Turns are read from a text file with one turn per line, given by the
+//! external names of its two edges:
+//!
+//! ```text
+//! # from to [costs...]
+//! 17 18
+//! 17 19 4.5 0
+//! ```
+//!
+//! A turn without costs is forbidden, otherwise it carries one additional
+//! cost per metric of the graph. Searches only respect turns in their
+//! edge-based mode, see `Dijkstra::with_turns` and `NDijkstra::with_turns`.
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::graph::{Edge, Graph};
+use crate::graphml::GraphData;
+use crate::helpers::{add_edge_costs, Costs};
+use crate::{MyError, MyResult};
+
+#[derive(Debug, Default, Clone)]
+pub struct Turns {
+    forbidden: HashSet<(u32, u32)>,
+    costs: HashMap<(u32, u32), Costs>,
+    /// Forbids turning back onto the reverse edge, even where no restriction
+    /// is given. Dead ends can then only be left by other edges.
+    pub forbid_u_turns: bool,
+}
+
+impl Turns {
+    pub fn new() -> Turns {
+        Default::default()
+    }
+
+    pub fn forbid(&mut self, from: u32, to: u32) {
+        self.forbidden.insert((from, to));
+    }
+
+    pub fn set_costs(&mut self, from: u32, to: u32, costs: Costs) {
+        self.costs.insert((from, to), costs);
+    }
+
+    /// Whether a path may continue with edge `to` after edge `from`.
+    pub fn is_allowed(&self, from: &Edge, to: &Edge) -> bool {
+        if self.forbid_u_turns && from.source_id == to.target_id {
+            return false;
+        }
+        !self.forbidden.contains(&(from.id, to.id))
+    }
+
+    /// Additional costs of continuing with edge `to` after edge `from`.
+    pub fn costs(&self, from: u32, to: u32) -> Option<&Costs> {
+        self.costs.get(&(from, to))
+    }
+
+    /// Sum of the turn costs along `edges`.
+    pub fn path_costs(&self, graph: &Graph, edges: &[u32]) -> Costs {
+        edges
+            .windows(2)
+            .filter_map(|turn| self.costs(turn[0], turn[1]))
+            .fold(vec![0.0; graph.dim()], |sum, costs| {
+                add_edge_costs(&sum, costs)
+            })
+    }
+}
+
+/// Reads turn restrictions and costs for the edges of `graph_data` from a
+/// file in the format described in the module documentation.
+pub fn read_turns<P: AsRef<Path>>(file_path: P, graph_data: &GraphData) -> MyResult<Turns> {
+    let file_name = file_path.as_ref().display().to_string();
+    let reader = BufReader::new(File::open(file_path)?);
+    let GraphData {
+        graph, edge_lookup, ..
+    } = graph_data;
+
+    let mut turns = Turns::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let error = |reason: String| {
+            Box::new(MyError::InvalidGraphFile {
+                file: file_name.clone(),
+                line: i + 1,
+                reason,
+            })
+        };
+        let tokens: Vec<_> = line.split_whitespace().collect();
+        if tokens.is_empty() || tokens[0].starts_with('#') {
+            continue;
+        }
+        if tokens.len() < 2 {
+            return Err(error(format!("expected two edges, got '{}'", line)));
+        }
+
+        let edge = |name: &str| {
+            edge_lookup
+                .get(name)
+                .ok_or_else(|| error(format!("unknown edge {}", name)))
+        };
+        let (from, to) = (edge(tokens[0])?, edge(tokens[1])?);
+        if graph.edges[from].target_id != graph.edges[to].source_id {
+            return Err(error(format!(
+                "edges {} and {} are not consecutive",
+                tokens[0], tokens[1]
+            )));
+        }
+
+        match tokens.len() - 2 {
+            0 => turns.forbid(from, to),
+            dim if dim == graph.dim() => {
+                let costs = tokens[2..]
+                    .iter()
+                    .map(|c| match c.parse::<f64>() {
+                        Ok(cost) if cost >= 0.0 => Ok(cost),
+                        _ => Err(error(format!("invalid turn cost {}", c))),
+                    })
+                    .collect::<Result<Costs, _>>()?;
+                turns.set_costs(from, to, costs);
+            }
+            dim => {
+                return Err(error(format!(
+                    "expected {} turn costs, got {}",
+                    graph.dim(),
+                    dim
+                )))
+            }
+        }
+    }
+    Ok(turns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dijkstra::{Dijkstra, NDijkstra};
+    use crate::graph::Node;
+
+    // 0 -> 1 -> 2 straight on, with a detour 1 -> 3 -> 2 and the reverse
+    // edges 1 -> 0 and 2 -> 1
+    fn junction() -> GraphData {
+        let nodes = (0..4).map(|id| Node::new(id, 0)).collect();
+        let edges = [
+            (0, 1, 1.0),
+            (1, 2, 1.0),
+            (1, 3, 1.0),
+            (3, 2, 1.5),
+            (1, 0, 1.0),
+            (2, 1, 1.0),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(id, &(s, t, c))| Edge::new(id as u32, s, t, vec![c], None))
+        .collect();
+        let graph = Graph::new(nodes, edges);
+        GraphData {
+            edge_lookup: graph
+                .edges
+                .iter()
+                .map(|e| (i64::from(e.id), e.id))
+                .collect(),
+            graph,
+            keys: Default::default(),
+        }
+    }
+
+    #[test]
+    fn searches_respect_turns() {
+        let graph_data = junction();
+        let graph = &graph_data.graph;
+        let path = std::env::temp_dir().join("ppts_turns");
+        std::fs::write(&path, "# from to costs\n0 1\n0 2 0.25\n").unwrap();
+        let turns = read_turns(&path, &graph_data).unwrap();
+
+        let mut dijkstra = Dijkstra::with_turns(graph, &turns);
+        let result = dijkstra.run(0, 2, &[1.0]).unwrap();
+        assert_eq!(result.edges.0, vec![0, 2, 3]);
+        assert_eq!(result.costs, vec![3.75]);
+        assert_eq!(result.total_cost, 3.75);
+        assert_eq!(turns.path_costs(graph, &result.edges), vec![0.25]);
+
+        let mut n_dijkstra = NDijkstra::with_turns(graph, &turns);
+        assert_eq!(n_dijkstra.run(0, 2, &[1.0]), Some(3.75));
+        assert_eq!(n_dijkstra.path(2).unwrap().edges.0, vec![0, 2, 3]);
+        assert_eq!(n_dijkstra.run(0, 0, &[1.0]), Some(0.0));
+
+        // without the turn into the detour, node 3 can only be reached by
+        // turning back at node 2
+        let mut turns = Turns::new();
+        turns.forbid(0, 2);
+        let mut dijkstra = Dijkstra::with_turns(graph, &turns);
+        assert_eq!(
+            dijkstra.run(0, 3, &[1.0]).unwrap().edges.0,
+            vec![0, 1, 5, 2]
+        );
+        turns.forbid_u_turns = true;
+        let mut dijkstra = Dijkstra::with_turns(graph, &turns);
+        assert!(dijkstra.run(0, 3, &[1.0]).is_none());
+        assert!(dijkstra.run(0, 0, &[1.0]).unwrap().edges.is_empty());
+
+        std::fs::write(&path, "0 3\n").unwrap();
+        let error = read_turns(&path, &graph_data).err().unwrap();
+        assert!(error.to_string().contains("not consecutive"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,105 @@
+use crate::graph::Graph;
+use crate::helpers::MyVec;
+
+/// Original edges of every edge, stored one after the other.
+#[derive(Debug)]
+pub(super) struct UnpackingTable {
+    offsets: MyVec<u32>,
+    edges: MyVec<u32>,
+}
+
+/// Iterator over the original edges a (shortcut) edge replaces, in path
+/// order. See `Graph::unpacked_edges`.
+pub struct UnpackedEdges<'a> {
+    graph: &'a Graph,
+    table: Option<std::slice::Iter<'a, u32>>,
+    next: Option<u32>,
+    // Second halves of the shortcuts currently being unpacked
+    pending: Vec<u32>,
+}
+
+impl<'a> Iterator for UnpackedEdges<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if let Some(table) = self.table.as_mut() {
+            return table.next().copied();
+        }
+
+        let mut edge = self.next.take().or_else(|| self.pending.pop())?;
+        while let Some((first, second)) = self.graph.edges[edge].replaced_edges {
+            self.pending.push(second);
+            edge = first;
+        }
+        Some(edge)
+    }
+}
+
+impl Graph {
+    /// Original edges of `edge` without collecting them. Reads them from the
+    /// unpacking table if there is one, otherwise walks the shortcut tree
+    /// with one stack that is only allocated for shortcuts.
+    ///
+    /// Unlike `unpack_edge`, the shortcuts are not checked for consistency,
+    /// use `Graph::validate` for that.
+    pub fn unpacked_edges(&self, edge: u32) -> UnpackedEdges<'_> {
+        let table = self.unpacking.as_ref().map(|table| {
+            let (start, end) = (table.offsets[edge], table.offsets[edge + 1]);
+            table.edges[start..end].iter()
+        });
+        UnpackedEdges {
+            graph: self,
+            table,
+            next: Some(edge),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Precomputes the original edges of all edges, so that unpacking does
+    /// not need to walk shortcuts anymore. This trades memory (the total
+    /// length of all unpacked shortcuts) for query time.
+    ///
+    /// The table has to be rebuilt if shortcuts are changed afterwards.
+    pub fn build_unpacking_table(&mut self) {
+        self.unpacking = None;
+        let mut offsets = MyVec(Vec::with_capacity(self.edges.len() + 1));
+        let mut edges = MyVec::new();
+        offsets.push(0);
+        for edge in 0..self.edges.len() as u32 {
+            edges.extend(self.unpacked_edges(edge));
+            offsets.push(edges.len() as u32);
+        }
+        self.unpacking = Some(UnpackingTable { offsets, edges });
+    }
+
+    pub fn has_unpacking_table(&self) -> bool {
+        self.unpacking.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::parse_minimal_graph_file;
+
+    #[test]
+    fn unpacking_methods_agree() {
+        let mut graph = parse_minimal_graph_file("./src/test_graphs/minimalTestGraph")
+            .unwrap()
+            .graph;
+        let edge_count = graph.edges.len() as u32;
+        let unpacked: Vec<Vec<u32>> = (0..edge_count).map(|e| graph.unpack_edge(e)).collect();
+        assert!(unpacked.iter().any(|edges| edges.len() > 2));
+
+        for e in 0..edge_count {
+            let walked: Vec<_> = graph.unpacked_edges(e).collect();
+            assert_eq!(walked, unpacked[e as usize]);
+        }
+
+        graph.build_unpacking_table();
+        assert!(graph.has_unpacking_table());
+        for e in 0..edge_count {
+            let looked_up: Vec<_> = graph.unpacked_edges(e).collect();
+            assert_eq!(looked_up, unpacked[e as usize]);
+        }
+    }
+}
@@ -0,0 +1,287 @@
+use std::fmt::{Display, Formatter};
+
+use crate::graph::{Edge, Graph};
+use crate::helpers::MyVec;
+
+/// Relative tolerance for shortcut costs compared to the sum of the costs of
+/// their replaced edges.
+const COST_TOLERANCE: f64 = 0.000_001;
+
+/// Problems found by `Graph::validate`. Every list holds edge ids, except
+/// `misplaced_nodes` which holds node indices.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    /// Nodes whose id differs from their index
+    pub misplaced_nodes: Vec<u32>,
+    /// Edges whose id differs from their index
+    pub misplaced_edges: Vec<u32>,
+    /// Edges with a source or target that is no node of the graph
+    pub dangling_edges: Vec<u32>,
+    /// Edges with a different number of metrics than the graph
+    pub metric_mismatches: Vec<u32>,
+    /// Shortcuts replacing edges that do not exist
+    pub unknown_replaced_edges: Vec<u32>,
+    /// Shortcuts whose replaced edges do not form a path from their source
+    /// to their target
+    pub disconnected_shortcuts: Vec<u32>,
+    /// Shortcuts that (transitively) replace themselves
+    pub cyclic_shortcuts: Vec<u32>,
+    /// Shortcuts whose costs are not the sum of the replaced edges' costs
+    pub cost_mismatches: Vec<u32>,
+    /// Shortcuts whose middle node is not below both end nodes in the
+    /// hierarchy, so that the upward searches of `Dijkstra` can not find
+    /// paths through it
+    pub level_violations: Vec<u32>,
+    /// Largest number of nested shortcuts unpacking an edge has to go through
+    pub max_depth: usize,
+    /// Edge with `max_depth` nested shortcuts
+    pub deepest_edge: Option<u32>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.misplaced_nodes.is_empty()
+            && self.misplaced_edges.is_empty()
+            && self.dangling_edges.is_empty()
+            && self.metric_mismatches.is_empty()
+            && self.unknown_replaced_edges.is_empty()
+            && self.disconnected_shortcuts.is_empty()
+            && self.cyclic_shortcuts.is_empty()
+            && self.cost_mismatches.is_empty()
+            && self.level_violations.is_empty()
+    }
+
+    fn problems(&self) -> [(&'static str, &[u32]); 9] {
+        [
+            ("nodes with wrong id", &self.misplaced_nodes),
+            ("edges with wrong id", &self.misplaced_edges),
+            ("edges with unknown nodes", &self.dangling_edges),
+            (
+                "edges with wrong number of metrics",
+                &self.metric_mismatches,
+            ),
+            ("shortcuts with unknown edges", &self.unknown_replaced_edges),
+            ("disconnected shortcuts", &self.disconnected_shortcuts),
+            ("cyclic shortcuts", &self.cyclic_shortcuts),
+            ("shortcuts with wrong costs", &self.cost_mismatches),
+            ("shortcuts violating CH levels", &self.level_violations),
+        ]
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        for (description, ids) in self.problems().iter() {
+            write!(f, "{}: {}", description, ids.len())?;
+            if !ids.is_empty() {
+                let shown: Vec<_> = ids.iter().take(10).map(u32::to_string).collect();
+                let more = if ids.len() > shown.len() { ", ..." } else { "" };
+                write!(f, " ({}{})", shown.join(", "), more)?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "maximum shortcut depth: {}", self.max_depth)?;
+        if let Some(edge) = self.deepest_edge {
+            write!(f, " (edge {})", edge)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Visit {
+    New,
+    Active,
+    Done(usize),
+}
+
+impl Graph {
+    /// Checks the graph for inconsistencies that would make queries or
+    /// unpacking fail or return wrong results.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = check_replaced_edges(&self.edges);
+        let node_count = self.nodes.len() as u32;
+        let edge_count = self.edges.len() as u32;
+
+        for (i, n) in self.nodes.iter().enumerate() {
+            if n.id != i as u32 {
+                report.misplaced_nodes.push(i as u32);
+            }
+        }
+
+        for (i, e) in self.edges.iter().enumerate() {
+            let i = i as u32;
+            if e.id != i {
+                report.misplaced_edges.push(i);
+            }
+            if e.edge_costs.len() != self.dim {
+                report.metric_mismatches.push(i);
+            }
+            if e.source_id >= node_count || e.target_id >= node_count {
+                report.dangling_edges.push(i);
+                continue;
+            }
+            let (edge_a, edge_b) = match e.replaced_edges {
+                Some((a, b)) if a < edge_count && b < edge_count => (a, b),
+                _ => continue,
+            };
+            let (edge_a, edge_b) = (&self.edges[edge_a], &self.edges[edge_b]);
+            if edge_a.source_id != e.source_id
+                || edge_a.target_id != edge_b.source_id
+                || edge_b.target_id != e.target_id
+            {
+                report.disconnected_shortcuts.push(i);
+                continue;
+            }
+
+            let costs_match = e.edge_costs.len() == edge_a.edge_costs.len()
+                && e.edge_costs.len() == edge_b.edge_costs.len()
+                && e.edge_costs
+                    .iter()
+                    .zip(edge_a.edge_costs.iter().zip(edge_b.edge_costs.iter()))
+                    .all(|(c, (a, b))| {
+                        (c - (a + b)).abs() <= COST_TOLERANCE * (a + b).abs().max(1.0)
+                    });
+            if !costs_match {
+                report.cost_mismatches.push(i);
+            }
+
+            let middle = edge_a.target_id;
+            if middle < node_count {
+                let level = self.nodes[middle].ch_level;
+                if level >= self.nodes[e.source_id].ch_level
+                    || level >= self.nodes[e.target_id].ch_level
+                {
+                    report.level_violations.push(i);
+                }
+            }
+        }
+
+        report
+    }
+}
+
+/// Only the checks of `Graph::validate` that need nothing but the edges:
+/// unknown replaced edges, cyclic shortcuts and the shortcut depths. The
+/// loaders run these before building a `Graph`, as unpacking or
+/// customizing such shortcuts panics.
+pub(crate) fn check_replaced_edges(edges: &[Edge]) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let edge_count = edges.len() as u32;
+    for (i, e) in edges.iter().enumerate() {
+        if let Some((a, b)) = e.replaced_edges {
+            if a >= edge_count || b >= edge_count {
+                report.unknown_replaced_edges.push(i as u32);
+            }
+        }
+    }
+    check_depths(edges, &mut report);
+    report
+}
+
+/// Computes the shortcut depths without recursion, so arbitrarily deep
+/// (or cyclic) shortcuts do not overflow the stack.
+fn check_depths(edges: &[Edge], report: &mut ValidationReport) {
+    let edge_count = edges.len() as u32;
+    let mut visits = MyVec(vec![Visit::New; edges.len()]);
+
+    for start in 0..edge_count {
+        if let Visit::Done(_) = visits[start] {
+            continue;
+        }
+        let mut stack = vec![start];
+        while let Some(&edge) = stack.last() {
+            let children = match edges[edge as usize].replaced_edges {
+                Some((a, b)) if a < edge_count && b < edge_count => vec![a, b],
+                _ => Vec::new(),
+            };
+            if let Visit::New = visits[edge] {
+                visits[edge] = Visit::Active;
+                for &child in children.iter() {
+                    match visits[child] {
+                        Visit::New => stack.push(child),
+                        Visit::Active => report.cyclic_shortcuts.push(edge),
+                        Visit::Done(_) => (),
+                    }
+                }
+                continue;
+            }
+
+            stack.pop();
+            if let Visit::Active = visits[edge] {
+                let depth = children
+                    .iter()
+                    .map(|&child| match visits[child] {
+                        Visit::Done(depth) => depth + 1,
+                        _ => 0,
+                    })
+                    .max()
+                    .unwrap_or(0);
+                visits[edge] = Visit::Done(depth);
+                if depth > report.max_depth {
+                    report.max_depth = depth;
+                    report.deepest_edge = Some(edge);
+                }
+            }
+        }
+    }
+    report.cyclic_shortcuts.sort_unstable();
+    report.cyclic_shortcuts.dedup();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{parse_minimal_graph_file, parse_minimal_graph_file_unchecked};
+
+    #[test]
+    fn contracted_graph_is_valid() {
+        let graph_data = parse_minimal_graph_file("./src/test_graphs/minimalTestGraph").unwrap();
+        let report = graph_data.graph.validate();
+        assert!(report.is_valid(), "{}", report);
+        assert!(report.max_depth > 0);
+    }
+
+    #[test]
+    fn broken_shortcuts_are_reported() {
+        let mut graph = parse_minimal_graph_file("./src/test_graphs/minimalTestGraph")
+            .unwrap()
+            .graph;
+        let shortcuts: Vec<_> = graph
+            .edges
+            .iter()
+            .filter(|e| e.replaced_edges.is_some())
+            .map(|e| e.id)
+            .collect();
+
+        graph.edges[shortcuts[0]].edge_costs[0] += 1.0;
+        let (a, b) = graph.edges[shortcuts[1]].replaced_edges.unwrap();
+        graph.edges[shortcuts[1]].replaced_edges = Some((b, a));
+        graph.edges[shortcuts[2]].replaced_edges = Some((shortcuts[2], shortcuts[2]));
+
+        let report = graph.validate();
+        assert!(!report.is_valid());
+        assert_eq!(report.cost_mismatches, vec![shortcuts[0]]);
+        assert!(report.disconnected_shortcuts.contains(&shortcuts[1]));
+        assert!(report.cyclic_shortcuts.contains(&shortcuts[2]));
+    }
+
+    #[test]
+    fn dangling_edges_are_reported() {
+        let path = std::env::temp_dir().join("ppts_dangling_graph");
+        std::fs::write(
+            &path,
+            "# comment\n\n1\na\n2\n2\n0 0\n1 0\n0 0 1 1.5 -1 -1\n1 1 7 2.5 -1 -1\n",
+        )
+        .unwrap();
+
+        let graph = parse_minimal_graph_file_unchecked(&path).unwrap().graph;
+        std::fs::remove_file(path).unwrap();
+        let report = graph.validate();
+        assert!(!report.is_valid());
+        assert_eq!(report.dangling_edges, vec![1]);
+        assert!(report
+            .to_string()
+            .contains("edges with unknown nodes: 1 (1)"));
+        assert_eq!(graph.get_ch_edges_out(1).len(), 0);
+    }
+}
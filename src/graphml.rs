@@ -1,15 +1,21 @@
 use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::iter::FromIterator;
+use std::ops::Index;
 
-use std::io::Read;
+use std::io::BufReader;
 use std::path::Path;
 
-use std::collections::{BTreeMap, HashMap};
-use std::convert::TryInto;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use crate::graph::{Edge, Graph, Node};
-use crate::EDGE_COST_DIMENSION;
+use crate::cache;
+use crate::graph::validation::check_replaced_edges;
+use crate::graph::{Coordinates, Edge, Graph, Node};
+use crate::helpers::Costs;
+use crate::MyError;
 
-use roxmltree::Document;
+use xml::common::Position;
+use xml::reader::{EventReader, XmlEvent};
 
 #[derive(Debug)]
 enum GraphObject {
@@ -21,7 +27,10 @@ enum GraphObject {
 pub enum AttributeType {
     Boolean,
     String,
+    /// Edge metric with its index in the cost vectors
     Double(usize),
+    /// Double valued node attribute like `lat` and `lon`
+    NodeDouble,
     Long,
 }
 
@@ -32,34 +41,231 @@ pub struct GraphmlAttribute {
     pub attribute_type: AttributeType,
 }
 
-pub type EdgeLookup = HashMap<String, u32>;
-
 impl<'a> GraphmlAttribute {
     pub fn new(
         obj_type: &'a str,
         name: &'a str,
         attribute_type: &'a str,
         metric_count: usize,
-    ) -> GraphmlAttribute {
+    ) -> Result<GraphmlAttribute, MyError> {
         let obj_type = match obj_type {
             "node" => GraphObject::Node,
             "edge" => GraphObject::Edge,
-            _ => panic!("unknown graph object type"),
+            _ => return Err(MyError::UnknownGraphObject(obj_type.to_owned())),
         };
 
         let attribute_type = match attribute_type {
             "boolean" => AttributeType::Boolean,
             "string" => AttributeType::String,
             "long" => AttributeType::Long,
-            "double" => AttributeType::Double(metric_count),
-            _ => panic!("unkown attribute type"),
+            "double" => match obj_type {
+                GraphObject::Edge => AttributeType::Double(metric_count),
+                GraphObject::Node => AttributeType::NodeDouble,
+            },
+            _ => return Err(MyError::UnknownAttributeType(attribute_type.to_owned())),
         };
 
-        GraphmlAttribute {
+        Ok(GraphmlAttribute {
             _obj_type: obj_type,
             name: name.to_owned(),
             attribute_type,
+        })
+    }
+
+    /// Edge metric with the given index in the cost vectors.
+    pub fn metric(name: &str, index: usize) -> GraphmlAttribute {
+        GraphmlAttribute {
+            _obj_type: GraphObject::Edge,
+            name: name.to_owned(),
+            attribute_type: AttributeType::Double(index),
+        }
+    }
+}
+
+/// External name of an edge, as used by trajectories and graph files.
+/// Names that are plain decimal integers are stored as numbers, so
+/// trajectories can be mapped without formatting their edge ids.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EdgeName {
+    Number(i64),
+    Text(String),
+}
+
+impl EdgeName {
+    pub fn as_number(&self) -> Option<i64> {
+        match self {
+            EdgeName::Number(number) => Some(*number),
+            EdgeName::Text(_) => None,
+        }
+    }
+
+    /// The number `name` represents, if formatting it gives `name` back.
+    fn parse_number(name: &str) -> Option<i64> {
+        let digits = name.strip_prefix('-').unwrap_or(name);
+        if digits.starts_with('+') || (digits.starts_with('0') && name != "0") {
+            return None;
+        }
+        name.parse().ok()
+    }
+}
+
+impl From<String> for EdgeName {
+    fn from(name: String) -> EdgeName {
+        match EdgeName::parse_number(&name) {
+            Some(number) => EdgeName::Number(number),
+            None => EdgeName::Text(name),
+        }
+    }
+}
+
+impl From<&str> for EdgeName {
+    fn from(name: &str) -> EdgeName {
+        match EdgeName::parse_number(name) {
+            Some(number) => EdgeName::Number(number),
+            None => EdgeName::Text(name.to_owned()),
+        }
+    }
+}
+
+impl From<i64> for EdgeName {
+    fn from(number: i64) -> EdgeName {
+        EdgeName::Number(number)
+    }
+}
+
+impl Display for EdgeName {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            EdgeName::Number(number) => write!(f, "{}", number),
+            EdgeName::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+/// Bidirectional index between external edge names and edge ids. Every
+/// edge has at most one name, inserting another name for an edge or
+/// another edge for a name replaces the old mapping.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EdgeLookup {
+    numbers: HashMap<i64, u32>,
+    texts: HashMap<String, u32>,
+    names: Vec<Option<EdgeName>>,
+}
+
+impl EdgeLookup {
+    pub fn new() -> EdgeLookup {
+        Default::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> EdgeLookup {
+        EdgeLookup {
+            numbers: HashMap::with_capacity(capacity),
+            texts: HashMap::new(),
+            names: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Maps `name` to the edge `id` and returns the edge previously known
+    /// under `name`.
+    pub fn insert<N: Into<EdgeName>>(&mut self, name: N, id: u32) -> Option<u32> {
+        let name = name.into();
+        let previous = match &name {
+            EdgeName::Number(number) => self.numbers.insert(*number, id),
+            EdgeName::Text(text) => self.texts.insert(text.clone(), id),
+        };
+        if let Some(previous) = previous.filter(|&p| p != id) {
+            self.names[previous as usize] = None;
+        }
+
+        if self.names.len() <= id as usize {
+            self.names.resize(id as usize + 1, None);
         }
+        match self.names[id as usize].replace(name.clone()) {
+            Some(EdgeName::Number(old)) if name != EdgeName::Number(old) => {
+                self.numbers.remove(&old);
+            }
+            Some(EdgeName::Text(old)) if name != EdgeName::Text(old.clone()) => {
+                self.texts.remove(&old);
+            }
+            _ => (),
+        }
+        previous
+    }
+
+    pub fn get(&self, name: &str) -> Option<u32> {
+        match EdgeName::parse_number(name) {
+            Some(number) => self.numbers.get(&number),
+            None => self.texts.get(name),
+        }
+        .copied()
+    }
+
+    pub fn get_name(&self, name: &EdgeName) -> Option<u32> {
+        self.id(name).copied()
+    }
+
+    /// Edge named `number`, without formatting it as a string first.
+    pub fn get_number(&self, number: i64) -> Option<u32> {
+        self.numbers.get(&number).copied()
+    }
+
+    /// External name of the edge `id`.
+    pub fn name(&self, id: u32) -> Option<&EdgeName> {
+        self.names.get(id as usize).and_then(Option::as_ref)
+    }
+
+    pub fn len(&self) -> usize {
+        self.numbers.len() + self.texts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn id(&self, name: &EdgeName) -> Option<&u32> {
+        match name {
+            EdgeName::Number(number) => self.numbers.get(number),
+            EdgeName::Text(text) => self.texts.get(text),
+        }
+    }
+
+    /// All names with their edge, ordered by edge id.
+    pub fn iter(&self) -> impl Iterator<Item = (&EdgeName, u32)> {
+        self.names
+            .iter()
+            .enumerate()
+            .filter_map(|(id, name)| name.as_ref().map(|name| (name, id as u32)))
+    }
+}
+
+impl Index<&str> for EdgeLookup {
+    type Output = u32;
+
+    fn index(&self, name: &str) -> &u32 {
+        let id = match EdgeName::parse_number(name) {
+            Some(number) => self.numbers.get(&number),
+            None => self.texts.get(name),
+        };
+        id.unwrap_or_else(|| panic!("unknown edge {}", name))
+    }
+}
+
+impl Index<&EdgeName> for EdgeLookup {
+    type Output = u32;
+
+    fn index(&self, name: &EdgeName) -> &u32 {
+        self.id(name)
+            .unwrap_or_else(|| panic!("unknown edge {}", name))
+    }
+}
+
+impl<N: Into<EdgeName>> FromIterator<(N, u32)> for EdgeLookup {
+    fn from_iter<I: IntoIterator<Item = (N, u32)>>(iter: I) -> EdgeLookup {
+        let mut edge_lookup = EdgeLookup::new();
+        for (name, id) in iter {
+            edge_lookup.insert(name, id);
+        }
+        edge_lookup
     }
 }
 
@@ -71,175 +277,429 @@ pub struct GraphData {
     pub keys: KeyMap,
 }
 
-pub fn read_graphml<P: AsRef<Path>>(file_path: P) -> Result<GraphData, Box<dyn Error>> {
-    let mut contents = String::new();
+impl GraphData {
+    /// Names of the edge metrics, ordered by their index in the cost vectors.
+    pub fn metric_names(&self) -> Vec<String> {
+        let mut names = vec![String::new(); self.graph.dim()];
+        for key in self.keys.values() {
+            if let AttributeType::Double(index) = key.attribute_type {
+                names[index] = key.name.clone();
+            }
+        }
+        names
+    }
+}
 
-    let file = std::fs::File::open(file_path)?;
-    let mut file = std::io::BufReader::new(file);
+pub fn read_graphml<P: AsRef<Path>>(file_path: P) -> Result<GraphData, Box<dyn Error>> {
+    read_graphml_file(file_path, true)
+}
 
-    file.read_to_string(&mut contents)?;
-    let doc = Document::parse(&contents)?;
+/// Like `read_graphml`, but edges with unknown nodes or missing metrics are
+/// kept (see `Graph::new_unchecked`), so `Graph::validate` can report them.
+pub fn read_graphml_unchecked<P: AsRef<Path>>(file_path: P) -> Result<GraphData, Box<dyn Error>> {
+    read_graphml_file(file_path, false)
+}
 
-    let mut metric_count = 0;
-    let keys: KeyMap = doc
-        .root()
-        .descendants()
-        .filter(|n| n.has_tag_name("key"))
-        .map(|d| {
-            let obj_type = d
-                .attribute("for")
-                .expect("No 'for' attribute on key element");
-            let name = d
-                .attribute("attr.name")
-                .expect("No 'attr.name' attribute on key element");
-            let attr_type = d
-                .attribute("attr.type")
-                .expect("No 'attr.type' attribute on key element");
-            let id = d.attribute("id").expect("No 'id' attribute on key element");
-            let attr = GraphmlAttribute::new(obj_type, name, attr_type, metric_count);
-            if let AttributeType::Double(_) = attr.attribute_type {
-                metric_count += 1;
-            }
-            (id.to_owned(), attr)
-        })
-        .collect();
-    if metric_count != EDGE_COST_DIMENSION {
-        panic!(
-            "Found {} metrics. Code was compiled for {}",
-            metric_count, EDGE_COST_DIMENSION
-        );
+fn read_graphml_file<P: AsRef<Path>>(
+    file_path: P,
+    check_edges: bool,
+) -> Result<GraphData, Box<dyn Error>> {
+    if cache::is_graph_cache(&file_path)? {
+        return cache::read_graph_cache(file_path);
     }
 
-    let nodes: Vec<Node> = doc
-        .root()
-        .descendants()
-        .filter(|n| n.has_tag_name("node"))
-        .map(|n| {
-            let mut id = 0;
-            let mut ch_level = 0;
-            for d in n.descendants().filter(|n| n.has_tag_name("data")) {
-                let key = d
-                    .attribute("key")
-                    .expect("data element has no key attribute.");
-                let text = d.text().expect("data element has no text.");
-
-                let attribute = &keys[key];
-
-                match attribute.name.as_str() {
-                    "level" => ch_level = text.parse().expect("Could not parse ch level"),
-                    "id" => id = parse_node_id(text),
+    let file = std::fs::File::open(file_path)?;
+    let mut parser = EventReader::new(BufReader::new(file));
+
+    let mut keys = KeyMap::new();
+    let mut metric_count = 0;
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    let mut edge_lookup = EdgeLookup::new();
+    // Replaced edges are given by name and might only be defined further
+    // down in the file, so they get resolved after reading all edges
+    let mut replaced_edges = Vec::new();
+
+    let mut element = None;
+    let mut data_key = None;
+    let mut text = String::new();
+
+    loop {
+        let event = parser.next();
+        let line = parser.position().row + 1;
+        let error = |reason: String| Box::new(MyError::InvalidGraphml { line, reason });
+
+        match event.map_err(|e| error(e.msg().to_owned()))? {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                let attribute = |attr: &str| {
+                    attributes
+                        .iter()
+                        .find(|a| a.name.local_name == attr)
+                        .map(|a| a.value.as_str())
+                        .ok_or_else(|| {
+                            error(format!(
+                                "{} element has no '{}' attribute",
+                                name.local_name, attr
+                            ))
+                        })
+                };
+                match name.local_name.as_str() {
+                    "key" => {
+                        let attr = GraphmlAttribute::new(
+                            attribute("for")?,
+                            attribute("attr.name")?,
+                            attribute("attr.type")?,
+                            metric_count,
+                        )
+                        .map_err(|e| error(e.to_string()))?;
+                        if let AttributeType::Double(_) = attr.attribute_type {
+                            metric_count += 1;
+                        }
+                        keys.insert(attribute("id")?.to_owned(), attr);
+                    }
+                    "node" => {
+                        element = Some(Element::Node {
+                            node: Node::new(0, 0),
+                            lat: None,
+                            lon: None,
+                        })
+                    }
+                    "edge" => {
+                        let source_text = attribute("source")?;
+                        let target_text = attribute("target")?;
+                        element = Some(Element::Edge(PartialEdge {
+                            source: parse_node_id(source_text)
+                                .ok_or_else(|| error(format!("invalid node id {}", source_text)))?,
+                            target: parse_node_id(target_text)
+                                .ok_or_else(|| error(format!("invalid node id {}", target_text)))?,
+                            costs: vec![0.0; metric_count],
+                            name: None,
+                            edge_a: None,
+                            edge_b: None,
+                        }))
+                    }
+                    "data" => {
+                        data_key = Some(attribute("key")?.to_owned());
+                        text.clear();
+                    }
                     _ => (),
                 }
             }
-            Node::new(id, ch_level)
-        })
-        .collect();
-
-    println!("parsed {} nodes", nodes.len());
-
-    let edge_lookup: EdgeLookup = doc
-        .root()
-        .descendants()
-        .filter(|n| n.has_tag_name("edge"))
-        .enumerate()
-        .map(|(id, edge)| {
-            for d in edge.descendants().filter(|d| d.has_tag_name("data")) {
-                let key = d.attribute("key").unwrap();
-                if "name" == keys[key].name {
-                    return (d.text().unwrap().to_owned(), id as u32);
+            XmlEvent::Characters(chars) if data_key.is_some() => text.push_str(&chars),
+            XmlEvent::EndElement { name } => match name.local_name.as_str() {
+                "data" => {
+                    let key = data_key.take().unwrap_or_default();
+                    let attr = keys
+                        .get(&key)
+                        .ok_or_else(|| error(format!("unknown key {}", key)))?;
+                    if text.is_empty() {
+                        continue;
+                    }
+                    match &mut element {
+                        Some(Element::Node { node, lat, lon }) => match attr.name.as_str() {
+                            "level" => {
+                                node.ch_level = text.parse().map_err(|_| {
+                                    error(format!("could not parse ch level {}", text))
+                                })?
+                            }
+                            "id" => {
+                                node.id = parse_node_id(&text)
+                                    .ok_or_else(|| error(format!("invalid node id {}", text)))?
+                            }
+                            "lat" | "lon" => {
+                                let value = text.parse().map_err(|_| {
+                                    error(format!("could not parse coordinate {}", text))
+                                })?;
+                                if attr.name == "lat" {
+                                    *lat = Some(value);
+                                } else {
+                                    *lon = Some(value);
+                                }
+                            }
+                            _ => (),
+                        },
+                        Some(Element::Edge(edge)) => {
+                            match attr.name.as_str() {
+                                "name" if edge.name.is_none() => edge.name = Some(text.clone()),
+                                "edgeA" => edge.edge_a = Some(text.clone()),
+                                "edgeB" => edge.edge_b = Some(text.clone()),
+                                _ => (),
+                            }
+                            if let AttributeType::Double(idx) = attr.attribute_type {
+                                let cost = edge.costs.get_mut(idx).ok_or_else(|| {
+                                    error(format!("metric {} declared after edges", attr.name))
+                                })?;
+                                *cost = text.parse().map_err(|_| {
+                                    error(format!("could not parse text {} of {}", text, attr.name))
+                                })?;
+                            }
+                        }
+                        None => (),
+                    }
                 }
-            }
-            panic!("could not find name for edge")
-        })
-        .collect();
+                "node" => {
+                    if let Some(Element::Node { mut node, lat, lon }) = element.take() {
+                        if let (Some(lat), Some(lon)) = (lat, lon) {
+                            node.coordinates = Some(Coordinates::new(lat, lon));
+                        }
+                        nodes.push(node);
+                    }
+                }
+                "edge" => {
+                    if let Some(Element::Edge(edge)) = element.take() {
+                        let id = edges.len() as u32;
+                        let name = edge
+                            .name
+                            .ok_or_else(|| error("could not find name for edge".to_owned()))?;
+                        edge_lookup.insert(name, id);
+                        replaced_edges.push((edge.edge_a, edge.edge_b, line));
+                        edges.push(Edge::new(id, edge.source, edge.target, edge.costs, None));
+                    }
+                }
+                _ => (),
+            },
+            XmlEvent::EndDocument => break,
+            _ => (),
+        }
+    }
 
+    println!("parsed {} nodes", nodes.len());
     println!("lookup table size: {}", edge_lookup.len());
 
-    let edges: Vec<Edge> = doc
-        .root()
-        .descendants()
-        .filter(|n| n.has_tag_name("edge"))
-        .enumerate()
-        .map(|(id, e)| parse_edge_from_xml(id as u32, &e, &keys, &edge_lookup))
-        .collect();
+    let edge_lines: Vec<_> = replaced_edges.iter().map(|&(_, _, line)| line).collect();
+    for (edge, (edge_a, edge_b, line)) in edges.iter_mut().zip(replaced_edges) {
+        let lookup = |name: Option<String>| match name {
+            Some(name) if name != "-1" => {
+                edge_lookup
+                    .get(&name)
+                    .map(Some)
+                    .ok_or_else(|| MyError::InvalidGraphml {
+                        line,
+                        reason: format!("unknown replaced edge {}", name),
+                    })
+            }
+            _ => Ok(None),
+        };
+        if let (Some(edge_a), Some(edge_b)) = (lookup(edge_a)?, lookup(edge_b)?) {
+            if edge_a == edge.id || edge_b == edge.id {
+                return Err(Box::new(MyError::InvalidGraphml {
+                    line,
+                    reason: "shortcut replaces itself".to_owned(),
+                }));
+            }
+            edge.replaced_edges = Some((edge_a, edge_b));
+        }
+    }
 
     println!("parsed {} edges", edges.len());
 
+    if check_edges {
+        let node_ids: HashSet<_> = nodes.iter().map(|n| n.id).collect();
+        for (edge, &line) in edges.iter().zip(&edge_lines) {
+            let error = |reason: String| Box::new(MyError::InvalidGraphml { line, reason });
+            for &node in &[edge.source_id, edge.target_id] {
+                if !node_ids.contains(&node) {
+                    return Err(error(format!("unknown node {}", node)));
+                }
+            }
+            if edge.edge_costs.len() != metric_count {
+                return Err(error(format!(
+                    "edge has {} metrics instead of {}",
+                    edge.edge_costs.len(),
+                    metric_count
+                )));
+            }
+        }
+        let report = check_replaced_edges(&edges);
+        if let Some(&edge) = report.cyclic_shortcuts.first() {
+            return Err(Box::new(MyError::InvalidGraphml {
+                line: edge_lines[edge as usize],
+                reason: format!("shortcut {} (transitively) replaces itself", edge),
+            }));
+        }
+    }
+
     Ok(GraphData {
-        graph: Graph::new(nodes, edges),
+        graph: Graph::new_unchecked(nodes, edges),
         edge_lookup,
         keys,
     })
 }
 
-fn parse_edge_from_xml<'a, 'input>(
-    id: u32,
-    node: &roxmltree::Node<'a, 'input>,
-    keys: &KeyMap,
-    edge_lookup: &EdgeLookup,
-) -> Edge {
-    let mut costs = [0.0; super::EDGE_COST_DIMENSION];
-
-    let source_text = node
-        .attribute("source")
-        .expect("edge element has no source attribute.");
-    let target_text = node
-        .attribute("target")
-        .expect("edge element has no target attribute.");
-
-    let source = parse_node_id(source_text);
-    let target = parse_node_id(target_text);
-
-    let mut edge_a: i64 = -1;
-    let mut edge_b: i64 = -1;
-
-    for d in node.descendants().filter(|n| n.has_tag_name("data")) {
-        let attr = &keys[d
-            .attribute("key")
-            .expect("data element has no key attribute")];
-
-        let text = if let Some(t) = d.text() {
-            t
-        } else {
-            continue;
-        };
+enum Element {
+    Node {
+        node: Node,
+        lat: Option<f64>,
+        lon: Option<f64>,
+    },
+    Edge(PartialEdge),
+}
 
-        match attr.name.as_str() {
-            "edgeA" => {
-                edge_a = if text != "-1" {
-                    edge_lookup[text].try_into().unwrap()
-                } else {
-                    -1
-                }
-            }
-            "edgeB" => {
-                edge_b = if text != "-1" {
-                    edge_lookup[text].try_into().unwrap()
-                } else {
-                    -1
+/// Edge as read so far, with replaced edges still given by name.
+struct PartialEdge {
+    source: u32,
+    target: u32,
+    costs: Costs,
+    name: Option<String>,
+    edge_a: Option<String>,
+    edge_b: Option<String>,
+}
+
+fn parse_node_id(node_id: &str) -> Option<u32> {
+    let tail: String = node_id.chars().skip(1).collect();
+
+    tail.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::parse_minimal_graph_file;
+
+    #[test]
+    fn graphml_matches_minimal_graph() {
+        let graphml = read_graphml("./src/test_graphs/testGraph.graphml").unwrap();
+        let minimal = parse_minimal_graph_file("./src/test_graphs/minimalTestGraph").unwrap();
+
+        assert_eq!(graphml.graph.dim(), minimal.graph.dim());
+        assert_eq!(graphml.edge_lookup.len(), minimal.edge_lookup.len());
+        for (name, id) in graphml.edge_lookup.iter() {
+            let e = &graphml.graph.edges[id];
+            let other = &minimal.graph.edges[minimal.edge_lookup[name]];
+            assert_eq!(e.edge_costs, other.edge_costs);
+            match (e.replaced_edges, other.replaced_edges) {
+                (Some((a, b)), Some((other_a, other_b))) => {
+                    assert_eq!(
+                        graphml.graph.edges[a].edge_costs,
+                        minimal.graph.edges[other_a].edge_costs
+                    );
+                    assert_eq!(
+                        graphml.graph.edges[b].edge_costs,
+                        minimal.graph.edges[other_b].edge_costs
+                    );
                 }
+                (a, b) => assert_eq!(a.is_some(), b.is_some()),
             }
-            _ => (),
         }
+    }
 
-        if let AttributeType::Double(idx) = attr.attribute_type {
-            costs[idx] = text
-                .parse()
-                .unwrap_or_else(|t| panic!("could not parse text {} of {}", t, attr.name));
-        }
+    #[test]
+    fn errors_report_line() {
+        let path = std::env::temp_dir().join("ppts_broken.graphml");
+        std::fs::write(
+            &path,
+            "<graphml>\n<key id=\"d0\" for=\"edge\" attr.name=\"name\" attr.type=\"string\"/>\n\
+             <graph>\n<edge source=\"n0\">\n</edge>\n</graph>\n</graphml>\n",
+        )
+        .unwrap();
+
+        let error = read_graphml(&path).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Invalid GraphML in line 4: edge element has no 'target' attribute"
+        );
+        std::fs::remove_file(path).unwrap();
     }
 
-    let skips = if edge_a >= 0 && edge_b >= 0 {
-        Some((edge_a as u32, edge_b as u32))
-    } else {
-        None
-    };
+    #[test]
+    fn unknown_nodes_and_missing_metrics_are_errors() {
+        let path = std::env::temp_dir().join("ppts_inconsistent.graphml");
+        let graphml = |edges: &str| {
+            format!(
+                "<graphml>\n\
+                 <key id=\"d0\" for=\"node\" attr.name=\"id\" attr.type=\"long\"/>\n\
+                 <key id=\"e0\" for=\"edge\" attr.name=\"name\" attr.type=\"string\"/>\n\
+                 <key id=\"m0\" for=\"edge\" attr.name=\"a\" attr.type=\"double\"/>\n\
+                 <graph>\n\
+                 <node id=\"n0\"><data key=\"d0\">n0</data></node>\n\
+                 <node id=\"n1\"><data key=\"d0\">n1</data></node>\n\
+                 {}</graph>\n</graphml>\n",
+                edges
+            )
+        };
 
-    Edge::new(id, source, target, costs, skips)
-}
+        std::fs::write(
+            &path,
+            graphml("<edge source=\"n0\" target=\"n5\"><data key=\"e0\">7</data></edge>\n"),
+        )
+        .unwrap();
+        let error = read_graphml(&path).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Invalid GraphML in line 8: unknown node 5"
+        );
+        let graph = read_graphml_unchecked(&path).unwrap().graph;
+        assert_eq!(graph.validate().dangling_edges, vec![0]);
+
+        std::fs::write(
+            &path,
+            graphml(
+                "<edge source=\"n0\" target=\"n1\"><data key=\"e0\">7</data></edge>\n\
+                 <key id=\"m1\" for=\"edge\" attr.name=\"b\" attr.type=\"double\"/>\n\
+                 <edge source=\"n1\" target=\"n0\"><data key=\"e0\">8</data></edge>\n",
+            ),
+        )
+        .unwrap();
+        let error = read_graphml(&path).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Invalid GraphML in line 8: edge has 1 metrics instead of 2"
+        );
 
-fn parse_node_id(node_id: &str) -> u32 {
-    let tail: String = node_id.chars().skip(1).collect();
+        let shortcut = |name: u32, source: u32, target: u32, replaced: u32| {
+            format!(
+                "<edge source=\"n{}\" target=\"n{}\"><data key=\"e0\">{}</data>\
+                 <data key=\"e1\">{}</data><data key=\"e2\">{}</data></edge>\n",
+                source, target, name, replaced, replaced
+            )
+        };
+        let keys = "<key id=\"e1\" for=\"edge\" attr.name=\"edgeA\" attr.type=\"string\"/>\n\
+                    <key id=\"e2\" for=\"edge\" attr.name=\"edgeB\" attr.type=\"string\"/>\n";
+        std::fs::write(&path, graphml(&format!("{}{}", keys, shortcut(7, 0, 1, 7)))).unwrap();
+        let error = read_graphml(&path).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Invalid GraphML in line 10: shortcut replaces itself"
+        );
+
+        let edges = format!("{}{}{}", keys, shortcut(7, 0, 1, 8), shortcut(8, 1, 0, 7));
+        std::fs::write(&path, graphml(&edges)).unwrap();
+        let error = read_graphml(&path).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Invalid GraphML in line 11: shortcut 1 (transitively) replaces itself"
+        );
+        let graph = read_graphml_unchecked(&path).unwrap().graph;
+        assert_eq!(graph.validate().cyclic_shortcuts, vec![1]);
 
-    tail.parse().expect("could not parse node id")
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn edge_lookup_is_bidirectional() {
+        let mut edge_lookup: EdgeLookup =
+            vec![("17", 0), ("007", 1), ("-3", 2)].into_iter().collect();
+
+        assert_eq!(edge_lookup.get_number(17), Some(0));
+        assert_eq!(edge_lookup.get("17"), Some(0));
+        assert_eq!(edge_lookup.get_number(7), None, "007 is not a plain number");
+        assert_eq!(edge_lookup["007"], 1);
+        assert_eq!(edge_lookup.name(2), Some(&EdgeName::Number(-3)));
+
+        assert_eq!(edge_lookup.insert("x", 0), None);
+        assert_eq!(edge_lookup.get("17"), None);
+        assert_eq!(edge_lookup.insert(-3, 3), Some(2));
+        assert_eq!(edge_lookup.name(2), None);
+        assert_eq!(edge_lookup.len(), 3);
+        let names: Vec<_> = edge_lookup
+            .iter()
+            .map(|(name, id)| (name.to_string(), id))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("x".into(), 0), ("007".into(), 1), ("-3".into(), 3)]
+        );
+    }
 }
@@ -1,5 +1,3 @@
-use crate::EDGE_COST_DIMENSION;
-
 use rand::{
     distributions::{Distribution, Uniform},
     prelude::{SliceRandom, ThreadRng},
@@ -7,25 +5,29 @@ use rand::{
 use serde::{Deserialize, Serialize};
 use std::ops::{Deref, DerefMut, Index, IndexMut, Range, RangeInclusive};
 
-pub type Preference = [f64; EDGE_COST_DIMENSION];
-pub type Costs = [f64; EDGE_COST_DIMENSION];
+pub type Preference = Vec<f64>;
+pub type Costs = Vec<f64>;
+
+/// Tolerance for comparing costs that were summed up in different orders
+pub const ACCURACY: f64 = 0.000001;
 
-pub const EQUAL_WEIGHTS: Preference = [1.0 / EDGE_COST_DIMENSION as f64; EDGE_COST_DIMENSION];
+/// Preference that weights each of the `dim` metrics equally.
+pub fn equal_weights(dim: usize) -> Preference {
+    vec![1.0 / dim as f64; dim]
+}
 
-pub fn costs_by_alpha(costs: &Costs, alpha: &Preference) -> f64 {
+pub fn costs_by_alpha(costs: &[f64], alpha: &[f64]) -> f64 {
     costs
         .iter()
         .zip(alpha.iter())
         .fold(0.0, |acc, (cost, factor)| acc + cost * factor)
 }
 
-pub fn add_edge_costs(a: &Costs, b: &Costs) -> Costs {
-    let mut result = [0.0; EDGE_COST_DIMENSION];
+pub fn add_edge_costs(a: &[f64], b: &[f64]) -> Costs {
     a.iter()
         .zip(b.iter())
-        .enumerate()
-        .for_each(|(index, (first, second))| result[index] = first + second);
-    result
+        .map(|(first, second)| first + second)
+        .collect()
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -110,8 +112,8 @@ impl<T> DerefMut for MyVec<T> {
     }
 }
 
-pub fn randomized_preference(rng: &mut ThreadRng) -> Preference {
-    let mut result = [0.0; EDGE_COST_DIMENSION];
+pub fn randomized_preference(rng: &mut ThreadRng, dim: usize) -> Preference {
+    let mut result = vec![0.0; dim];
     let (last, elements) = result.split_last_mut().unwrap();
     let mut rest = 1.0;
     for r in elements.iter_mut() {
@@ -126,6 +128,20 @@ pub fn randomized_preference(rng: &mut ThreadRng) -> Preference {
     result
 }
 
+/// Great-circle distance in meters between two coordinates.
+pub fn haversine(from_lat: f64, from_lng: f64, to_lat: f64, to_lng: f64) -> f64 {
+    const EARTH_RADIUS: f64 = 6_371_007.2; // in m
+    let theta1 = from_lat.to_radians();
+    let theta2 = to_lat.to_radians();
+    let delta_theta = (to_lat - from_lat).to_radians();
+    let delta_lambda = (to_lng - from_lng).to_radians();
+    let a = (delta_theta / 2.0).sin().powi(2)
+        + theta1.cos() * theta2.cos() * (delta_lambda / 2.0).sin().powi(2);
+    let c = 2.0 * a.sqrt().asin();
+
+    EARTH_RADIUS * c
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,6 +151,6 @@ mod tests {
         let a = [1.5, 2.0, 0.7, 1.3];
         let b = [1.3, 0.1, 0.3, 0.3];
         let result = add_edge_costs(&a, &b);
-        assert_eq!([2.8, 2.1, 1.0, 1.6], result);
+        assert_eq!(vec![2.8, 2.1, 1.0, 1.6], result);
     }
 }
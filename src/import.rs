@@ -0,0 +1,334 @@
+//! Readers for graphs from other tools: DIMACS shortest path challenge
+//! files and CSV edge lists.
+//!
+//! Imported graphs are uncontracted (all `ch_level`s 0), which `Dijkstra`
+//! handles as a plain bidirectional search. Edges are named by their id
+//! unless a name column is given.
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::graph::{Coordinates, Edge, Graph, Node};
+use crate::graphml::{EdgeLookup, EdgeName, GraphData, GraphmlAttribute};
+use crate::{MyError, MyResult};
+
+/// Columns of a CSV edge list to build a graph from.
+pub struct CsvColumns {
+    pub source: String,
+    pub target: String,
+    /// Column with the edge names used in trajectories
+    pub name: Option<String>,
+    /// Cost columns with the metric name to use for each
+    pub metrics: Vec<(String, String)>,
+    pub delimiter: u8,
+}
+
+impl CsvColumns {
+    /// Uses the `metrics` columns under their own names.
+    pub fn new(source: &str, target: &str, metrics: &[&str]) -> CsvColumns {
+        CsvColumns {
+            source: source.to_owned(),
+            target: target.to_owned(),
+            name: None,
+            metrics: metrics
+                .iter()
+                .map(|m| (m.to_string(), m.to_string()))
+                .collect(),
+            delimiter: b',',
+        }
+    }
+}
+
+/// Reads a graph from DIMACS `.gr` files, one per metric (e.g. the distance
+/// and travel time graphs of the 9th DIMACS challenge). All files must list
+/// the same arcs in the same order. Metrics are named after the files.
+pub fn read_dimacs<P: AsRef<Path>>(files: &[P]) -> MyResult<GraphData> {
+    let mut node_count = 0;
+    let mut arcs: Vec<(u32, u32)> = Vec::new();
+    let mut costs: Vec<Vec<f64>> = Vec::new();
+    let mut metric_names = Vec::new();
+
+    for (metric, path) in files.iter().enumerate() {
+        let file_name = path.as_ref().display().to_string();
+        let error = |line: usize, reason: String| {
+            Box::new(MyError::InvalidGraphFile {
+                file: file_name.clone(),
+                line,
+                reason,
+            })
+        };
+        println!("Reading DIMACS graph {}", file_name);
+        let reader = BufReader::new(File::open(path)?);
+
+        let mut arc = 0;
+        let mut line_count = 0;
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            line_count += 1;
+            let tokens: Vec<_> = line.split_whitespace().collect();
+            match tokens.as_slice() {
+                ["p", "sp", nodes, _] => {
+                    let nodes = nodes
+                        .parse()
+                        .map_err(|_| error(i + 1, format!("invalid node count {}", nodes)))?;
+                    if metric > 0 && nodes != node_count {
+                        return Err(error(i + 1, "node count differs from first file".into()));
+                    }
+                    node_count = nodes;
+                }
+                ["a", source, target, cost] => {
+                    let node = |token: &str| match token.parse::<u32>() {
+                        Ok(id) if id >= 1 && id <= node_count => Ok(id - 1),
+                        _ => Err(error(i + 1, format!("invalid node id {}", token))),
+                    };
+                    let ends = (node(source)?, node(target)?);
+                    let cost = cost
+                        .parse()
+                        .map_err(|_| error(i + 1, format!("invalid arc cost {}", cost)))?;
+                    if metric == 0 {
+                        arcs.push(ends);
+                        costs.push(vec![cost]);
+                    } else if arcs.get(arc) == Some(&ends) {
+                        costs[arc].push(cost);
+                    } else {
+                        return Err(error(i + 1, "arc differs from first file".into()));
+                    }
+                    arc += 1;
+                }
+                [] | ["c", ..] => (),
+                _ => return Err(error(i + 1, format!("unexpected line '{}'", line))),
+            }
+        }
+        if arc != arcs.len() {
+            return Err(error(
+                line_count,
+                "number of arcs differs from first file".into(),
+            ));
+        }
+
+        let stem = path.as_ref().file_stem().unwrap_or_default();
+        metric_names.push(stem.to_string_lossy().into_owned());
+    }
+
+    let edges = arcs
+        .into_iter()
+        .zip(costs)
+        .map(|((source, target), costs)| (source, target, costs, None));
+    Ok(build_graph_data(node_count as usize, edges, metric_names))
+}
+
+/// Sets node coordinates from a DIMACS `.co` file, which gives longitude
+/// and latitude in millionths of degrees.
+pub fn read_dimacs_coordinates<P: AsRef<Path>>(
+    graph_data: &mut GraphData,
+    path: P,
+) -> MyResult<()> {
+    let file_name = path.as_ref().display().to_string();
+    let reader = BufReader::new(File::open(path)?);
+    let nodes = &mut graph_data.graph.nodes;
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let tokens: Vec<_> = line.split_whitespace().collect();
+        if let ["v", id, lon, lat] = tokens.as_slice() {
+            let parsed = (id.parse::<u32>(), lon.parse::<f64>(), lat.parse::<f64>());
+            match parsed {
+                (Ok(id), Ok(lon), Ok(lat)) if id >= 1 && id as usize <= nodes.len() => {
+                    nodes[id - 1].coordinates =
+                        Some(Coordinates::new(lat / 1_000_000.0, lon / 1_000_000.0));
+                }
+                _ => {
+                    return Err(Box::new(MyError::InvalidGraphFile {
+                        file: file_name,
+                        line: i + 1,
+                        reason: format!("invalid coordinate line '{}'", line),
+                    }))
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads a graph from a CSV edge list with a header row. Nodes are
+/// numbered in order of their first appearance.
+pub fn read_csv_edges<P: AsRef<Path>>(path: P, columns: &CsvColumns) -> MyResult<GraphData> {
+    let file_name = path.as_ref().display().to_string();
+    println!("Reading CSV edge list {}", file_name);
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(columns.delimiter)
+        .from_path(&path)?;
+
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| {
+        headers.iter().position(|h| h == name).ok_or_else(|| {
+            Box::new(MyError::InvalidGraphFile {
+                file: file_name.clone(),
+                line: 1,
+                reason: format!("missing column {}", name),
+            })
+        })
+    };
+    let source_column = column(&columns.source)?;
+    let target_column = column(&columns.target)?;
+    let name_column = columns.name.as_deref().map(column).transpose()?;
+    let metric_columns = columns
+        .metrics
+        .iter()
+        .map(|(c, _)| column(c))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut node_ids: HashMap<String, u32> = HashMap::new();
+    let mut edges = Vec::new();
+    for (i, record) in reader.records().enumerate() {
+        let record = record?;
+        let line = record.position().map_or(i + 2, |p| p.line() as usize);
+        let field = |column: usize| {
+            record.get(column).ok_or_else(|| {
+                Box::new(MyError::InvalidGraphFile {
+                    file: file_name.clone(),
+                    line,
+                    reason: format!("missing field {}", headers.get(column).unwrap_or("")),
+                })
+            })
+        };
+
+        let mut node = |id: &str| {
+            let next = node_ids.len() as u32;
+            *node_ids.entry(id.to_owned()).or_insert(next)
+        };
+        let source = node(field(source_column)?);
+        let target = node(field(target_column)?);
+        let costs = metric_columns
+            .iter()
+            .map(|&c| {
+                let value = field(c)?;
+                value.trim().parse().map_err(|_| {
+                    Box::new(MyError::InvalidGraphFile {
+                        file: file_name.clone(),
+                        line,
+                        reason: format!("could not parse cost {}", value),
+                    })
+                })
+            })
+            .collect::<Result<Vec<f64>, _>>()?;
+        let name = name_column.map(field).transpose()?.map(str::to_owned);
+        edges.push((source, target, costs, name));
+    }
+
+    let metric_names = columns.metrics.iter().map(|(_, n)| n.clone()).collect();
+    Ok(build_graph_data(node_ids.len(), edges, metric_names))
+}
+
+fn build_graph_data(
+    node_count: usize,
+    edges: impl IntoIterator<Item = (u32, u32, Vec<f64>, Option<String>)>,
+    metric_names: Vec<String>,
+) -> GraphData {
+    let nodes = (0..node_count as u32).map(|id| Node::new(id, 0)).collect();
+
+    let mut edge_lookup = EdgeLookup::new();
+    let edges = edges
+        .into_iter()
+        .enumerate()
+        .map(|(id, (source, target, costs, name))| {
+            let id = id as u32;
+            let name = name.map_or_else(|| EdgeName::Number(i64::from(id)), EdgeName::from);
+            edge_lookup.insert(name, id);
+            Edge::new(id, source, target, costs, None)
+        })
+        .collect();
+
+    let keys = metric_names
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.to_string(), GraphmlAttribute::metric(n, i)))
+        .collect();
+
+    GraphData {
+        graph: Graph::new(nodes, edges),
+        edge_lookup,
+        keys,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::dijkstra::Dijkstra;
+
+    #[test]
+    fn dimacs_metrics_are_combined() {
+        let dir = std::env::temp_dir();
+        let distance = dir.join("ppts_import_distance.gr");
+        let time = dir.join("ppts_import_time.gr");
+        let coordinates = dir.join("ppts_import.co");
+        std::fs::write(
+            &distance,
+            "c distances\np sp 3 3\na 1 2 10\na 2 3 10\na 1 3 25\n",
+        )
+        .unwrap();
+        std::fs::write(&time, "p sp 3 3\na 1 2 5\na 2 3 5\na 1 3 4\n").unwrap();
+        std::fs::write(
+            &coordinates,
+            "p aux sp co 3\nv 1 9100000 48700000\nv 2 9200000 48800000\nv 3 9300000 48900000\n",
+        )
+        .unwrap();
+
+        let mut graph_data = read_dimacs(&[&distance, &time]).unwrap();
+        read_dimacs_coordinates(&mut graph_data, &coordinates).unwrap();
+        assert_eq!(
+            graph_data.metric_names(),
+            vec!["ppts_import_distance", "ppts_import_time"]
+        );
+        let graph = &graph_data.graph;
+        assert_eq!(graph.edges[2_u32].edge_costs, vec![25.0, 4.0]);
+        assert_eq!(
+            graph.nodes[1_u32].coordinates,
+            Some(Coordinates::new(48.8, 9.2))
+        );
+
+        let mut dijkstra = Dijkstra::new(graph);
+        assert_eq!(dijkstra.run(0, 2, &[1.0, 0.0]).unwrap().total_cost, 20.0);
+        assert_eq!(dijkstra.run(0, 2, &[0.0, 1.0]).unwrap().total_cost, 4.0);
+
+        std::fs::write(&time, "p sp 3 3\na 1 2 5\na 3 2 5\na 1 3 4\n").unwrap();
+        assert!(read_dimacs(&[&distance, &time]).is_err());
+
+        for file in &[distance, time, coordinates] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn csv_columns_are_mapped() {
+        let path = std::env::temp_dir().join("ppts_import.csv");
+        std::fs::write(
+            &path,
+            "id,from,to,length,comment,time\n\
+             17,a,b,1.5,x,3\n\
+             18,b,c,2,\"y, z\",4\n\
+             19,c,a,0.5,,1\n",
+        )
+        .unwrap();
+
+        let mut columns = CsvColumns::new("from", "to", &["time"]);
+        columns.name = Some("id".to_owned());
+        columns
+            .metrics
+            .push(("length".to_owned(), "distance".to_owned()));
+        let graph_data = read_csv_edges(&path, &columns).unwrap();
+
+        assert_eq!(graph_data.metric_names(), vec!["time", "distance"]);
+        assert_eq!(graph_data.graph.nodes.len(), 3);
+        let edge = &graph_data.graph.edges[graph_data.edge_lookup["18"]];
+        assert_eq!(edge.edge_costs, vec![4.0, 2.0]);
+
+        std::fs::write(&path, "from,to,time\na,b,fast\n").unwrap();
+        let error = read_csv_edges(&path, &columns).err().unwrap();
+        assert!(error.to_string().contains("missing column id"));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,21 +1,51 @@
 use std::fmt::{Display, Formatter};
 
+pub mod cache;
+pub mod costs;
+pub mod export;
 pub mod geojson;
 pub mod graph;
 pub mod graphml;
 pub mod helpers;
+pub mod import;
 pub mod lp;
+pub mod osm;
 pub mod statistics;
 pub mod trajectories;
 
-pub const EDGE_COST_DIMENSION: usize = 4;
-
 pub type MyResult<T> = Result<T, Box<dyn std::error::Error>>;
 
 #[derive(Debug)]
 pub enum MyError {
     InvalidTrajectories,
     WrongArgumentNumber,
+    AlreadyContracted,
+    InvalidGraphCache(&'static str),
+    InvalidGraphml {
+        line: u64,
+        reason: String,
+    },
+    InvalidCostFile(String),
+    InvalidGraphFile {
+        file: String,
+        line: usize,
+        reason: String,
+    },
+    UnknownGraphObject(String),
+    UnknownAttributeType(String),
+    InvalidTrajectory {
+        trip_id: String,
+        reason: String,
+    },
+    InvalidGraph,
+    InvalidProfile(String),
+    NotCustomizable,
+    DifferingMetricCounts,
+    MissingCosts {
+        metrics: String,
+        edges: usize,
+    },
+    OpenSubgraph,
 }
 
 impl Display for MyError {
@@ -23,6 +53,41 @@ impl Display for MyError {
         match self {
             MyError::InvalidTrajectories => write!(f, "Invalid Trajectories"),
             MyError::WrongArgumentNumber => write!(f, "Too few arguments"),
+            MyError::AlreadyContracted => write!(f, "Graph is already contracted"),
+            MyError::InvalidGraphCache(reason) => write!(f, "Invalid graph cache: {}", reason),
+            MyError::InvalidGraphml { line, reason } => {
+                write!(f, "Invalid GraphML in line {}: {}", line, reason)
+            }
+            MyError::InvalidCostFile(file) => {
+                write!(f, "Edges in cost file {} have differing numbers of costs", file)
+            }
+            MyError::InvalidGraphFile { file, line, reason } => {
+                write!(f, "Invalid graph file {} in line {}: {}", file, line, reason)
+            }
+            MyError::UnknownGraphObject(obj_type) => {
+                write!(f, "Unknown graph object type {}", obj_type)
+            }
+            MyError::UnknownAttributeType(attr_type) => {
+                write!(f, "Unknown attribute type {}", attr_type)
+            }
+            MyError::InvalidTrajectory { trip_id, reason } => {
+                write!(f, "Invalid trajectory {}: {}", trip_id, reason)
+            }
+            MyError::InvalidGraph => write!(f, "Graph failed validation"),
+            MyError::InvalidProfile(reason) => write!(f, "Invalid travel time profile: {}", reason),
+            MyError::NotCustomizable => {
+                write!(f, "Graph was contracted with witness searches and cannot be customized")
+            }
+            MyError::DifferingMetricCounts => write!(f, "Edges have differing numbers of metrics"),
+            MyError::MissingCosts { metrics, edges } => write!(
+                f,
+                "{} edges have no costs for {} and no fill value was given",
+                edges, metrics
+            ),
+            MyError::OpenSubgraph => write!(
+                f,
+                "Shortcuts can only be kept for subgraphs that no shortest path leaves"
+            ),
         }
     }
 }
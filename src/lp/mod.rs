@@ -1,8 +1,7 @@
-use crate::graph::dijkstra::Dijkstra;
+use crate::graph::dijkstra::{many_to_many, Dijkstra};
 use crate::graph::path::Path;
 use crate::graph::Graph;
-use crate::helpers::{costs_by_alpha, Preference};
-use crate::EDGE_COST_DIMENSION;
+use crate::helpers::{add_edge_costs, costs_by_alpha, equal_weights, Preference};
 
 pub struct PreferenceEstimator<'a, 'b> {
     graph: &'a Graph,
@@ -23,12 +22,12 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
         target_idx: u32,
     ) -> MyResult<Option<Preference>> {
         self.lp.reset().expect("LP Process could not be reset");
-        let costs = path.get_subpath_costs(self.graph, source_idx, target_idx);
+        let costs = self.subpath_costs(dijkstra, path, source_idx, target_idx);
 
         let mut prev_alphas: Vec<Preference> = Vec::new();
-        let mut alpha = [1.0 / EDGE_COST_DIMENSION as f64; EDGE_COST_DIMENSION];
+        let mut alpha = equal_weights(self.graph.dim());
         let accuracy = 0.000005;
-        prev_alphas.push(alpha);
+        prev_alphas.push(alpha.clone());
         loop {
             let result = self
                 .graph
@@ -36,13 +35,13 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
                     dijkstra,
                     0,
                     &[path.nodes[source_idx], path.nodes[target_idx]],
-                    alpha,
+                    &alpha,
                 )
                 .unwrap();
-            let mut cost_dif: Costs = [0.0; EDGE_COST_DIMENSION];
+            let mut cost_dif: Costs = vec![0.0; self.graph.dim()];
             let mut total_cost_dif = 0.0;
 
-            for i in 0..EDGE_COST_DIMENSION {
+            for i in 0..self.graph.dim() {
                 let dif = result.total_dimension_costs[i] - costs[i];
                 cost_dif[i] = dif;
                 total_cost_dif += dif * alpha[i];
@@ -87,7 +86,7 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
                         return Ok(None);
                     }
                     alpha = pref;
-                    prev_alphas.push(alpha);
+                    prev_alphas.push(alpha.clone());
                 }
 
                 None => {
@@ -110,33 +109,30 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
         let accuracy = 0.0001;
 
         let mut prev_alphas: Vec<Preference> = Vec::new();
-        let mut alpha = EQUAL_WEIGHTS;
-        prev_alphas.push(alpha);
+        let mut alpha = equal_weights(self.graph.dim());
+        prev_alphas.push(alpha.clone());
         loop {
             let mut sum_dif = 0.0;
             for path in paths {
+                let costs = self.path_costs(dijkstra, path);
                 let result = self
                     .graph
                     .find_shortest_path(
                         dijkstra,
                         0,
                         &[*path.nodes.first().unwrap(), *path.nodes.last().unwrap()],
-                        alpha,
+                        &alpha,
                     )
                     .unwrap();
-                let dif = costs_by_alpha(&path.total_dimension_costs, &alpha)
+                let dif = costs_by_alpha(&costs, &alpha)
                     - costs_by_alpha(&result.total_dimension_costs, &alpha);
                 sum_dif += dif;
 
-                let mut cost_dif: Costs = [0.0; EDGE_COST_DIMENSION];
+                let mut cost_dif: Costs = vec![0.0; self.graph.dim()];
 
                 cost_dif
                     .iter_mut()
-                    .zip(
-                        path.total_dimension_costs
-                            .iter()
-                            .zip(result.total_dimension_costs.iter()),
-                    )
+                    .zip(costs.iter().zip(result.total_dimension_costs.iter()))
                     .for_each(|(c, (p, r))| *c = r - p);
 
                 self.lp.add_constraint(&cost_dif)?;
@@ -162,7 +158,7 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
                         return Ok(None);
                     }
                     alpha = pref;
-                    prev_alphas.push(alpha);
+                    prev_alphas.push(alpha.clone());
                 }
                 None => {
                     #[cfg(feature = "debug")]
@@ -189,12 +185,13 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
         let accuracy = 0.0001;
 
         let mut prev_alphas: Vec<Preference> = Vec::new();
-        let mut alpha = EQUAL_WEIGHTS;
-        prev_alphas.push(alpha);
+        let mut alpha = equal_weights(self.graph.dim());
+        prev_alphas.push(alpha.clone());
         let mut constraints_by_path: Vec<Vec<Costs>> = vec![Vec::new(); paths.len()];
         loop {
             let mut sum_dif = 0.0;
             for i in 0..paths.len() {
+                let costs = self.path_costs(dijkstra, &paths[i]);
                 let result = self
                     .graph
                     .find_shortest_path(
@@ -204,23 +201,18 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
                             *paths[i].nodes.first().unwrap(),
                             *paths[i].nodes.last().unwrap(),
                         ],
-                        alpha,
+                        &alpha,
                     )
                     .unwrap();
-                let dif = costs_by_alpha(&paths[i].total_dimension_costs, &alpha)
+                let dif = costs_by_alpha(&costs, &alpha)
                     - costs_by_alpha(&result.total_dimension_costs, &alpha);
                 sum_dif += dif;
 
-                let mut cost_dif: Costs = [0.0; EDGE_COST_DIMENSION];
+                let mut cost_dif: Costs = vec![0.0; self.graph.dim()];
 
                 cost_dif
                     .iter_mut()
-                    .zip(
-                        paths[i]
-                            .total_dimension_costs
-                            .iter()
-                            .zip(result.total_dimension_costs.iter()),
-                    )
+                    .zip(costs.iter().zip(result.total_dimension_costs.iter()))
                     .for_each(|(c, (p, r))| *c = r - p);
 
                 self.lp.add_constraint(&cost_dif)?;
@@ -247,7 +239,7 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
                         return Ok((None, constraints_by_path));
                     }
                     alpha = pref;
-                    prev_alphas.push(alpha);
+                    prev_alphas.push(alpha.clone());
                 }
                 None => {
                     #[cfg(feature = "debug")]
@@ -265,16 +257,16 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
         path: &Path,
     ) -> MyResult<Preference> {
         self.lp.reset().expect("LP Process could not be reset");
-        let costs = path.total_dimension_costs;
+        let costs = &self.path_costs(dijkstra, path);
 
         let accuracy = 0.0001;
 
         let mut best_dif = f64::MAX;
-        let mut best_pref = EQUAL_WEIGHTS;
+        let mut best_pref = equal_weights(self.graph.dim());
 
         let mut prev_alphas: Vec<Preference> = Vec::new();
-        let mut alpha = EQUAL_WEIGHTS;
-        prev_alphas.push(alpha);
+        let mut alpha = equal_weights(self.graph.dim());
+        prev_alphas.push(alpha.clone());
         loop {
             let result = self
                 .graph
@@ -282,10 +274,10 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
                     dijkstra,
                     0,
                     &[*path.nodes.first().unwrap(), *path.nodes.last().unwrap()],
-                    alpha,
+                    &alpha,
                 )
                 .unwrap();
-            let dif = costs_by_alpha(&costs, &alpha)
+            let dif = costs_by_alpha(costs, &alpha)
                 - costs_by_alpha(&result.total_dimension_costs, &alpha);
 
             if dif - accuracy <= 0.0 {
@@ -294,10 +286,10 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
 
             if dif < best_dif {
                 best_dif = dif;
-                best_pref = alpha;
+                best_pref = alpha.clone();
             }
 
-            let mut cost_dif: Costs = [0.0; EDGE_COST_DIMENSION];
+            let mut cost_dif: Costs = vec![0.0; self.graph.dim()];
 
             cost_dif
                 .iter_mut()
@@ -314,7 +306,7 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
                         return Ok(best_pref);
                     }
                     alpha = pref;
-                    prev_alphas.push(alpha);
+                    prev_alphas.push(alpha.clone());
                 }
                 None => {
                     if best_dif < f64::MAX {
@@ -333,37 +325,27 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
         paths: &Vec<Path>,
     ) -> MyResult<Preference> {
         self.lp.reset().expect("LP Process could not be reset");
-        let mut sum_costs = [0.0; EDGE_COST_DIMENSION];
+        let mut sum_costs = vec![0.0; self.graph.dim()];
         for path in paths {
-            let costs = path.total_dimension_costs;
-            for i in 0..EDGE_COST_DIMENSION {
+            let costs = self.path_costs(dijkstra, path);
+            for i in 0..self.graph.dim() {
                 sum_costs[i] += costs[i];
             }
         }
         let accuracy = 0.0001;
+        let endpoints: Vec<_> = paths
+            .iter()
+            .map(|p| (*p.nodes.first().unwrap(), *p.nodes.last().unwrap()))
+            .collect();
 
         let mut best_dif = f64::MAX;
-        let mut best_pref = EQUAL_WEIGHTS;
+        let mut best_pref = equal_weights(self.graph.dim());
 
         let mut prev_alphas: Vec<Preference> = Vec::new();
-        let mut alpha = EQUAL_WEIGHTS;
-        prev_alphas.push(alpha);
+        let mut alpha = equal_weights(self.graph.dim());
+        prev_alphas.push(alpha.clone());
         loop {
-            let mut sum_optimal_costs = [0.0; EDGE_COST_DIMENSION];
-            for path in paths {
-                let result = self
-                    .graph
-                    .find_shortest_path(
-                        dijkstra,
-                        0,
-                        &[*path.nodes.first().unwrap(), *path.nodes.last().unwrap()],
-                        alpha,
-                    )
-                    .unwrap();
-                for i in 0..EDGE_COST_DIMENSION {
-                    sum_optimal_costs[i] += result.total_dimension_costs[i];
-                }
-            }
+            let sum_optimal_costs = self.sum_optimal_costs(dijkstra, &endpoints, &alpha);
 
             let dif =
                 costs_by_alpha(&sum_costs, &alpha) - costs_by_alpha(&sum_optimal_costs, &alpha);
@@ -374,10 +356,10 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
 
             if dif < best_dif {
                 best_dif = dif;
-                best_pref = alpha;
+                best_pref = alpha.clone();
             }
 
-            let mut cost_dif: Costs = [0.0; EDGE_COST_DIMENSION];
+            let mut cost_dif: Costs = vec![0.0; self.graph.dim()];
 
             cost_dif
                 .iter_mut()
@@ -394,7 +376,7 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
                         return Ok(best_pref);
                     }
                     alpha = pref;
-                    prev_alphas.push(alpha);
+                    prev_alphas.push(alpha.clone());
                 }
                 None => {
                     if best_dif < f64::MAX {
@@ -406,23 +388,137 @@ impl<'a, 'b> PreferenceEstimator<'a, 'b> {
             }
         }
     }
+
+    /// Costs of the edges `start..end` of `path` as `dijkstra` counts them,
+    /// that is including the costs of the turns it respects.
+    fn subpath_costs(&self, dijkstra: &Dijkstra, path: &Path, start: u32, end: u32) -> Costs {
+        let costs = path.get_subpath_costs(self.graph, start, end);
+        match dijkstra.turns() {
+            Some(turns) => add_edge_costs(
+                &costs,
+                &turns.path_costs(self.graph, &path.edges[start..end]),
+            ),
+            None => costs,
+        }
+    }
+
+    /// Costs of the whole `path` as `dijkstra` counts them, see
+    /// `subpath_costs`.
+    fn path_costs(&self, dijkstra: &Dijkstra, path: &Path) -> Costs {
+        match dijkstra.turns() {
+            Some(turns) => add_edge_costs(
+                &path.total_dimension_costs,
+                &turns.path_costs(self.graph, &path.edges),
+            ),
+            None => path.total_dimension_costs.clone(),
+        }
+    }
+
+    /// Sum of the costs of the optimal paths between all `endpoints` under
+    /// `alpha`. Shared sources and targets are only searched once with a
+    /// many-to-many query, unless `dijkstra` has to respect turns.
+    fn sum_optimal_costs(
+        &self,
+        dijkstra: &mut Dijkstra,
+        endpoints: &[(u32, u32)],
+        alpha: &[f64],
+    ) -> Costs {
+        let mut sum = vec![0.0; self.graph.dim()];
+        if dijkstra.respects_turns() {
+            for &(source, target) in endpoints {
+                let result = self
+                    .graph
+                    .find_shortest_path(dijkstra, 0, &[source, target], alpha)
+                    .unwrap();
+                sum = add_edge_costs(&sum, &result.total_dimension_costs);
+            }
+            return sum;
+        }
+
+        let unique = |nodes: Vec<u32>| {
+            let mut nodes = nodes;
+            nodes.sort_unstable();
+            nodes.dedup();
+            nodes
+        };
+        let sources = unique(endpoints.iter().map(|&(s, _)| s).collect());
+        let targets = unique(endpoints.iter().map(|&(_, t)| t).collect());
+        let matrix = many_to_many(self.graph, &sources, &targets, alpha);
+        for (source, target) in endpoints {
+            let i = sources.binary_search(source).unwrap();
+            let j = targets.binary_search(target).unwrap();
+            let costs = matrix.costs(i, j).expect("There must be a path");
+            sum = add_edge_costs(&sum, costs);
+        }
+        sum
+    }
 }
 
-use crate::helpers::{Costs, EQUAL_WEIGHTS};
+#[test]
+fn turn_costs_change_the_preference() {
+    use crate::graph::turns::Turns;
+    use crate::graph::{Edge, Node};
+
+    // The trajectory 0 -> 1 -> 2 against the direct edge 0 -> 2
+    let nodes = vec![Node::new(0, 0), Node::new(1, 0), Node::new(2, 0)];
+    let edges = vec![
+        Edge::new(0, 0, 1, vec![1.0, 0.0], None),
+        Edge::new(1, 1, 2, vec![1.0, 0.0], None),
+        Edge::new(2, 0, 2, vec![0.0, 3.0], None),
+    ];
+    let graph = Graph::new(nodes, edges);
+    let mut turns = Turns::new();
+    turns.set_costs(0, 1, vec![2.0, 0.0]);
+
+    let mut dijkstra = Dijkstra::new(&graph);
+    let path = graph
+        .find_shortest_path(&mut dijkstra, 0, &[0, 1, 2], &equal_weights(2))
+        .unwrap();
+    let mut lp = LpProcess::new(2).unwrap();
+    let mut estimator = PreferenceEstimator::new(&graph, &mut lp);
+
+    let pref = estimator
+        .calc_preference(&mut dijkstra, &path, 0, 2)
+        .unwrap();
+    assert_eq!(Some(equal_weights(2)), pref);
+
+    // With the turn, the trajectory costs [4, 0] and is only optimal if the
+    // first metric weighs at most 3/7
+    let mut dijkstra = Dijkstra::with_turns(&graph, &turns);
+    let pref = estimator
+        .calc_preference(&mut dijkstra, &path, 0, 2)
+        .unwrap()
+        .unwrap();
+    assert!(costs_by_alpha(&[4.0, 0.0], &pref) <= costs_by_alpha(&[0.0, 3.0], &pref) + 0.0001);
+    assert!(pref[0] < 0.5);
+}
+
+use crate::helpers::Costs;
 use crate::MyResult;
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::process::{Child, Command, Stdio};
 
 pub const F64_SIZE: usize = std::mem::size_of::<f64>();
-pub const BUFFER_SIZE: usize = F64_SIZE * EDGE_COST_DIMENSION;
-pub const OUTPUT_BUFFER_SIZE: usize = F64_SIZE * (EDGE_COST_DIMENSION + 1);
+
+/// Size of a constraint message for an LP over `dim` metrics.
+pub fn buffer_size(dim: usize) -> usize {
+    F64_SIZE * dim
+}
+
+/// Size of a solution message (preference plus delta) for an LP over
+/// `dim` metrics.
+pub fn output_buffer_size(dim: usize) -> usize {
+    F64_SIZE * (dim + 1)
+}
 
 pub struct LpProcess {
     lp: Child,
+    dim: usize,
 }
 
 impl LpProcess {
-    pub fn new() -> MyResult<LpProcess> {
+    /// Spawns an `lp_solver` process for preferences over `dim` metrics.
+    pub fn new(dim: usize) -> MyResult<LpProcess> {
         let mut path = std::env::current_exe().unwrap();
         path.pop();
         path.push("lp_solver");
@@ -435,17 +531,29 @@ impl LpProcess {
         }
 
         let lp = Command::new(&path)
+            .arg(dim.to_string())
             .stdout(Stdio::piped())
             .stdin(Stdio::piped())
             .spawn()?;
 
-        Ok(Self { lp })
+        Ok(Self { lp, dim })
     }
 
-    pub fn add_constraint(&mut self, costs: &Costs) -> MyResult<()> {
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn add_constraint(&mut self, costs: &[f64]) -> MyResult<()> {
+        if costs.len() != self.dim {
+            panic!(
+                "got wrong number of coefficients ({} instead of {})",
+                costs.len(),
+                self.dim
+            );
+        }
         let accuracy = 0.000005;
 
-        let mut norm_costs = [0.0; EDGE_COST_DIMENSION];
+        let mut norm_costs = vec![0.0; self.dim];
 
         costs.iter().zip(norm_costs.iter_mut()).for_each(|(c, n)| {
             if *c < accuracy && *c > -accuracy {
@@ -490,7 +598,7 @@ impl LpProcess {
         b.write_all(&[2u8])?;
         b.flush()?;
 
-        let mut buffer = [0u8; OUTPUT_BUFFER_SIZE];
+        let mut buffer = vec![0u8; output_buffer_size(self.dim)];
         let child_stdout = self.lp.stdout.as_mut().unwrap();
         let mut r = BufReader::new(child_stdout);
         let mut control_byte = [0u8; 1];
@@ -507,10 +615,7 @@ impl LpProcess {
                         f64::from_ne_bytes(copy_buff)
                     })
                     .collect();
-                let mut pref: Preference = [0.0; EDGE_COST_DIMENSION];
-                pref.iter_mut()
-                    .zip(result.iter().map(|r| r.max(0.0)))
-                    .for_each(|(p, r)| *p = r);
+                let pref: Preference = result[..self.dim].iter().map(|r| r.max(0.0)).collect();
                 Ok(Some((pref, *result.last().unwrap())))
             }
             1 => Ok(None),
@@ -521,7 +626,7 @@ impl LpProcess {
 
 #[test]
 fn test_strange_lp_behavior() {
-    let mut lp = LpProcess::new().unwrap();
+    let mut lp = LpProcess::new(4).unwrap();
     lp.add_constraint(&[-0.0638948999999998, -1.106574, 1.11022302462516e-16, 0.0])
         .unwrap();
 
@@ -545,22 +650,22 @@ fn test_calc_representative_preference() {
 
     let nodes = vec![Node::new(0, 0), Node::new(1, 0), Node::new(2, 0)];
     let edges = vec![
-        Edge::new(0, 0, 1, [0.0; EDGE_COST_DIMENSION], None),
-        Edge::new(1, 1, 2, [10.0; EDGE_COST_DIMENSION], None),
-        Edge::new(2, 0, 2, [0.0, 0.0, 0.0, 9.0], None),
-        // Edge::new(3, 0, 2, [0.0; EDGE_COST_DIMENSION], None),
-        // Edge::new(4, 0, 2, [0.0; EDGE_COST_DIMENSION], None),
+        Edge::new(0, 0, 1, vec![0.0; 4], None),
+        Edge::new(1, 1, 2, vec![10.0; 4], None),
+        Edge::new(2, 0, 2, vec![0.0, 0.0, 0.0, 9.0], None),
+        // Edge::new(3, 0, 2, vec![0.0; self.graph.dim()], None),
+        // Edge::new(4, 0, 2, vec![0.0; self.graph.dim()], None),
     ];
 
     let graph = Graph::new(nodes, edges);
     let mut dijkstra = Dijkstra::new(&graph);
 
-    let mut lp = LpProcess::new().unwrap();
+    let mut lp = LpProcess::new(4).unwrap();
 
     let mut estimator = PreferenceEstimator::new(&graph, &mut lp);
 
     let path = graph
-        .find_shortest_path(&mut dijkstra, 0, &[0, 1, 2], EQUAL_WEIGHTS)
+        .find_shortest_path(&mut dijkstra, 0, &[0, 1, 2], &equal_weights(4))
         .unwrap();
 
     let opt = estimator
@@ -573,7 +678,7 @@ fn test_calc_representative_preference() {
         .calc_representative_preference(&mut dijkstra, &path)
         .unwrap();
 
-    assert_eq!([0.0, 0.0, 0.0, 1.0], representative);
+    assert_eq!(vec![0.0, 0.0, 0.0, 1.0], representative);
 }
 
 #[test]
@@ -582,22 +687,22 @@ fn test_calc_mixed_representative_preference() {
 
     let nodes = vec![Node::new(0, 0), Node::new(1, 0), Node::new(2, 0)];
     let edges = vec![
-        Edge::new(0, 0, 1, [0.0; EDGE_COST_DIMENSION], None),
-        Edge::new(1, 1, 2, [10.0; EDGE_COST_DIMENSION], None),
-        Edge::new(2, 0, 2, [0.0, 0.0, 12.0, 7.0], None),
-        Edge::new(3, 0, 2, [0.0, 0.0, 8.0, 8.0], None),
-        Edge::new(4, 0, 2, [0.0, 0.0, 7.0, 12.0], None),
+        Edge::new(0, 0, 1, vec![0.0; 4], None),
+        Edge::new(1, 1, 2, vec![10.0; 4], None),
+        Edge::new(2, 0, 2, vec![0.0, 0.0, 12.0, 7.0], None),
+        Edge::new(3, 0, 2, vec![0.0, 0.0, 8.0, 8.0], None),
+        Edge::new(4, 0, 2, vec![0.0, 0.0, 7.0, 12.0], None),
     ];
 
     let graph = Graph::new(nodes, edges);
     let mut dijkstra = Dijkstra::new(&graph);
 
-    let mut lp = LpProcess::new().unwrap();
+    let mut lp = LpProcess::new(4).unwrap();
 
     let mut estimator = PreferenceEstimator::new(&graph, &mut lp);
 
     let path = graph
-        .find_shortest_path(&mut dijkstra, 0, &[0, 1, 2], EQUAL_WEIGHTS)
+        .find_shortest_path(&mut dijkstra, 0, &[0, 1, 2], &equal_weights(4))
         .unwrap();
 
     let opt = estimator
@@ -610,5 +715,5 @@ fn test_calc_mixed_representative_preference() {
         .calc_representative_preference(&mut dijkstra, &path)
         .unwrap();
 
-    assert_eq!([0.0, 0.0, 0.2, 0.8], representative);
+    assert_eq!(vec![0.0, 0.0, 0.2, 0.8], representative);
 }
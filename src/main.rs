@@ -5,6 +5,7 @@ use std::time::Instant;
 use preference_splitting::graph::dijkstra::Dijkstra;
 use preference_splitting::graph::path::Path;
 use preference_splitting::graph::trajectory_analysis::{SubPath, TrajectoryAnalysis};
+use preference_splitting::graph::turns::{read_turns, Turns};
 use preference_splitting::graph::{parse_minimal_graph_file, Graph};
 use preference_splitting::graphml::{read_graphml, AttributeType, GraphData};
 use preference_splitting::helpers::MyVec;
@@ -13,8 +14,8 @@ use preference_splitting::statistics::{
     ExperimentResults, NonOptSubPathsResult, SplittingStatistics,
 };
 use preference_splitting::trajectories::{check_trajectory, read_trajectories};
+use preference_splitting::MyError;
 use preference_splitting::MyResult;
-use preference_splitting::{MyError, EDGE_COST_DIMENSION};
 
 use chrono::prelude::*;
 use indicatif::{ProgressBar, ProgressStyle};
@@ -32,6 +33,16 @@ struct Opts {
     /// Number of threads to use
     #[structopt(short, long, default_value = "8")]
     threads: usize,
+    /// Turn restrictions and costs to respect in the searches
+    #[structopt(long)]
+    turns: Option<String>,
+    /// Forbid U-turns in the searches
+    #[structopt(long)]
+    no_u_turns: bool,
+    /// Precompute the unpacking of all shortcuts for faster path
+    /// reconstruction, at the cost of memory
+    #[structopt(long)]
+    unpack_table: bool,
 }
 
 fn run_experiment<'a, 'b>(
@@ -87,33 +98,42 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         graph_file,
         trajectory_file,
         threads,
+        turns,
+        no_u_turns,
+        unpack_table,
     } = Opts::from_args();
 
-    let GraphData {
-        graph,
-        edge_lookup,
-        keys,
-    } = if graphml {
+    let graph_data = if graphml {
         read_graphml(&graph_file)?
     } else {
         parse_minimal_graph_file(&graph_file)?
     };
+    let mut turns = turns.map(|f| read_turns(f, &graph_data)).transpose()?;
+    if no_u_turns {
+        turns.get_or_insert_with(Turns::new).forbid_u_turns = true;
+    }
+    let GraphData {
+        mut graph,
+        edge_lookup,
+        keys,
+    } = graph_data;
+    if unpack_table {
+        graph.build_unpacking_table();
+    }
 
     let mut trajectories = read_trajectories(&trajectory_file)?;
 
     let mut statistics: Vec<_> = trajectories.iter().map(SplittingStatistics::new).collect();
 
-    trajectories
-        .iter_mut()
-        .zip(statistics.iter_mut())
-        .for_each(|(t, s)| {
-            s.removed_self_loop_indices = t.filter_out_self_loops(&graph, &edge_lookup);
-        });
+    for (t, s) in trajectories.iter_mut().zip(statistics.iter_mut()) {
+        s.removed_self_loop_indices = t.filter_out_self_loops(&graph, &edge_lookup)?;
+    }
 
-    if trajectories
+    let valid = trajectories
         .iter()
-        .all(|t| check_trajectory(t, &graph, &edge_lookup))
-    {
+        .map(|t| check_trajectory(t, &graph, &edge_lookup))
+        .collect::<Result<Vec<_>, _>>()?;
+    if valid.into_iter().all(|v| v) {
         println!("all {} trajectories seem valid :-)", trajectories.len());
     } else {
         println!("There are invalid trajectories :-(");
@@ -135,6 +155,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut paths: Vec<_> = trajectories
         .into_iter()
         .map(|t| t.to_path(&graph, &edge_lookup))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
         .zip(statistics.into_iter())
         .collect();
 
@@ -147,8 +169,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     crossbeam::scope(|scope| {
         for chunk in paths.chunks_mut(items_per_thread) {
             (scope.spawn(|_| {
-                let mut d = Dijkstra::new(&graph);
-                let mut lp = LpProcess::new().unwrap();
+                let mut d = match &turns {
+                    Some(turns) => Dijkstra::with_turns(&graph, turns),
+                    None => Dijkstra::new(&graph),
+                };
+                let mut lp = LpProcess::new(graph.dim()).unwrap();
                 let mut counter = 0;
                 for (p, s) in chunk {
                     run_experiment(&graph, &mut d, &mut lp, p, s).expect("Something failed");
@@ -181,7 +206,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("writing results to \"{}\"", outfile_name);
 
-    let mut metrics = vec!["".to_owned(); EDGE_COST_DIMENSION];
+    let mut metrics = vec!["".to_owned(); graph.dim()];
 
     for key in keys.values() {
         if let AttributeType::Double(idx) = key.attribute_type {
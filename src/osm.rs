@@ -0,0 +1,393 @@
+use crate::graph::{Coordinates, Edge, Graph, Node};
+use crate::graphml::{EdgeLookup, GraphData, GraphmlAttribute};
+use crate::helpers::haversine;
+use crate::MyResult;
+
+use geojson::{Geometry, Value};
+use osmpbfreader::{OsmObj, OsmPbfReader, Tags};
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Highway types imported if no other filter is given.
+pub const DEFAULT_HIGHWAYS: &[&str] = &[
+    "motorway",
+    "motorway_link",
+    "trunk",
+    "trunk_link",
+    "primary",
+    "primary_link",
+    "secondary",
+    "secondary_link",
+    "tertiary",
+    "tertiary_link",
+    "unclassified",
+    "residential",
+    "living_street",
+];
+
+/// Metrics of imported graphs in the order of the edge costs.
+pub const METRICS: &[&str] = &["length", "travel_time", "unit_distance"];
+
+pub struct OsmImport {
+    pub graph_data: GraphData,
+    /// Geometry per edge name, as read by `read_geojson_map`
+    pub geometries: HashMap<i64, Geometry>,
+}
+
+/// Road as read from the PBF file. Oneway roads against their node order
+/// are already reversed.
+struct Road {
+    nodes: Vec<i64>,
+    oneway: bool,
+    speed: f64,
+}
+
+/// Part of one or more roads between two junctions.
+struct Segment {
+    nodes: Vec<i64>,
+    oneway: bool,
+    length: f64,
+    travel_time: f64,
+}
+
+/// Imports the roads with a `highway` tag in `highways` from a PBF file.
+///
+/// Graph nodes are junctions and dead ends; chains of nodes in between are
+/// merged into single edges that keep their geometry. Edges carry the
+/// metrics in `METRICS` (meters, seconds and 1 per edge). Edges are named by
+/// their id, which is also the key of their geometry.
+pub fn import_pbf<P: AsRef<Path>>(path: P, highways: &[&str]) -> MyResult<OsmImport> {
+    let file = std::fs::File::open(path)?;
+    let mut pbf = OsmPbfReader::new(file);
+
+    println!("Collecting roads from PBF file");
+    let mut roads = Vec::new();
+    for obj in pbf.iter() {
+        if let OsmObj::Way(way) = obj? {
+            let highway = match way.tags.get("highway") {
+                Some(highway) if highways.contains(&highway.as_str()) => highway,
+                _ => continue,
+            };
+            let mut nodes: Vec<_> = way.nodes.iter().map(|n| n.0).collect();
+            let oneway = match way.tags.get("oneway").map(String::as_str) {
+                Some("yes") | Some("true") | Some("1") => true,
+                Some("-1") | Some("reverse") => {
+                    nodes.reverse();
+                    true
+                }
+                Some("no") | Some("false") | Some("0") => false,
+                _ => highway == "motorway" || way.tags.contains("junction", "roundabout"),
+            };
+            let speed = parse_maxspeed(&way.tags).unwrap_or_else(|| default_speed(highway));
+            roads.push(Road {
+                nodes,
+                oneway,
+                speed,
+            });
+        }
+    }
+    println!("found {} roads", roads.len());
+
+    let needed: HashSet<_> = roads.iter().flat_map(|r| r.nodes.iter().copied()).collect();
+
+    println!("Collecting node coordinates from PBF file");
+    pbf.rewind()?;
+    let mut coordinates = HashMap::with_capacity(needed.len());
+    for obj in pbf.iter() {
+        if let OsmObj::Node(n) = obj? {
+            if needed.contains(&n.id.0) {
+                coordinates.insert(n.id.0, (n.lat(), n.lon()));
+            }
+        }
+    }
+
+    // Roads referencing nodes outside of the extract are cut there
+    let roads = roads
+        .into_iter()
+        .flat_map(|r| {
+            let Road {
+                nodes,
+                oneway,
+                speed,
+            } = r;
+            nodes
+                .split(|n| !coordinates.contains_key(n))
+                .filter(|part| part.len() > 1)
+                .map(|part| Road {
+                    nodes: part.to_vec(),
+                    oneway,
+                    speed,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    Ok(build_graph(roads, &coordinates))
+}
+
+fn build_graph(roads: Vec<Road>, coordinates: &HashMap<i64, (f64, f64)>) -> OsmImport {
+    let mut segments = split_at_junctions(roads, coordinates);
+    merge_chains(&mut segments);
+
+    let mut node_ids = HashMap::new();
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    let mut edge_lookup = EdgeLookup::new();
+    let mut geometries = HashMap::new();
+
+    for s in segments.into_iter().flatten() {
+        let mut node_id = |osm_id: i64| {
+            *node_ids.entry(osm_id).or_insert_with(|| {
+                let (lat, lon) = coordinates[&osm_id];
+                let mut node = Node::new(nodes.len() as u32, 0);
+                node.coordinates = Some(Coordinates::new(lat, lon));
+                nodes.push(node);
+                nodes.len() as u32 - 1
+            })
+        };
+        let first = node_id(s.nodes[0]);
+        let last = node_id(*s.nodes.last().unwrap());
+        let costs = vec![s.length, s.travel_time, 1.0];
+        let line: Vec<_> = s
+            .nodes
+            .iter()
+            .map(|n| {
+                let (lat, lon) = coordinates[n];
+                vec![lon, lat]
+            })
+            .collect();
+
+        let mut add_edge = |source, target, line: Vec<Vec<f64>>| {
+            let id = edges.len() as u32;
+            let mut edge = Edge::new(id, source, target, costs.clone(), None);
+            edge.geometry = Some(line.iter().map(|p| Coordinates::new(p[1], p[0])).collect());
+            edges.push(edge);
+            edge_lookup.insert(i64::from(id), id);
+            geometries.insert(i64::from(id), Geometry::new(Value::LineString(line)));
+        };
+        if !s.oneway {
+            add_edge(last, first, line.iter().rev().cloned().collect());
+        }
+        add_edge(first, last, line);
+    }
+
+    println!(
+        "Imported graph with {} nodes and {} edges",
+        nodes.len(),
+        edges.len()
+    );
+
+    let keys = METRICS
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.to_string(), GraphmlAttribute::metric(n, i)))
+        .collect();
+
+    OsmImport {
+        graph_data: GraphData {
+            graph: Graph::new(nodes, edges),
+            edge_lookup,
+            keys,
+        },
+        geometries,
+    }
+}
+
+/// Splits roads at nodes shared with other roads (or visited twice).
+fn split_at_junctions(
+    roads: Vec<Road>,
+    coordinates: &HashMap<i64, (f64, f64)>,
+) -> Vec<Option<Segment>> {
+    let mut usage: HashMap<i64, u32> = HashMap::new();
+    for r in &roads {
+        for n in &r.nodes {
+            *usage.entry(*n).or_default() += 1;
+        }
+    }
+
+    let mut segments = Vec::new();
+    for r in roads {
+        let mut start = 0;
+        for i in 1..r.nodes.len() {
+            if i == r.nodes.len() - 1 || usage[&r.nodes[i]] > 1 {
+                let nodes = r.nodes[start..=i].to_vec();
+                let length = nodes
+                    .windows(2)
+                    .map(|w| {
+                        let (from_lat, from_lng) = coordinates[&w[0]];
+                        let (to_lat, to_lng) = coordinates[&w[1]];
+                        haversine(from_lat, from_lng, to_lat, to_lng)
+                    })
+                    .sum();
+                segments.push(Some(Segment {
+                    nodes,
+                    oneway: r.oneway,
+                    length,
+                    travel_time: length / (r.speed / 3.6),
+                }));
+                start = i;
+            }
+        }
+    }
+    segments
+}
+
+/// Merges segments meeting at nodes without any other connection.
+fn merge_chains(segments: &mut [Option<Segment>]) {
+    let mut incident: HashMap<i64, Vec<usize>> = HashMap::new();
+    for (i, s) in segments.iter().enumerate() {
+        let s = s.as_ref().unwrap();
+        incident.entry(s.nodes[0]).or_default().push(i);
+        incident
+            .entry(*s.nodes.last().unwrap())
+            .or_default()
+            .push(i);
+    }
+
+    let candidates: Vec<_> = incident
+        .iter()
+        .filter(|(_, segs)| segs.len() == 2)
+        .map(|(&n, _)| n)
+        .collect();
+
+    for node in candidates {
+        let (a, b) = match incident[&node].as_slice() {
+            &[a, b] if a != b => (a, b),
+            _ => continue,
+        };
+        let (first, second) = {
+            let sa = segments[a].as_ref().unwrap();
+            let sb = segments[b].as_ref().unwrap();
+            if sa.oneway != sb.oneway {
+                continue;
+            }
+            let ends_at = |s: &Segment| *s.nodes.last().unwrap() == node;
+            if ends_at(sa) && !ends_at(sb) {
+                (a, b)
+            } else if ends_at(sb) && !ends_at(sa) {
+                (b, a)
+            } else if sa.oneway {
+                // oneway roads in opposing directions
+                continue;
+            } else {
+                (a, b)
+            }
+        };
+
+        let mut merged = segments[first].take().unwrap();
+        let mut next = segments[second].take().unwrap();
+        if *merged.nodes.last().unwrap() != node {
+            merged.nodes.reverse();
+        }
+        if next.nodes[0] != node {
+            next.nodes.reverse();
+        }
+        merged.nodes.extend_from_slice(&next.nodes[1..]);
+        merged.length += next.length;
+        merged.travel_time += next.travel_time;
+
+        let other_end = *merged.nodes.last().unwrap();
+        for s in incident.get_mut(&other_end).unwrap().iter_mut() {
+            if *s == second {
+                *s = first;
+            }
+        }
+        incident.get_mut(&node).unwrap().clear();
+        segments[first] = Some(merged);
+    }
+}
+
+/// Speed limit in km/h given by the `maxspeed` tag.
+fn parse_maxspeed(tags: &Tags) -> Option<f64> {
+    let maxspeed = tags.get("maxspeed")?;
+    let mut tokens = maxspeed.split_whitespace();
+    let speed: f64 = tokens.next()?.parse().ok()?;
+    match tokens.next() {
+        None => Some(speed),
+        Some("mph") => Some(speed * 1.609_344),
+        Some(_) => None,
+    }
+}
+
+/// Typical speed in km/h for roads without a usable `maxspeed` tag.
+fn default_speed(highway: &str) -> f64 {
+    match highway {
+        "motorway" => 120.0,
+        "trunk" => 90.0,
+        "primary" => 80.0,
+        "secondary" => 70.0,
+        "tertiary" => 60.0,
+        "motorway_link" | "trunk_link" => 60.0,
+        "primary_link" | "secondary_link" | "tertiary_link" => 50.0,
+        "unclassified" | "residential" => 50.0,
+        "living_street" => 10.0,
+        _ => 30.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn road(nodes: &[i64], oneway: bool) -> Road {
+        Road {
+            nodes: nodes.to_vec(),
+            oneway,
+            speed: 36.0,
+        }
+    }
+
+    #[test]
+    fn chains_are_merged() {
+        let lon = |i: i64| 10.0 + 0.001 * i as f64;
+        let coordinates = (0..6).map(|i| (i, (55.0, lon(i)))).collect();
+        // 0 - 1 - 2 - 3 as two roads, with a oneway side road 2 -> 4 -> 5
+        let roads = vec![
+            road(&[0, 1, 2], false),
+            road(&[2, 3], false),
+            road(&[2, 4], true),
+            road(&[4, 5], true),
+        ];
+        let OsmImport {
+            graph_data,
+            geometries,
+        } = build_graph(roads, &coordinates);
+        let graph = &graph_data.graph;
+
+        // nodes 0, 2, 3 and 5 remain
+        assert_eq!(graph.nodes.len(), 4);
+        // 0 <-> 2 and 2 <-> 3 in both directions, 2 -> 5 only one way
+        assert_eq!(graph.edges.len(), 5);
+        assert_eq!(geometries.len(), 5);
+
+        let lines: Vec<_> = graph
+            .edges
+            .iter()
+            .map(|e| match &geometries[&i64::from(e.id)].value {
+                Value::LineString(line) => line.iter().map(|p| p[0]).collect::<Vec<_>>(),
+                _ => panic!("edge geometry is no line string"),
+            })
+            .collect();
+        assert!(lines.contains(&vec![lon(0), lon(1), lon(2)]));
+        assert!(lines.contains(&vec![lon(2), lon(1), lon(0)]));
+        assert!(!lines.contains(&vec![lon(5), lon(4), lon(2)]));
+        for e in graph.edges.iter() {
+            let geometry = e.geometry.as_ref().unwrap();
+            assert_eq!(Some(geometry[0]), graph.nodes[e.source_id].coordinates);
+            assert_eq!(
+                geometry.last().copied(),
+                graph.nodes[e.target_id].coordinates
+            );
+        }
+
+        let oneway = &graph.edges[lines
+            .iter()
+            .position(|l| l == &vec![lon(2), lon(4), lon(5)])
+            .unwrap() as u32];
+        let length = haversine(55.0, lon(2), 55.0, lon(4)) + haversine(55.0, lon(4), 55.0, lon(5));
+        assert!((oneway.edge_costs[0] - length).abs() < 0.001);
+        assert!((oneway.edge_costs[1] - length / 10.0).abs() < 0.001);
+        assert_eq!(oneway.edge_costs[2], 1.0);
+    }
+}
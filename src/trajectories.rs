@@ -3,14 +3,14 @@ use crate::graph::{
     dijkstra::{self, Dijkstra},
     Graph,
 };
-use crate::graphml::EdgeLookup;
-use crate::helpers::{randomized_preference, MyVec, EQUAL_WEIGHTS};
+use crate::graphml::{EdgeLookup, EdgeName};
+use crate::helpers::{equal_weights, randomized_preference, MyVec};
+use crate::MyError;
 
 use serde::{Deserialize, Serialize};
 use serde_json::from_reader;
 
 use rand::prelude::ThreadRng;
-use std::{collections::HashMap, string::ToString};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Trajectory {
@@ -19,28 +19,26 @@ pub struct Trajectory {
     pub path: MyVec<i64>,
 }
 
-pub fn check_trajectory(tra: &Trajectory, graph: &Graph, edge_lookup: &EdgeLookup) -> bool {
-    tra.path.windows(2).all(|window| {
-        let e0_idx = edge_lookup
-            .get(&window[0].to_string())
-            .unwrap_or_else(|| panic!("Could not find edge {}", window[0]));
-        let e1_idx = edge_lookup
-            .get(&window[1].to_string())
-            .unwrap_or_else(|| panic!("Could not find edge {}", window[1]));
-
-        let edge0 = &graph.edges[*e0_idx];
-        let edge1 = &graph.edges[*e1_idx];
-
-        let is_connected = edge0.target_id == edge1.source_id;
+/// Checks that consecutive edges of `tra` are connected. Edges missing from
+/// `edge_lookup` are an error.
+pub fn check_trajectory(
+    tra: &Trajectory,
+    graph: &Graph,
+    edge_lookup: &EdgeLookup,
+) -> Result<bool, MyError> {
+    for window in tra.path.windows(2) {
+        let edge0 = &graph.edges[tra.edge_index(window[0], edge_lookup)?];
+        let edge1 = &graph.edges[tra.edge_index(window[1], edge_lookup)?];
 
-        if !is_connected {
+        if edge0.target_id != edge1.source_id {
             println!(
                 "trip {:?} is not connected between edges {} and {}",
                 tra.trip_id, window[0], window[1]
             );
+            return Ok(false);
         }
-        is_connected
-    })
+    }
+    Ok(true)
 }
 
 pub fn read_trajectories<P: AsRef<std::path::Path>>(
@@ -53,13 +51,16 @@ pub fn read_trajectories<P: AsRef<std::path::Path>>(
 }
 
 impl Trajectory {
-    pub fn to_path(&self, graph: &Graph, edge_lookup: &EdgeLookup) -> Path {
+    pub fn to_path(&self, graph: &Graph, edge_lookup: &EdgeLookup) -> Result<Path, MyError> {
+        if self.path.is_empty() {
+            return Err(self.error("trajectory has no edges".to_owned()));
+        }
         let id = self.trip_id.clone();
-        let edges: Vec<u32> = self
+        let edges = self
             .path
             .iter()
-            .map(|id| edge_lookup[&id.to_string()])
-            .collect();
+            .map(|&id| self.edge_index(id, edge_lookup))
+            .collect::<Result<Vec<u32>, _>>()?;
 
         let first_node = edges.iter().take(1).map(|e| &graph.edges[*e].source_id);
         let rest_nodes = edges.iter().map(|e| &graph.edges[*e].target_id);
@@ -67,7 +68,7 @@ impl Trajectory {
         let nodes: Vec<u32> = first_node.chain(rest_nodes).copied().collect();
 
         let algo_split = None;
-        let total_dimension_costs = [0.0; super::EDGE_COST_DIMENSION];
+        let total_dimension_costs = vec![0.0; graph.dim()];
 
         let user_split = PathSplit {
             cuts: MyVec::new(),
@@ -87,42 +88,61 @@ impl Trajectory {
 
         path.total_dimension_costs = path.get_subpath_costs(graph, 0, node_count as u32 - 1);
 
-        path
+        Ok(path)
     }
 
-    pub fn filter_out_self_loops(&mut self, graph: &Graph, edge_lookup: &EdgeLookup) -> MyVec<u32> {
-        let (normal, self_loops): (Vec<_>, Vec<_>) =
-            self.path.iter().enumerate().partition(|(_, e)| {
-                let e_idx = *edge_lookup
-                    .get(&e.to_string())
-                    .unwrap_or_else(|| panic!("can not find edge {}", e));
-                let edge = &graph.edges[e_idx];
-                edge.source_id != edge.target_id
-            });
-
-        let indices = MyVec(self_loops.into_iter().map(|(i, _)| i as u32).collect());
-        self.path = MyVec(normal.into_iter().map(|(_, e)| e).copied().collect());
+    pub fn filter_out_self_loops(
+        &mut self,
+        graph: &Graph,
+        edge_lookup: &EdgeLookup,
+    ) -> Result<MyVec<u32>, MyError> {
+        let mut normal = Vec::new();
+        let mut self_loops = MyVec::new();
+        for (i, &e) in self.path.iter().enumerate() {
+            let edge = &graph.edges[self.edge_index(e, edge_lookup)?];
+            if edge.source_id != edge.target_id {
+                normal.push(e);
+            } else {
+                self_loops.push(i as u32);
+            }
+        }
+        self.path = MyVec(normal);
 
-        indices
+        Ok(self_loops)
     }
 
-    pub fn from_path(path: &Path, edge_lookup: &EdgeLookup) -> Trajectory {
-        let reverse_lookup: HashMap<_, _> = edge_lookup.iter().map(|(k, v)| (v, k)).collect();
-
+    pub fn from_path(path: &Path, edge_lookup: &EdgeLookup) -> Result<Trajectory, MyError> {
         let edges = path
             .edges
             .iter()
-            .map(|id| {
-                reverse_lookup[&(*id as u32)]
-                    .parse()
-                    .unwrap_or_else(|_| panic!("could not map {} back to exteranl id", id))
+            .map(|&id| {
+                edge_lookup
+                    .name(id)
+                    .and_then(EdgeName::as_number)
+                    .ok_or_else(|| MyError::InvalidTrajectory {
+                        trip_id: format!("{:?}", path.id),
+                        reason: format!("could not map edge {} back to external id", id),
+                    })
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
-        Trajectory {
+        Ok(Trajectory {
             trip_id: path.id.clone(),
             vehicle_id: -1,
             path: MyVec(edges),
+        })
+    }
+
+    fn edge_index(&self, edge: i64, edge_lookup: &EdgeLookup) -> Result<u32, MyError> {
+        edge_lookup
+            .get_number(edge)
+            .ok_or_else(|| self.error(format!("unknown edge {}", edge)))
+    }
+
+    fn error(&self, reason: String) -> MyError {
+        MyError::InvalidTrajectory {
+            trip_id: format!("{:?}", self.trip_id),
+            reason,
         }
     }
 }
@@ -137,22 +157,23 @@ pub fn create_randomwalk_trajectory(
     let mut cur_node = source;
     let mut path = MyVec::new();
 
-    let _ = d.run(cur_node, target, EQUAL_WEIGHTS)?;
+    let _ = d.run(cur_node, target, &equal_weights(graph.dim()))?;
 
     while cur_node != target {
-        let alpha = randomized_preference(rng);
+        let alpha = randomized_preference(rng, graph.dim());
         let tmp_path =
-            dijkstra::find_path(d, &[cur_node, target], alpha).expect("There must be a path");
+            dijkstra::find_path(d, &[cur_node, target], &alpha).expect("There must be a path");
 
-        let edges = tmp_path
+        let first_edge = tmp_path
             .edges
             .iter()
             .flat_map(|e| &e.0)
-            .flat_map(|&e| graph.unpack_edge(e))
-            .collect::<Vec<_>>();
+            .flat_map(|&e| graph.unpacked_edges(e))
+            .next()
+            .expect("There must be an edge");
 
-        cur_node = graph.edges[edges[0]].target_id;
-        path.push(edges[0]);
+        cur_node = graph.edges[first_edge].target_id;
+        path.push(first_edge);
     }
 
     let path = path.iter().map(|&i| i as i64).collect::<Vec<_>>().into();
@@ -163,3 +184,30 @@ pub fn create_randomwalk_trajectory(
         path,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::parse_minimal_graph_file;
+    use crate::graphml::GraphData;
+
+    #[test]
+    fn unknown_edges_are_errors() {
+        let graph_data = parse_minimal_graph_file("./src/test_graphs/minimalTestGraph").unwrap();
+        let GraphData {
+            graph, edge_lookup, ..
+        } = &graph_data;
+        let trajectory = Trajectory {
+            trip_id: vec![(Some(7), 0)],
+            vehicle_id: 1,
+            path: MyVec(vec![100, 9999]),
+        };
+
+        let error = trajectory.to_path(graph, edge_lookup).err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Invalid trajectory [(Some(7), 0)]: unknown edge 9999"
+        );
+        assert!(check_trajectory(&trajectory, graph, edge_lookup).is_err());
+    }
+}